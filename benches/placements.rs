@@ -0,0 +1,77 @@
+//! Measures how many placements the move generator evaluates per second,
+//! with collisions checked on the color matrix and on the bitboard.
+//!
+//! Run with `cargo bench --bench placements`.
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use tetris::app::{reachable_placements, Occupancy, TetrisGrid, Tetromino, TetrominoKind};
+use tetris::assets::TetrisColor;
+
+const NB_COLUMNS: u32 = 10;
+const NB_ROWS: u32 = 22;
+const NB_BOARDS: usize = 64;
+/// Height of the random garbage at the bottom of each board.
+const GARBAGE_HEIGHT: usize = 8;
+const DURATION: Duration = Duration::from_secs(3);
+
+const KINDS: [TetrominoKind; 7] = [
+    TetrominoKind::I,
+    TetrominoKind::O,
+    TetrominoKind::T,
+    TetrominoKind::S,
+    TetrominoKind::Z,
+    TetrominoKind::J,
+    TetrominoKind::L,
+];
+
+/// Returns boards whose bottom rows are randomly filled, each row having at least one hole.
+fn random_boards() -> Vec<TetrisGrid> {
+    let mut rng = Pcg32::seed_from_u64(0);
+    (0..NB_BOARDS)
+        .map(|_| {
+            let mut grid = TetrisGrid::new(0.0, 0.0, NB_COLUMNS, NB_ROWS);
+            for y in NB_ROWS as usize - GARBAGE_HEIGHT..NB_ROWS as usize {
+                let hole = rng.gen_range(0..NB_COLUMNS as usize);
+                for x in (0..NB_COLUMNS as usize).filter(|&x| x != hole) {
+                    if rng.gen_bool(0.7) {
+                        grid.set_cell(x, y, Some(TetrisColor::Grey));
+                    }
+                }
+            }
+            grid
+        })
+        .collect()
+}
+
+/// Generates the placements of every kind of Tetromino on every board until `DURATION` is elapsed.
+/// Returns the number of placements generated per second.
+fn placements_per_second<G: Occupancy + ?Sized>(boards: &[&G]) -> f64 {
+    let start = Instant::now();
+    let mut nb_placements = 0;
+    while start.elapsed() < DURATION {
+        for board in boards {
+            for kind in KINDS {
+                let tetromino = Tetromino::new_unchecked(kind);
+                nb_placements += black_box(reachable_placements(*board, &tetromino)).len();
+            }
+        }
+    }
+    nb_placements as f64 / start.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let grids = random_boards();
+
+    let matrices: Vec<_> = grids.iter().map(|grid| grid.rows()).collect();
+    let matrix = placements_per_second(&matrices);
+    println!("color matrix: {matrix:>12.0} placements/s");
+
+    let bitboards: Vec<_> = grids.iter().map(|grid| grid.bitboard()).collect();
+    let bitboard = placements_per_second(&bitboards);
+    println!(
+        "bitboard:     {bitboard:>12.0} placements/s ({:.2}x)",
+        bitboard / matrix
+    );
+}
@@ -1,20 +1,48 @@
 //! Defines the app that handles the players, their interactions and the changes of views, settings and number of players.
+mod controller;
+mod editor;
+mod fumen;
+mod game_mode;
+mod launch;
+mod opener;
 mod player;
+mod practice;
+mod profiles;
+mod puzzle;
+mod records;
 mod remote;
 mod render_app;
+mod results;
+mod stats;
 mod update_app;
 
-use self::player::LocalPlayer;
-pub use self::player::PlayerScreen;
-use self::remote::RemotePlayer;
+pub use self::game_mode::GameMode;
+pub use self::launch::Launch;
+pub use self::player::{
+    finesse, lines_of_garbage, reachable_placements, Bitboard, ClearedLines, LocalPlayer, Modifier,
+    Modifiers, Move, Occupancy, PcProblem, PcStep, Placement, PlayerScreen, Randomizer,
+    RotationSystem, Rules, Speed, TSpin, TetrisGrid, Tetromino, TetrominoKind,
+};
+pub use self::stats::Stat;
+use self::{
+    controller::Controllers,
+    editor::Editor,
+    game_mode::MasterProgress,
+    opener::{Opener, Target},
+    practice::PracticeAction,
+    profiles::Profile,
+    puzzle::Puzzle,
+    records::{Records, ZenRecord},
+    remote::RemotePlayer,
+    results::{Outcome, Results, ResultsPage},
+};
+use crate::assets::Assets;
 use crate::ui::{
-    interactive_widget_manager::{InteractiveWidgetManager, SettingsType},
+    interactive_widget_manager::{ButtonType, InteractiveWidgetManager, SettingsType},
     text::Text,
 };
-use crate::Assets;
 use crate::{app::remote::MessageType, PlayerConfig};
-use crate::{once, settings::*};
-use local_ip_address::local_ip;
+use crate::{input::Input, once, settings::*};
 use opengl_graphics::{GlGraphics, OpenGL};
 use piston::MouseButton;
 use piston_window::Key;
@@ -46,6 +74,10 @@ pub enum ViewState {
     Local,
     TwoLocal,
     Remote,
+    /// Editor of a position to play from, for a single player.
+    Editor,
+    /// Leaderboards and match history, browsed from the main menu.
+    Results,
 }
 
 impl ViewState {
@@ -76,12 +108,62 @@ pub struct App<'a> {
     restart_text: Text,
     pause_text: Text,
     timer_text: Text,
+    /// Progress in the game mode: last split time of a sprint, level of a marathon.
+    mode_text: Text,
+    /// Finesse faults of the single-player game, under the progress in the game mode.
+    finesse_text: Text,
+    /// Stats of LIVE_STATS for the single-player game, under the grid.
+    stats_text: Text,
+    /// Results shown over the grid when the goal of the game mode is reached.
+    finish_texts: Vec<Text>,
+    game_mode: GameMode,
+    /// Changes to the single-player game chosen on top of the game mode.
+    modifiers: Modifiers,
+    /// Times at which every SPRINT_SPLIT_LINES lines were cleared during the sprint.
+    splits: Vec<f64>,
+    /// Rows of garbage added to the grid since the start of the dig race.
+    garbage_rows: u64,
+    /// Level and hidden score of the master being played.
+    master: MasterProgress,
+    /// Totals of the zen sessions before the current one.
+    zen_start: ZenRecord,
+    /// Points scored in the classic game being played.
+    classic_points: u64,
+    /// Puzzles of all the packs, chosen with the index of [GameMode::Puzzle].
+    puzzles: Vec<Puzzle>,
+    /// Setups of the opener trainer, chosen with the index of [GameMode::Opener].
+    openers: Vec<Opener>,
+    /// Position set up in the editor, played from with [ViewState::Editor].
+    editor: Editor,
+    /// Setups of the opener being built, as it is and mirrored, which the placements follow so far.
+    opener_setups: Vec<Vec<Target>>,
+    records: Records,
+    /// Leaderboards of the game modes and match history.
+    results: Results,
+    /// Page of the results shown by [ViewState::Results], kept until it's opened again.
+    results_page: ResultsPage,
+    /// Lines of the page of the results.
+    results_texts: Vec<Text>,
+    /// Profiles saved by the players, sorted by name.
+    profiles: Vec<Profile>,
+    /// Indices of the profiles of the left and right seats, None for a guest.
+    seats: [Option<usize>; 2],
+    /// Outcome of the last change of the profiles, under the title of the settings.
+    settings_text: Text,
+    /// Why the room can't be created, under the title of the room.
+    room_text: Text,
     pub cursor_position: [f64; 2],
     widget_manager: Vec<InteractiveWidgetManager>,
     keybindings_manager: Vec<Keybindings>,
     settings_manager: Settings,
     is_synchronized: bool,
     is_host: bool,
+    /// Seed of every game, given on the command line, a random one being drawn for each game otherwise.
+    seed: Option<u64>,
+    /// Address on which a hosted room listens, given on the command line, the local IP and HOST_PORT otherwise.
+    host_address: Option<String>,
+    /// Directions held on the controllers.
+    controllers: Controllers,
 }
 
 impl App<'_> {
@@ -101,8 +183,9 @@ impl App<'_> {
 
         let assets = Assets::new(assets_folder);
         let settings_manager = Settings::new(seed, &player_config);
+        let (profiles, seats) = profiles::load_profiles();
 
-        App {
+        let mut app = App {
             gl: GlGraphics::new(gl_version),
             local_players: players,
             remote_player: rem_players,
@@ -114,28 +197,81 @@ impl App<'_> {
                 DEFAULT_FONT_SIZE,
                 DEFAULT_WINDOW_WIDTH as f64 * 27.0 / 65.0,
                 DEFAULT_TITLE_Y,
-                TEXT_COLOR,
+                *TEXT_COLOR,
             ),
             restart_text: Text::new(
                 "Press R to (re)start",
                 (DEFAULT_FONT_SIZE * 22) / 16,
                 DEFAULT_WINDOW_WIDTH as f64 / 2.0,
                 DEFAULT_TITLE_Y,
-                TEXT_COLOR,
+                *TEXT_COLOR,
             ),
             timer_text: Text::new(
                 "Elapsed: 0.0s",
                 DEFAULT_FONT_SIZE,
                 DEFAULT_GRID_X - 4.0 * BLOCK_SIZE,
                 DEFAULT_SCORE_TEXT_Y + 1.5 * BLOCK_SIZE,
-                TEXT_COLOR,
+                *TEXT_COLOR,
+            ),
+            mode_text: Text::new(
+                "",
+                DEFAULT_FONT_SIZE,
+                DEFAULT_GRID_X - 4.0 * BLOCK_SIZE,
+                DEFAULT_SCORE_TEXT_Y + 3.0 * BLOCK_SIZE,
+                *TEXT_COLOR,
+            ),
+            finesse_text: Text::new(
+                "",
+                DEFAULT_FONT_SIZE,
+                DEFAULT_GRID_X - 4.0 * BLOCK_SIZE,
+                DEFAULT_SCORE_TEXT_Y + 4.5 * BLOCK_SIZE,
+                *TEXT_COLOR,
+            ),
+            stats_text: Text::new(
+                "",
+                DEFAULT_FONT_SIZE,
+                DEFAULT_GRID_X + NB_COLUMNS as f64 * BLOCK_SIZE / 2.0,
+                DEFAULT_GRID_Y + (NB_ROWS as f64 + 1.5) * BLOCK_SIZE,
+                *TEXT_COLOR,
             ),
             pause_text: Text::new(
                 "Press P to resume",
                 (DEFAULT_FONT_SIZE * 22) / 16,
                 DEFAULT_WINDOW_WIDTH as f64 / 2.0,
                 DEFAULT_TITLE_Y,
-                TEXT_COLOR,
+                *TEXT_COLOR,
+            ),
+            finish_texts: vec![],
+            game_mode: GameMode::Endless,
+            modifiers: Modifiers::default(),
+            splits: vec![],
+            garbage_rows: 0,
+            master: MasterProgress::new(),
+            zen_start: ZenRecord::default(),
+            classic_points: 0,
+            puzzles: puzzle::load_puzzles(),
+            openers: opener::load_openers(),
+            editor: Editor::new(),
+            opener_setups: vec![],
+            records: Records::load(),
+            results: Results::load(),
+            results_page: ResultsPage::Leaderboard(0),
+            results_texts: vec![],
+            profiles,
+            seats,
+            settings_text: Text::new(
+                "",
+                DEFAULT_FONT_SIZE,
+                DEFAULT_WINDOW_WIDTH as f64 / 2.0,
+                DEFAULT_TITLE_Y + 1.5 * BLOCK_SIZE,
+                *TEXT_COLOR,
+            ),
+            room_text: Text::new(
+                "",
+                DEFAULT_FONT_SIZE,
+                DEFAULT_WINDOW_WIDTH as f64 / 2.0,
+                DEFAULT_TITLE_Y + 1.5 * BLOCK_SIZE,
+                *TEXT_COLOR,
             ),
             clock: 0.0,
             frame_counter: 0,
@@ -146,7 +282,12 @@ impl App<'_> {
             settings_manager,
             is_synchronized: false,
             is_host,
-        }
+            seed: None,
+            host_address: None,
+            controllers: Controllers::default(),
+        };
+        app.apply_profiles();
+        app
     }
 
     pub fn set_player_config(&mut self, player_config: PlayerConfig) {
@@ -158,13 +299,17 @@ impl App<'_> {
             remote_ip: _,
         } = self.player_config
         {
-            let server: String = if self.is_host {
-                local_ip().unwrap().to_string() + HOST_PORT
+            let server = if self.is_host {
+                self.host_address()
             } else {
-                local_ip().unwrap().to_string() + GUEST_PORT
+                launch::local_address(&GUEST_PORT)
             };
             //let local_ip = "127.0.0.1".to_string() + HOST_PORT;
-            if let Ok(stream) = TcpStream::connect(server) {
+            // there's no listener to kill without the local IP
+            if let Some(stream) = server
+                .ok()
+                .and_then(|server| TcpStream::connect(server).ok())
+            {
                 serde_cbor::to_writer::<TcpStream, MessageType>(stream, &MessageType::Kill)
                     .unwrap();
             }
@@ -205,7 +350,9 @@ impl App<'_> {
                     self.remote_player = vec![remote_player];
                     self.remote_player[0].listen(local_ip);
                 }
-                self.is_host = local_ip.ends_with('0');
+                self.is_host = self
+                    .host_address()
+                    .is_ok_and(|address| address == *local_ip);
                 if self.keybindings_manager.len() > 1 {
                     self.keybindings_manager = vec![Keybindings::new()];
                 }
@@ -222,12 +369,18 @@ impl App<'_> {
 
         self.settings_manager.set_player_config(&player_config);
         self.player_config = player_config;
+        self.apply_profiles();
     }
 
     pub fn handle_text_input(&mut self, input: &str) {
         match self.view_state {
             ViewState::MainMenu => self.widget_manager[0].handle_text_input(input),
             ViewState::JoinRoom => self.widget_manager[0].handle_text_input(input),
+            ViewState::Settings => {
+                for widget_manager in &mut self.widget_manager {
+                    widget_manager.handle_text_input(input);
+                }
+            }
             _ => {}
         }
     }
@@ -242,15 +395,44 @@ impl App<'_> {
                 }
             }
             ViewState::JoinRoom => self.widget_manager[0].handle_key_press(key),
+            ViewState::Editor => self.edit_queue(key),
             a if a.is_game() => {
+                // the perfect clear hint is only shown in practice, the runs of the other modes are ranked
+                if self.view_state == ViewState::Local
+                    && self.running == RunningState::Running
+                    && matches!(self.game_mode(), GameMode::Practice { gravity: _ })
+                    && PC_HINT_KEYS.contains(&key)
+                {
+                    self.local_players[0].toggle_pc_hint();
+                }
+                if self.view_state == ViewState::Local {
+                    if let Some(action) = PracticeAction::from_key(key) {
+                        self.practice(action);
+                    }
+                    if COPY_FUMEN_KEYS.contains(&key) {
+                        self.copy_game_fumen();
+                    } else if PASTE_FUMEN_KEYS.contains(&key) {
+                        self.paste_practice_fumen();
+                    } else if EXPORT_STATS_KEYS.contains(&key) {
+                        self.export_stats();
+                    }
+                }
                 for (id, player) in self.local_players.iter_mut().enumerate() {
-                    game_key_press =
-                        player.handle_key_press(&self.keybindings_manager[id], key, self.running)
+                    game_key_press = player.handle_key_press(
+                        &self.keybindings_manager[id],
+                        Input::Key(key),
+                        self.running,
+                    )
                 }
             }
             _ => {}
         }
-        match game_key_press {
+        self.change_game_flow(game_key_press);
+    }
+
+    /// Restarts, pauses, resumes or ends the game after a command of a player.
+    fn change_game_flow(&mut self, game_flow_change: GameFlowChange) {
+        match game_flow_change {
             GameFlowChange::Restart => self.restart(),
             GameFlowChange::Resume => self.pause(),
             GameFlowChange::Pause => self.pause(),
@@ -306,7 +488,10 @@ impl App<'_> {
                 }
             }
             GameFlowChange::Hello(remote_ip) => {
-                let local_ip = local_ip().unwrap().to_string() + HOST_PORT;
+                // the room only listens once its address is known
+                let Ok(local_ip) = self.host_address() else {
+                    return;
+                };
                 //let local_ip = "127.0.0.1".to_string() + HOST_PORT;
                 let player_config = PlayerConfig::TwoRemote {
                     local_ip,
@@ -323,7 +508,7 @@ impl App<'_> {
     pub fn handle_key_release(&mut self, key: Key) {
         if self.view_state.is_game() {
             for player in &mut self.local_players {
-                player.handle_key_release(key);
+                player.handle_key_release(Input::Key(key));
             }
         }
     }
@@ -332,17 +517,34 @@ impl App<'_> {
         for widget_manager in &mut self.widget_manager {
             widget_manager.handle_mouse_press(button, &self.cursor_position);
         }
+        if self.view_state == ViewState::Editor {
+            self.start_painting(button);
+        }
     }
 
     pub fn handle_mouse_release(&mut self, button: MouseButton) {
         for widget_manager in &mut self.widget_manager {
             widget_manager.handle_mouse_release(button);
         }
+        self.stop_painting();
+    }
+
+    /// Saves what must be kept until the next launch, when the window is closed.
+    pub fn quit(&mut self) {
+        self.save_zen();
     }
 
     fn set_view(&mut self, view_state: ViewState) {
         println!("setting view to {:?}", view_state);
-        let from_game = self.view_state.is_game();
+        // the settings are rebuilt when a profile changes, keeping the way back
+        let from_game = if self.view_state == ViewState::Settings {
+            self.widget_manager[0].has_button(&ButtonType::BackToGame)
+        } else {
+            self.view_state.is_game()
+        };
+        if view_state != ViewState::Settings {
+            self.settings_text.set_text(String::new());
+        }
         self.view_state = view_state;
         match self.view_state {
             ViewState::MainMenu => {
@@ -355,6 +557,7 @@ impl App<'_> {
                             &self.keybindings_manager[0],
                             SettingsType::OnePlayer,
                             from_game,
+                            &self.profile_labels(0),
                         )]
                     }
                     _ => {
@@ -362,6 +565,7 @@ impl App<'_> {
                             &self.keybindings_manager[0],
                             SettingsType::LeftPlayer,
                             from_game,
+                            &self.profile_labels(0),
                         )]
                     }
                 }
@@ -371,6 +575,7 @@ impl App<'_> {
                             &self.keybindings_manager[1],
                             SettingsType::RightPlayer,
                             from_game,
+                            &self.profile_labels(1),
                         ));
                 }
             }
@@ -378,7 +583,37 @@ impl App<'_> {
                 self.widget_manager = vec![InteractiveWidgetManager::new_two_player_game()];
             }
             ViewState::Local => {
-                self.widget_manager = vec![InteractiveWidgetManager::new_single_player_game()];
+                let level_select = self.prepare_level_select();
+                self.prepare_puzzle_intro();
+                self.prepare_opener_intro();
+                let next_item = match self.game_mode() {
+                    GameMode::Puzzle { index } => {
+                        Some(format!("Puzzle {}/{}", index + 1, self.puzzles.len()))
+                    }
+                    GameMode::Opener { index } => {
+                        Some(format!("Opener {}/{}", index + 1, self.openers.len()))
+                    }
+                    _ => None,
+                };
+                let modifiers: Vec<String> = Modifier::ALL
+                    .into_iter()
+                    .map(|modifier| {
+                        let state = if self.modifiers.is_set(modifier) {
+                            "on"
+                        } else {
+                            "off"
+                        };
+                        format!("{}: {state}", modifier.name())
+                    })
+                    .collect();
+                self.widget_manager = vec![InteractiveWidgetManager::new_single_player_game(
+                    &self.game_mode.to_string(),
+                    self.game_mode.level(0),
+                    level_select,
+                    &modifiers,
+                    next_item.as_deref(),
+                    &self.practice_labels(),
+                )];
             }
             ViewState::Remote => {
                 self.widget_manager = vec![InteractiveWidgetManager::new_two_player_game()];
@@ -386,16 +621,33 @@ impl App<'_> {
             ViewState::CreateRoom => {
                 /* let mut file = File::create("local_port.txt").unwrap();
                 file.write(HOST_PORT.as_bytes()).unwrap(); */
-                let local_ip = local_ip().unwrap().to_string() + HOST_PORT;
                 //let local_ip = "127.0.0.1".to_string() + HOST_PORT;
-                self.set_player_config(PlayerConfig::Viewer(local_ip));
+                match self.host_address() {
+                    Ok(local_ip) => {
+                        self.room_text.set_text(String::new());
+                        self.set_player_config(PlayerConfig::Viewer(local_ip));
+                    }
+                    Err(error) => self.room_text.set_text(error),
+                }
                 self.widget_manager = vec![InteractiveWidgetManager::new_create_room()]
             }
+            ViewState::Editor => {
+                self.show_position();
+                let (brushes, piece, hold) = self.editor_labels();
+                self.widget_manager = vec![InteractiveWidgetManager::new_editor(
+                    &brushes, &piece, &hold,
+                )];
+            }
             ViewState::JoinRoom => {
                 /* let mut file = File::create("local_port.txt").unwrap();
                 file.write(GUEST_PORT.as_bytes()).unwrap(); */
                 self.widget_manager = vec![InteractiveWidgetManager::new_join_room()]
             }
+            ViewState::Results => {
+                let (texts, switch_label) = self.results_texts();
+                self.results_texts = texts;
+                self.widget_manager = vec![InteractiveWidgetManager::new_results(switch_label)];
+            }
         }
     }
 
@@ -408,6 +660,7 @@ impl App<'_> {
             println!("PAUSE");
             self.send_message(MessageType::Pause);
             self.running = RunningState::Paused;
+            self.save_zen();
         }
     }
     /// Starts a countdown then starts the game. Inn TwoRemote mode, sends a synchronizing message.
@@ -424,8 +677,7 @@ impl App<'_> {
                     self.clock = 0.0;
                 } else if self.is_host {
                     println!("HOST SYNCHRONIZE");
-                    let mut rng = rand::thread_rng();
-                    self.settings_manager.seed = rng.gen();
+                    self.settings_manager.seed = self.new_seed();
                     self.settings_manager.send();
                 } else {
                     self.send_message(MessageType::Restart);
@@ -434,20 +686,37 @@ impl App<'_> {
             PlayerConfig::TwoLocal => {
                 self.running = RunningState::Starting;
                 self.clock = 0.0;
-                let mut rng = rand::thread_rng();
-                self.settings_manager.seed = rng.gen();
+                self.settings_manager.seed = self.new_seed();
                 for player in &mut self.local_players {
                     player.renew(self.settings_manager.seed);
                 }
             }
             PlayerConfig::Local => {
+                if !matches!(self.game_mode(), GameMode::Practice { gravity: _ }) {
+                    self.local_players[0].hide_pc_hint();
+                }
+                // the puzzles and the openers start right away
+                match self.game_mode() {
+                    GameMode::Puzzle { index } => {
+                        self.start_puzzle(index);
+                        return;
+                    }
+                    GameMode::Opener { index } => {
+                        self.start_opener(index);
+                        return;
+                    }
+                    _ => {}
+                }
                 self.running = RunningState::Starting;
                 self.clock = 0.0;
-                let mut rng = rand::thread_rng();
-                self.settings_manager.seed = rng.gen();
+                self.settings_manager.seed = self.new_seed();
+                let rules = self.game_mode().rules();
                 for player in &mut self.local_players {
+                    player.set_rules(rules);
+                    player.set_modifiers(self.modifiers);
                     player.renew(self.settings_manager.seed);
                 }
+                self.refresh_level_select();
             }
             _ => {
                 self.running = RunningState::Starting;
@@ -461,6 +730,7 @@ impl App<'_> {
         for player in &mut self.local_players {
             player.start();
         }
+        self.start_game_mode();
         self.clock = 0.0;
         self.running = RunningState::Running;
     }
@@ -471,6 +741,10 @@ impl App<'_> {
         self.send_message(MessageType::GameOver);
         self.running = RunningState::NotRunning;
         self.is_synchronized = false;
+        if self.player_config != PlayerConfig::Local {
+            self.record_versus();
+        }
+        self.game_over_game_mode();
     }
 
     fn countdown(&mut self, i: &Countdown) {
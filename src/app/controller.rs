@@ -0,0 +1,338 @@
+//! Lets the players play with controllers, whose buttons, D-pad and analog sticks are bound like keys.
+//!
+//! Each controller plays on a seat: the controller `id` on the seat `id` modulo the number of seats,
+//! so all the controllers play the single-player game and the first two play the two sides of the two-player game.
+use super::{App, GameFlowChange, LocalPlayer, RunningState, ViewState};
+use crate::{
+    input::Input,
+    settings::{Keybindings, CONTROLLER_DEADZONE},
+};
+use piston::{ControllerAxisArgs, ControllerButton, ControllerHat, HatState};
+use std::collections::HashMap;
+
+/// Directions held on the D-pads and analog sticks of the controllers, released when they change.
+#[derive(Default)]
+pub(super) struct Controllers {
+    /// State of each D-pad, by controller and D-pad.
+    hats: HashMap<(u32, u8), HatState>,
+    /// Direction held on each axis, by controller and axis.
+    axes: HashMap<(u32, u8), Input>,
+}
+
+/// Input of a controller being pressed or released.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum InputChange {
+    Pressed(Input),
+    Released(Input),
+}
+
+impl Controllers {
+    /// Returns the directions of the D-pad that aren't held anymore, released, then the new ones, pressed.
+    fn hat_changes(&mut self, hat: ControllerHat) -> Vec<InputChange> {
+        let previous = self
+            .hats
+            .insert((hat.id, hat.which), hat.state)
+            .unwrap_or(HatState::Centered);
+        let released = Input::hat_directions(previous);
+        let pressed = Input::hat_directions(hat.state);
+        let mut changes: Vec<InputChange> = released
+            .iter()
+            .filter(|input| !pressed.contains(input))
+            .map(|&input| InputChange::Released(input))
+            .collect();
+        changes.extend(
+            pressed
+                .iter()
+                .filter(|input| !released.contains(input))
+                .map(|&input| InputChange::Pressed(input)),
+        );
+        changes
+    }
+
+    /// Returns the press of the direction of the axis once it's pushed past the deadzone, and its release when it comes back.
+    fn axis_changes(&mut self, args: ControllerAxisArgs, deadzone: f64) -> Vec<InputChange> {
+        let direction = Input::axis_direction(args.axis, args.position, deadzone);
+        let axis = (args.id, args.axis);
+        let previous = match direction {
+            Some(direction) => self.axes.insert(axis, direction),
+            None => self.axes.remove(&axis),
+        };
+        if previous == direction {
+            return vec![];
+        }
+        previous
+            .map(InputChange::Released)
+            .into_iter()
+            .chain(direction.map(InputChange::Pressed))
+            .collect()
+    }
+}
+
+/// Returns the seat played with the controller.
+fn controller_seat(id: u32, nb_seats: usize) -> usize {
+    id as usize % nb_seats
+}
+
+/// Gives the change to the player of the seat of the controller, and returns how it changes the game flow.
+fn give_to_player(
+    players: &mut [LocalPlayer],
+    keybindings: &[Keybindings],
+    id: u32,
+    change: InputChange,
+    running: RunningState,
+) -> GameFlowChange {
+    let seat = controller_seat(id, keybindings.len());
+    let Some(player) = players.get_mut(seat) else {
+        return GameFlowChange::Other;
+    };
+    match change {
+        InputChange::Pressed(input) => player.handle_key_press(&keybindings[seat], input, running),
+        InputChange::Released(input) => {
+            player.handle_key_release(input);
+            GameFlowChange::Other
+        }
+    }
+}
+
+impl App<'_> {
+    /// Gives the change to the player of the seat of the controller, or the pressed input to the key inputs being typed in the settings.
+    fn change_controller_input(&mut self, id: u32, change: InputChange) {
+        if self.view_state == ViewState::Settings {
+            if let InputChange::Pressed(input) = change {
+                for widget_manager in &mut self.widget_manager {
+                    widget_manager.handle_input_press(input);
+                }
+            }
+        } else if self.view_state.is_game() {
+            let game_flow_change = give_to_player(
+                &mut self.local_players,
+                &self.keybindings_manager,
+                id,
+                change,
+                self.running,
+            );
+            self.change_game_flow(game_flow_change);
+        }
+    }
+
+    pub fn handle_controller_press(&mut self, button: ControllerButton) {
+        let input = Input::Button {
+            button: button.button,
+        };
+        self.change_controller_input(button.id, InputChange::Pressed(input));
+    }
+
+    pub fn handle_controller_release(&mut self, button: ControllerButton) {
+        let input = Input::Button {
+            button: button.button,
+        };
+        self.change_controller_input(button.id, InputChange::Released(input));
+    }
+
+    /// Releases the directions of the D-pad that aren't held anymore and presses the new ones.
+    pub fn handle_controller_hat(&mut self, hat: ControllerHat) {
+        for change in self.controllers.hat_changes(hat) {
+            self.change_controller_input(hat.id, change);
+        }
+    }
+
+    /// Presses the direction of the axis once it's pushed past the deadzone, and releases it when it comes back.
+    pub fn handle_controller_axis(&mut self, args: ControllerAxisArgs) {
+        for change in self.controllers.axis_changes(args, *CONTROLLER_DEADZONE) {
+            self.change_controller_input(args.id, change);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{Speed, Tetromino};
+    use crate::settings::gravity;
+    use crate::PlayerConfig;
+
+    fn axis(id: u32, position: f64) -> ControllerAxisArgs {
+        ControllerAxisArgs {
+            id,
+            axis: 0,
+            position,
+        }
+    }
+
+    fn right_column(tetromino: &Tetromino) -> i8 {
+        tetromino.cells().iter().map(|&(x, _)| x).max().unwrap()
+    }
+
+    /// Gives the changes to the players then updates them for the number of frames.
+    fn play(
+        players: &mut [LocalPlayer],
+        keybindings: &[Keybindings],
+        id: u32,
+        changes: Vec<InputChange>,
+        frames: u64,
+    ) {
+        for change in changes {
+            give_to_player(players, keybindings, id, change, RunningState::Running);
+        }
+        let (fall_speed_divide, freeze) = gravity(0.0);
+        for player in players.iter_mut() {
+            for frame in 1..=frames {
+                player.update(
+                    &keybindings[0],
+                    frame,
+                    Speed::new(fall_speed_divide, freeze),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn axis_presses_past_the_deadzone() {
+        let mut controllers = Controllers::default();
+        let right = Input::Axis {
+            axis: 0,
+            positive: true,
+        };
+        let left = Input::Axis {
+            axis: 0,
+            positive: false,
+        };
+        assert_eq!(controllers.axis_changes(axis(0, 0.4), 0.5), vec![]);
+        assert_eq!(
+            controllers.axis_changes(axis(0, 0.6), 0.5),
+            vec![InputChange::Pressed(right)]
+        );
+        assert_eq!(controllers.axis_changes(axis(0, 0.9), 0.5), vec![]);
+        assert_eq!(
+            controllers.axis_changes(axis(0, -0.7), 0.5),
+            vec![InputChange::Released(right), InputChange::Pressed(left)]
+        );
+        assert_eq!(
+            controllers.axis_changes(axis(0, -0.2), 0.5),
+            vec![InputChange::Released(left)]
+        );
+        // each controller has its own axes
+        assert_eq!(
+            controllers.axis_changes(axis(1, 0.6), 0.5),
+            vec![InputChange::Pressed(right)]
+        );
+    }
+
+    #[test]
+    fn hat_releases_the_directions_left() {
+        let mut controllers = Controllers::default();
+        let hat = |state| ControllerHat {
+            id: 0,
+            state,
+            which: 0,
+        };
+        assert_eq!(
+            controllers.hat_changes(hat(HatState::RightUp)),
+            vec![
+                InputChange::Pressed(Input::Hat {
+                    hat: HatState::Right
+                }),
+                InputChange::Pressed(Input::Hat { hat: HatState::Up }),
+            ]
+        );
+        assert_eq!(
+            controllers.hat_changes(hat(HatState::Up)),
+            vec![InputChange::Released(Input::Hat {
+                hat: HatState::Right
+            })]
+        );
+        assert_eq!(
+            controllers.hat_changes(hat(HatState::Centered)),
+            vec![InputChange::Released(Input::Hat { hat: HatState::Up })]
+        );
+    }
+
+    #[test]
+    fn controllers_play_on_their_seat() {
+        assert_eq!(controller_seat(0, 1), 0);
+        assert_eq!(controller_seat(3, 1), 0);
+        assert_eq!(controller_seat(0, 2), 0);
+        assert_eq!(controller_seat(1, 2), 1);
+        assert_eq!(controller_seat(3, 2), 1);
+
+        let mut players = vec![
+            LocalPlayer::new(&PlayerConfig::TwoLocal),
+            LocalPlayer::new(&PlayerConfig::TwoLocal),
+        ];
+        for player in &mut players {
+            player.renew(0);
+            player.start();
+        }
+        let keybindings = vec![Keybindings::default(), Keybindings::default()];
+        let start = right_column(&players[0].player_screen().active_tetromino);
+        let press = InputChange::Pressed(Input::Hat {
+            hat: HatState::Right,
+        });
+        play(&mut players, &keybindings, 3, vec![press], 0);
+        assert_eq!(
+            right_column(&players[0].player_screen().active_tetromino),
+            start
+        );
+        assert_eq!(
+            right_column(&players[1].player_screen().active_tetromino),
+            start + 1
+        );
+    }
+
+    #[test]
+    fn release_stops_the_auto_repeat() {
+        let keybindings = vec![Keybindings::default()];
+        let mut controllers = Controllers::default();
+
+        let mut held = LocalPlayer::new(&PlayerConfig::Local);
+        held.renew(0);
+        held.start();
+        let start = right_column(&held.player_screen().active_tetromino);
+        let press = controllers.axis_changes(axis(0, 0.9), 0.5);
+        play(
+            std::slice::from_mut(&mut held),
+            &keybindings,
+            0,
+            press.clone(),
+            100,
+        );
+        assert_eq!(right_column(&held.player_screen().active_tetromino), 9);
+
+        let mut released = LocalPlayer::new(&PlayerConfig::Local);
+        released.renew(0);
+        released.start();
+        let mut changes = press;
+        changes.extend(controllers.axis_changes(axis(0, 0.1), 0.5));
+        play(
+            std::slice::from_mut(&mut released),
+            &keybindings,
+            0,
+            changes,
+            100,
+        );
+        assert_eq!(
+            right_column(&released.player_screen().active_tetromino),
+            start + 1
+        );
+    }
+
+    #[test]
+    fn buttons_pause_and_resume() {
+        let keybindings = vec![Keybindings::default()];
+        let mut players = vec![LocalPlayer::new(&PlayerConfig::Local)];
+        players[0].renew(0);
+        players[0].start();
+        let start = InputChange::Pressed(Input::Button { button: 7 });
+        let pause = give_to_player(&mut players, &keybindings, 0, start, RunningState::Running);
+        assert_eq!(pause, GameFlowChange::Pause);
+        give_to_player(
+            &mut players,
+            &keybindings,
+            0,
+            InputChange::Released(Input::Button { button: 7 }),
+            RunningState::Paused,
+        );
+        let resume = give_to_player(&mut players, &keybindings, 0, start, RunningState::Paused);
+        assert_eq!(resume, GameFlowChange::Resume);
+    }
+}
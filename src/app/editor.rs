@@ -0,0 +1,315 @@
+//! Defines the [Editor] where the player sets up a position, the grid and the tetrominos, to play from it.
+use super::{
+    fumen::Page, puzzle, App, GameMode, Modifiers, Randomizer, Rules, RunningState, TetrominoKind,
+    ViewState,
+};
+use crate::assets::TetrisColor;
+use crate::settings::{BLOCK_SIZE, DEFAULT_GRID_X, DEFAULT_GRID_Y, NB_COLUMNS, NB_ROWS};
+use crate::utils::storage;
+use piston::MouseButton;
+use piston_window::Key;
+use serde::{Deserialize, Serialize};
+
+/// File of the data directory where the position of the editor is saved.
+static POSITION_FILE: &str = "position.json";
+
+/// Position saved by the editor, in the format of the puzzles.
+#[derive(Serialize, Deserialize)]
+struct Position {
+    /// Rows at the bottom of the grid, from the highest one, see [Puzzle::board](super::puzzle::Puzzle::board).
+    board: Vec<String>,
+    /// Active tetromino followed by the next ones.
+    pieces: Vec<TetrominoKind>,
+    #[serde(default)]
+    hold: Option<TetrominoKind>,
+}
+
+/// What a click on a cell of the grid puts in it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Brush {
+    /// A block of the color of the tetromino.
+    Block(TetrominoKind),
+    Garbage,
+    Eraser,
+}
+
+impl Brush {
+    pub const ALL: [Brush; 9] = [
+        Brush::Block(TetrominoKind::I),
+        Brush::Block(TetrominoKind::O),
+        Brush::Block(TetrominoKind::T),
+        Brush::Block(TetrominoKind::S),
+        Brush::Block(TetrominoKind::Z),
+        Brush::Block(TetrominoKind::J),
+        Brush::Block(TetrominoKind::L),
+        Brush::Garbage,
+        Brush::Eraser,
+    ];
+
+    pub fn name(self) -> String {
+        match self {
+            Brush::Block(kind) => kind.get(),
+            Brush::Garbage => String::from("Garbage"),
+            Brush::Eraser => String::from("Eraser"),
+        }
+    }
+
+    fn cell(self) -> Option<TetrisColor> {
+        match self {
+            Brush::Block(kind) => Some(kind.get_color()),
+            Brush::Garbage => Some(TetrisColor::Grey),
+            Brush::Eraser => None,
+        }
+    }
+
+    /// Returns the character of the cell in the board of a [Position].
+    fn letter(cell: Option<TetrisColor>) -> char {
+        match cell {
+            None => '.',
+            Some(TetrisColor::Grey) => 'G',
+            Some(color) => Brush::ALL
+                .into_iter()
+                .find(|brush| brush.cell() == Some(color))
+                .and_then(|brush| brush.name().chars().next())
+                .unwrap_or('G'),
+        }
+    }
+}
+
+/// Position being edited, kept until the editor is opened again.
+pub(super) struct Editor {
+    /// Cells of the visible rows of the grid, from the highest one.
+    rows: Vec<Vec<Option<TetrisColor>>>,
+    /// Active tetromino followed by the next ones, never empty.
+    pieces: Vec<TetrominoKind>,
+    hold: Option<TetrominoKind>,
+    brush: Brush,
+    /// Brush applied to the cells under the cursor while a mouse button is held.
+    painting: Option<Brush>,
+}
+
+impl Editor {
+    pub(super) fn new() -> Editor {
+        Editor {
+            rows: vec![vec![None; NB_COLUMNS as usize]; (NB_ROWS - 2) as usize],
+            pieces: vec![TetrominoKind::T],
+            hold: None,
+            brush: Brush::Garbage,
+            painting: None,
+        }
+    }
+
+    fn position(&self) -> Position {
+        // the empty rows at the top are left out like in the puzzles
+        let board = self
+            .rows
+            .iter()
+            .skip_while(|row| row.iter().all(Option::is_none))
+            .map(|row| row.iter().copied().map(Brush::letter).collect())
+            .collect();
+        Position {
+            board,
+            pieces: self.pieces.clone(),
+            hold: self.hold,
+        }
+    }
+
+    /// Returns the rows of the whole grid, the hidden ones included, and the active tetromino, for a fumen.
+    pub(super) fn page(&self) -> (Vec<Vec<Option<TetrisColor>>>, TetrominoKind) {
+        let mut rows = vec![vec![None; NB_COLUMNS as usize]; 2];
+        rows.extend(self.rows.iter().cloned());
+        (rows, self.pieces[0])
+    }
+
+    /// Replaces the grid by the visible rows of the page of a fumen and the tetrominos by its tetromino, if any.
+    pub(super) fn set_page(&mut self, page: Page) {
+        let hidden_rows = page.rows.len().saturating_sub((NB_ROWS - 2) as usize);
+        self.rows = page.rows[hidden_rows..].to_vec();
+        let active = page.tetromino.map_or(self.pieces[0], |(kind, _)| kind);
+        self.pieces = vec![active];
+        self.hold = None;
+    }
+
+    fn set_position(&mut self, position: Position) {
+        let nb_rows = (NB_ROWS - 2) as usize;
+        let mut rows = vec![vec![None; NB_COLUMNS as usize]; nb_rows];
+        let first_row = position.board.len().saturating_sub(nb_rows);
+        let top = nb_rows - (position.board.len() - first_row);
+        for (y, row) in position.board[first_row..].iter().enumerate() {
+            for (x, c) in row.chars().take(NB_COLUMNS as usize).enumerate() {
+                rows[top + y][x] = puzzle::cell(c);
+            }
+        }
+        self.rows = rows;
+        if !position.pieces.is_empty() {
+            self.pieces = position.pieces;
+        }
+        self.hold = position.hold;
+    }
+}
+
+/// Returns the kind of tetromino typed with the key of its letter.
+fn typed_kind(key: Key) -> Option<TetrominoKind> {
+    match key {
+        Key::I => Some(TetrominoKind::I),
+        Key::O => Some(TetrominoKind::O),
+        Key::T => Some(TetrominoKind::T),
+        Key::S => Some(TetrominoKind::S),
+        Key::Z => Some(TetrominoKind::Z),
+        Key::J => Some(TetrominoKind::J),
+        Key::L => Some(TetrominoKind::L),
+        _ => None,
+    }
+}
+
+impl App<'_> {
+    /// Returns the labels of the buttons of the brushes, of the active tetromino and of the held one.
+    pub(super) fn editor_labels(&self) -> (Vec<String>, String, String) {
+        let brushes = Brush::ALL
+            .into_iter()
+            .map(|brush| {
+                if brush == self.editor.brush {
+                    format!("[{}]", brush.name())
+                } else {
+                    brush.name()
+                }
+            })
+            .collect();
+        let hold = self
+            .editor
+            .hold
+            .map_or(String::from("none"), |kind| kind.get());
+        (
+            brushes,
+            format!("Piece: {}", self.editor.pieces[0].get()),
+            format!("Hold: {hold}"),
+        )
+    }
+
+    /// Shows the position of the editor on the grid of the player, with only the tetrominos of the position.
+    pub(super) fn show_position(&mut self) {
+        let player = &mut self.local_players[0];
+        player.set_rules(Rules {
+            randomizer: Randomizer::Fixed,
+            ..Rules::default()
+        });
+        player.set_modifiers(Modifiers::default());
+        player.load(&self.editor.rows, &self.editor.pieces, self.editor.hold);
+        player.stop();
+    }
+
+    pub(super) fn select_brush(&mut self, index: usize) {
+        self.editor.brush = Brush::ALL[index];
+        self.set_view(ViewState::Editor);
+    }
+
+    /// Starts painting the cells under the cursor, with the brush or, with the right button, the eraser.
+    pub(super) fn start_painting(&mut self, button: MouseButton) {
+        self.editor.painting = match button {
+            MouseButton::Left => Some(self.editor.brush),
+            MouseButton::Right => Some(Brush::Eraser),
+            _ => None,
+        };
+        self.paint();
+    }
+
+    pub(super) fn stop_painting(&mut self) {
+        self.editor.painting = None;
+    }
+
+    /// Paints the visible cell under the cursor, if a mouse button is held.
+    pub(super) fn paint(&mut self) {
+        let Some(brush) = self.editor.painting else {
+            return;
+        };
+        let [x, y] = self.cursor_position;
+        let column = ((x - DEFAULT_GRID_X) / BLOCK_SIZE).floor();
+        // the two rows at the top of the grid are hidden
+        let row = ((y - DEFAULT_GRID_Y) / BLOCK_SIZE).floor() - 2.0;
+        if column < 0.0 || column >= NB_COLUMNS as f64 || row < 0.0 || row >= (NB_ROWS - 2) as f64 {
+            return;
+        }
+        let cell = &mut self.editor.rows[row as usize][column as usize];
+        if *cell != brush.cell() {
+            *cell = brush.cell();
+            self.show_position();
+        }
+    }
+
+    /// Adds the typed tetromino at the end of the queue, or removes the last one with Backspace.
+    pub(super) fn edit_queue(&mut self, key: Key) {
+        if let Some(kind) = typed_kind(key) {
+            self.editor.pieces.push(kind);
+        } else if key == Key::Backspace && self.editor.pieces.len() > 1 {
+            self.editor.pieces.pop();
+        } else {
+            return;
+        }
+        self.show_position();
+    }
+
+    /// Replaces the active tetromino by the one of the following kind.
+    pub(super) fn next_active_piece(&mut self) {
+        self.editor.pieces[0] = self.editor.pieces[0].next();
+        self.set_view(ViewState::Editor);
+    }
+
+    /// Replaces the held tetromino by the one of the following kind, none coming after the last kind.
+    pub(super) fn next_hold_piece(&mut self) {
+        self.editor.hold = match self.editor.hold {
+            None => Some(TetrominoKind::I),
+            Some(TetrominoKind::L) => None,
+            Some(kind) => Some(kind.next()),
+        };
+        self.set_view(ViewState::Editor);
+    }
+
+    pub(super) fn clear_grid(&mut self) {
+        self.editor.rows = Editor::new().rows;
+        self.show_position();
+    }
+
+    pub(super) fn save_position(&mut self) {
+        match storage::save(POSITION_FILE, &self.editor.position()) {
+            Ok(()) => self.mode_text.set_text(String::from("Position saved")),
+            Err(error) => {
+                println!("couldn't save the position: {error}");
+                self.mode_text.set_text(String::from("Couldn't save"));
+            }
+        }
+    }
+
+    pub(super) fn load_position(&mut self) {
+        match storage::load::<Position>(POSITION_FILE) {
+            Some(position) => {
+                self.editor.set_position(position);
+                self.mode_text.set_text(String::from("Position loaded"));
+                self.set_view(ViewState::Editor);
+            }
+            None => self.mode_text.set_text(String::from("No saved position")),
+        }
+    }
+
+    /// Starts a practice right away from the position, the next tetrominos being dealt from bags once its queue runs out.
+    pub(super) fn play_position(&mut self) {
+        if !matches!(self.game_mode, GameMode::Practice { gravity: _ }) {
+            self.game_mode = GameMode::Practice { gravity: false };
+        }
+        let rules = self.game_mode().rules();
+        // the position is made for a grid of the default size
+        let modifiers = Modifiers {
+            big: false,
+            ..self.modifiers
+        };
+        self.settings_manager.seed = self.new_seed();
+        let player = &mut self.local_players[0];
+        player.set_rules(rules);
+        player.set_modifiers(modifiers);
+        player.renew(self.settings_manager.seed);
+        player.load(&self.editor.rows, &self.editor.pieces, self.editor.hold);
+        self.start_game_mode();
+        self.clock = 0.0;
+        self.running = RunningState::Running;
+        self.set_view(ViewState::Local);
+    }
+}
@@ -0,0 +1,623 @@
+//! Reads and writes the fumens, the strings of the fumen editor used by the community to share boards,
+//! and defines how [App] copies and pastes them.
+//!
+//! Only the version 115 is supported: `v115@` followed by the pages, each one being the changes to the field,
+//! the tetromino placed on it and eventually a comment, written with the 64 characters of ENCODE_TABLE.
+use super::{App, GameMode, RunningState, Tetromino, TetrominoKind, ViewState};
+use crate::assets::TetrisColor;
+use crate::settings::{NB_COLUMNS, NB_ROWS};
+use clipboard::{ClipboardContext, ClipboardProvider};
+
+static PREFIX: &str = "v115@";
+static ENCODE_TABLE: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+/// Width of the field, the same as the grid.
+const FIELD_WIDTH: usize = 10;
+/// Number of rows of the field above its garbage row.
+const FIELD_TOP: usize = 23;
+/// Number of cells of the field, with the garbage row under it.
+const FIELD_BLOCKS: usize = (FIELD_TOP + 1) * FIELD_WIDTH;
+/// Kinds of tetromino in the order of their numbers in a fumen, 0 being an empty cell and 8 garbage.
+const KINDS: [TetrominoKind; 7] = [
+    TetrominoKind::I,
+    TetrominoKind::L,
+    TetrominoKind::O,
+    TetrominoKind::Z,
+    TetrominoKind::T,
+    TetrominoKind::J,
+    TetrominoKind::S,
+];
+
+/// Page of a fumen: the grid and the tetromino placed on it.
+pub struct Page {
+    /// Cells of the grid, from the highest row.
+    pub rows: Vec<Vec<Option<TetrisColor>>>,
+    /// Kind and cells of the tetromino, in the coordinates of the rows.
+    pub tetromino: Option<(TetrominoKind, [(i8, i8); 4])>,
+}
+
+/// Orientation of a tetromino, in the order of their numbers in a fumen.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Rotation {
+    Reverse,
+    Right,
+    Spawn,
+    Left,
+}
+
+impl Rotation {
+    const ALL: [Rotation; 4] = [
+        Rotation::Spawn,
+        Rotation::Right,
+        Rotation::Reverse,
+        Rotation::Left,
+    ];
+
+    fn number(self) -> u32 {
+        match self {
+            Rotation::Reverse => 0,
+            Rotation::Right => 1,
+            Rotation::Spawn => 2,
+            Rotation::Left => 3,
+        }
+    }
+
+    fn from_number(number: u32) -> Rotation {
+        match number % 4 {
+            0 => Rotation::Reverse,
+            1 => Rotation::Right,
+            2 => Rotation::Spawn,
+            _ => Rotation::Left,
+        }
+    }
+}
+
+/// Returns the cells of the tetromino around its center, the y axis going up like in a fumen.
+fn shape(kind: TetrominoKind, rotation: Rotation) -> [(i32, i32); 4] {
+    let spawn = match kind {
+        TetrominoKind::I => [(0, 0), (-1, 0), (1, 0), (2, 0)],
+        TetrominoKind::T => [(0, 0), (-1, 0), (1, 0), (0, 1)],
+        TetrominoKind::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+        TetrominoKind::L => [(0, 0), (-1, 0), (1, 0), (1, 1)],
+        TetrominoKind::J => [(0, 0), (-1, 0), (1, 0), (-1, 1)],
+        TetrominoKind::S => [(0, 0), (-1, 0), (0, 1), (1, 1)],
+        TetrominoKind::Z => [(0, 0), (1, 0), (0, 1), (-1, 1)],
+    };
+    spawn.map(|(x, y)| match rotation {
+        Rotation::Spawn => (x, y),
+        Rotation::Right => (y, -x),
+        Rotation::Reverse => (-x, -y),
+        Rotation::Left => (-y, x),
+    })
+}
+
+/// Returns the offset between the center of the tetromino and the position written in a fumen,
+/// which differs for some orientations of the O, I, S and Z.
+fn position_offset(kind: TetrominoKind, rotation: Rotation) -> (i32, i32) {
+    match (kind, rotation) {
+        (TetrominoKind::O, Rotation::Left) => (-1, 1),
+        (TetrominoKind::O, Rotation::Reverse) => (-1, 0),
+        (TetrominoKind::O, Rotation::Spawn) => (0, 1),
+        (TetrominoKind::I, Rotation::Reverse) => (-1, 0),
+        (TetrominoKind::I, Rotation::Left) => (0, 1),
+        (TetrominoKind::S, Rotation::Spawn) => (0, 1),
+        (TetrominoKind::S, Rotation::Right) => (1, 0),
+        (TetrominoKind::Z, Rotation::Spawn) => (0, 1),
+        (TetrominoKind::Z, Rotation::Left) => (-1, 0),
+        _ => (0, 0),
+    }
+}
+
+/// Cells of a field, from its highest row to its garbage row, as numbers of tetromino.
+type Field = [u32; FIELD_BLOCKS];
+
+/// Returns the index in a field of the cell at the column x and the height y, from 0 at the bottom.
+fn field_index(x: i32, y: i32) -> Option<usize> {
+    if !(0..FIELD_WIDTH as i32).contains(&x) || !(0..FIELD_TOP as i32).contains(&y) {
+        return None;
+    }
+    Some((FIELD_TOP - 1 - y as usize) * FIELD_WIDTH + x as usize)
+}
+
+/// Returns the field of the rows, the lowest one being at the bottom of the field.
+fn to_field(rows: &[Vec<Option<TetrisColor>>]) -> Field {
+    let mut field = [0; FIELD_BLOCKS];
+    for (y, row) in rows.iter().rev().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if let Some(index) = field_index(x as i32, y as i32) {
+                field[index] = cell_number(*cell);
+            }
+        }
+    }
+    field
+}
+
+fn cell_number(cell: Option<TetrisColor>) -> u32 {
+    match cell {
+        None => 0,
+        Some(TetrisColor::Grey) => 8,
+        Some(color) => KINDS
+            .iter()
+            .position(|kind| kind.get_color() == color)
+            .map_or(8, |index| index as u32 + 1),
+    }
+}
+
+fn number_cell(number: u32) -> Option<TetrisColor> {
+    match number {
+        0 => None,
+        1..=7 => Some(KINDS[number as usize - 1].get_color()),
+        _ => Some(TetrisColor::Grey),
+    }
+}
+
+/// Puts the tetromino in the field and clears the complete rows, like a fumen goes to the next page.
+fn lock(field: &mut Field, kind: TetrominoKind, cells: &[(i32, i32)]) {
+    let number = KINDS.iter().position(|&k| k == kind).unwrap() as u32 + 1;
+    for &(x, y) in cells {
+        if let Some(index) = field_index(x, y) {
+            field[index] = number;
+        }
+    }
+    let rows: Vec<&[u32]> = field[..FIELD_TOP * FIELD_WIDTH]
+        .chunks(FIELD_WIDTH)
+        .filter(|row| row.contains(&0))
+        .collect();
+    let mut cleared = [0; FIELD_BLOCKS];
+    let top = FIELD_TOP - rows.len();
+    for (y, row) in rows.iter().enumerate() {
+        cleared[(top + y) * FIELD_WIDTH..(top + y + 1) * FIELD_WIDTH].copy_from_slice(row);
+    }
+    cleared[FIELD_TOP * FIELD_WIDTH..].copy_from_slice(&field[FIELD_TOP * FIELD_WIDTH..]);
+    *field = cleared;
+}
+
+/// Returns the cells of the tetromino in the field, the y axis going up, from the cells in the rows.
+fn field_cells(cells: &[(i8, i8); 4], nb_rows: usize) -> [(i32, i32); 4] {
+    cells.map(|(x, y)| (x as i32, nb_rows as i32 - 1 - y as i32))
+}
+
+/// Returns the orientation and the center of the tetromino covering the cells.
+fn find_placement(kind: TetrominoKind, cells: &[(i32, i32); 4]) -> Option<(Rotation, (i32, i32))> {
+    for rotation in Rotation::ALL {
+        let offsets = shape(kind, rotation);
+        for &(x, y) in cells {
+            let center = (x - offsets[0].0, y - offsets[0].1);
+            if offsets
+                .iter()
+                .all(|(dx, dy)| cells.contains(&(center.0 + dx, center.1 + dy)))
+            {
+                return Some((rotation, center));
+            }
+        }
+    }
+    None
+}
+
+/// Writes the value with the given number of characters, the lowest digit first.
+fn push(values: &mut Vec<u32>, mut value: u32, nb_digits: usize) {
+    for _ in 0..nb_digits {
+        values.push(value % 64);
+        value /= 64;
+    }
+}
+
+/// Returns the fumen of the pages, the tetromino of each page being locked before the next one.
+///
+/// The rows must be at most 23 and NB_COLUMNS wide.
+pub fn encode(pages: &[Page]) -> String {
+    let mut values = vec![];
+    let mut previous = [0; FIELD_BLOCKS];
+    // index of the number of following pages with the same field, while the field doesn't change
+    let mut repeat_index: Option<usize> = None;
+    for (i, page) in pages.iter().enumerate() {
+        let field = to_field(&page.rows);
+        // the changes are runs of cells with the same difference
+        let mut runs: Vec<(u32, u32)> = vec![];
+        for (cell, previous_cell) in field.iter().zip(previous.iter()) {
+            let diff = cell + 8 - previous_cell;
+            match runs.last_mut() {
+                Some((last, count)) if *last == diff => *count += 1,
+                _ => runs.push((diff, 0)),
+            }
+        }
+        let changed = runs != [(8, FIELD_BLOCKS as u32 - 1)];
+        match repeat_index {
+            Some(index) if !changed && values[index] < 63 => values[index] += 1,
+            _ => {
+                for (diff, count) in runs {
+                    push(&mut values, diff * FIELD_BLOCKS as u32 + count, 2);
+                }
+                repeat_index = None;
+                if !changed {
+                    repeat_index = Some(values.len());
+                    values.push(0);
+                }
+            }
+        }
+
+        let placement = page.tetromino.and_then(|(kind, cells)| {
+            let cells = field_cells(&cells, page.rows.len());
+            find_placement(kind, &cells).map(|placement| (kind, cells, placement))
+        });
+        let (number, rotation, location) = match placement {
+            Some((kind, _, (rotation, (x, y)))) => {
+                let (dx, dy) = position_offset(kind, rotation);
+                let location = (FIELD_TOP as i32 - (y + dy) - 1) * FIELD_WIDTH as i32 + x + dx;
+                let number = KINDS.iter().position(|&k| k == kind).unwrap() as u32 + 1;
+                (number, rotation, location.max(0) as u32)
+            }
+            None => (0, Rotation::Reverse, 0),
+        };
+        // the flags from the highest: not locked, comment, guideline colors on the first page, mirror, rise
+        let colorize = (i == 0) as u32;
+        let mut action = colorize * 2 * 2;
+        action = action * FIELD_BLOCKS as u32 + location;
+        action = action * 4 + rotation.number();
+        action = action * 8 + number;
+        push(&mut values, action, 3);
+
+        previous = field;
+        if let Some((kind, cells, _)) = placement {
+            lock(&mut previous, kind, &cells);
+        }
+    }
+
+    let data: String = values
+        .into_iter()
+        .map(|value| ENCODE_TABLE[value as usize] as char)
+        .collect();
+    // a '?' is inserted after the first 42 characters then every 47 characters
+    let mut fumen = String::from(PREFIX);
+    for (i, c) in data.chars().enumerate() {
+        if i >= 42 && (i - 42) % 47 == 0 {
+            fumen.push('?');
+        }
+        fumen.push(c);
+    }
+    fumen
+}
+
+/// Reads the digits of a fumen.
+struct Reader {
+    values: Vec<u32>,
+    index: usize,
+}
+
+impl Reader {
+    fn poll(&mut self, nb_digits: usize) -> Result<u32, String> {
+        if self.index + nb_digits > self.values.len() {
+            return Err(String::from("the fumen is cut short"));
+        }
+        let value = self.values[self.index..self.index + nb_digits]
+            .iter()
+            .rev()
+            .fold(0, |value, digit| value * 64 + digit);
+        self.index += nb_digits;
+        Ok(value)
+    }
+
+    /// Skips the given number of digits.
+    fn poll_skip(&mut self, nb_digits: usize) -> Result<(), String> {
+        if self.index + nb_digits > self.values.len() {
+            return Err(String::from("the fumen is cut short"));
+        }
+        self.index += nb_digits;
+        Ok(())
+    }
+}
+
+/// Returns the first page of the fumen, with NB_ROWS rows.
+pub fn decode(fumen: &str) -> Result<Page, String> {
+    decode_pages(fumen)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| String::from("the fumen has no page"))
+}
+
+/// Returns the pages of the fumen, with NB_ROWS rows, the tetromino of each page being locked before the next one.
+pub fn decode_pages(fumen: &str) -> Result<Vec<Page>, String> {
+    let fumen = fumen.trim();
+    let Some(data) = fumen.strip_prefix(PREFIX) else {
+        return Err(String::from(
+            "only the fumens starting with v115@ can be read",
+        ));
+    };
+    let values = data
+        .chars()
+        .filter(|&c| c != '?' && !c.is_whitespace())
+        .map(|c| {
+            ENCODE_TABLE
+                .iter()
+                .position(|&e| e as char == c)
+                .map(|value| value as u32)
+                .ok_or_else(|| format!("unexpected character '{c}'"))
+        })
+        .collect::<Result<Vec<u32>, String>>()?;
+    let mut reader = Reader { values, index: 0 };
+
+    let mut pages = vec![];
+    let mut field: Field = [0; FIELD_BLOCKS];
+    // number of following pages whose field doesn't change, they have no changes written
+    let mut repeat = 0;
+    while reader.index < reader.values.len() {
+        if repeat > 0 {
+            repeat -= 1;
+        } else {
+            let mut index = 0;
+            let mut changed = false;
+            while index < FIELD_BLOCKS {
+                let value = reader.poll(2)?;
+                let diff = value / FIELD_BLOCKS as u32;
+                let count = value % FIELD_BLOCKS as u32 + 1;
+                changed |= diff != 8;
+                for _ in 0..count {
+                    if index < FIELD_BLOCKS {
+                        field[index] = (field[index] + diff).saturating_sub(8);
+                        index += 1;
+                    }
+                }
+            }
+            if !changed {
+                repeat = reader.poll(1)?;
+            }
+        }
+        let mut action = reader.poll(3)?;
+        let number = action % 8;
+        action /= 8;
+        let rotation = Rotation::from_number(action % 4);
+        action /= 4;
+        let location = (action % FIELD_BLOCKS as u32) as i32;
+        // the flags from the lowest: rise, mirror, guideline colors, comment, not locked
+        let flags = action / FIELD_BLOCKS as u32;
+        if flags & 8 != 0 {
+            // the comment is skipped, every 4 characters of it being written with 5 digits
+            let length = reader.poll(2)? as usize;
+            reader.poll_skip(length.div_ceil(4) * 5)?;
+        }
+
+        let nb_rows = NB_ROWS as usize;
+        let rows: Vec<Vec<Option<TetrisColor>>> = (0..nb_rows)
+            .map(|y| {
+                (0..NB_COLUMNS as i32)
+                    .map(|x| {
+                        field_index(x, (nb_rows - 1 - y) as i32).and_then(|i| number_cell(field[i]))
+                    })
+                    .collect()
+            })
+            .collect();
+        let tetromino = match number {
+            1..=7 => {
+                let kind = KINDS[number as usize - 1];
+                let (dx, dy) = position_offset(kind, rotation);
+                let x = location % FIELD_WIDTH as i32 - dx;
+                let y = FIELD_TOP as i32 - location / FIELD_WIDTH as i32 - 1 - dy;
+                let cells = shape(kind, rotation).map(|(cx, cy)| (x + cx, y + cy));
+                if flags & 16 == 0 {
+                    lock(&mut field, kind, &cells);
+                }
+                Some((
+                    kind,
+                    cells.map(|(x, y)| (x as i8, (nb_rows as i32 - 1 - y) as i8)),
+                ))
+            }
+            _ => None,
+        };
+        if flags & 1 != 0 {
+            // the garbage row rises into the field
+            field.copy_within(FIELD_WIDTH.., 0);
+            field[FIELD_TOP * FIELD_WIDTH..].fill(0);
+        }
+        if flags & 2 != 0 {
+            for row in field[..FIELD_TOP * FIELD_WIDTH].chunks_mut(FIELD_WIDTH) {
+                row.reverse();
+            }
+        }
+        pages.push(Page { rows, tetromino });
+    }
+    Ok(pages)
+}
+
+impl App<'_> {
+    /// Copies the fumen of the game to the clipboard: a page for each tetromino locked since the start,
+    /// the undone ones left out, and a last page with the grid and the active tetromino.
+    pub(super) fn copy_game_fumen(&mut self) {
+        let player = &self.local_players[0];
+        let screen = player.player_screen();
+        if screen.grid.nb_columns() != NB_COLUMNS {
+            self.mode_text
+                .set_text(String::from("Fumens need a full grid"));
+            return;
+        }
+        let mut pages: Vec<Page> = player
+            .placements()
+            .iter()
+            .map(|(rows, tetromino)| Page {
+                rows: rows.clone(),
+                tetromino: Some((tetromino.kind(), tetromino.cells())),
+            })
+            .collect();
+        pages.push(Page {
+            rows: screen.grid.rows().to_vec(),
+            tetromino: (!screen.game_over).then(|| {
+                let active = &screen.active_tetromino;
+                (active.kind(), active.cells())
+            }),
+        });
+        let nb_pages = pages.len();
+        self.copy_fumen(&encode(&pages), &format!("Copied {nb_pages} pages"));
+    }
+
+    /// Copies the fumen of the position of the editor, with its active tetromino, to the clipboard.
+    pub(super) fn copy_editor_fumen(&mut self) {
+        let (rows, kind) = self.editor.page();
+        let tetromino = Some((kind, Tetromino::new_unchecked(kind).cells()));
+        self.copy_fumen(&encode(&[Page { rows, tetromino }]), "Copied the position");
+    }
+
+    fn copy_fumen(&mut self, fumen: &str, message: &str) {
+        let copied = ClipboardContext::new()
+            .and_then(|mut ctx: ClipboardContext| ctx.set_contents(fumen.to_owned()));
+        match copied {
+            Ok(()) => self.mode_text.set_text(message.to_string()),
+            Err(error) => {
+                println!("couldn't copy the fumen: {error}");
+                self.mode_text.set_text(String::from("Couldn't copy"));
+            }
+        }
+    }
+
+    /// Reads the first page of the fumen of the clipboard.
+    fn paste_fumen(&mut self) -> Option<Page> {
+        let pasted =
+            ClipboardContext::new().and_then(|mut ctx: ClipboardContext| ctx.get_contents());
+        let page = match pasted {
+            Ok(fumen) => decode(&fumen),
+            Err(error) => Err(error.to_string()),
+        };
+        match page {
+            Ok(page) => Some(page),
+            Err(error) => {
+                println!("couldn't read the fumen: {error}");
+                self.mode_text.set_text(String::from("Not a fumen"));
+                None
+            }
+        }
+    }
+
+    /// Puts the grid and the tetromino of the fumen of the clipboard in the editor.
+    pub(super) fn paste_editor_fumen(&mut self) {
+        if let Some(page) = self.paste_fumen() {
+            self.editor.set_page(page);
+            self.mode_text.set_text(String::from("Fumen pasted"));
+            self.set_view(ViewState::Editor);
+        }
+    }
+
+    /// Starts a practice from the grid and the tetromino of the fumen of the clipboard.
+    pub(super) fn paste_practice_fumen(&mut self) {
+        if !matches!(self.game_mode(), GameMode::Practice { gravity: _ })
+            || self.running == RunningState::Starting
+        {
+            return;
+        }
+        if let Some(page) = self.paste_fumen() {
+            self.editor.set_page(page);
+            self.play_position();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_rows() -> Vec<Vec<Option<TetrisColor>>> {
+        vec![vec![None; NB_COLUMNS as usize]; NB_ROWS as usize]
+    }
+
+    fn sorted(mut cells: [(i8, i8); 4]) -> [(i8, i8); 4] {
+        cells.sort();
+        cells
+    }
+
+    fn same_tetromino(
+        a: Option<(TetrominoKind, [(i8, i8); 4])>,
+        b: Option<(TetrominoKind, [(i8, i8); 4])>,
+    ) -> bool {
+        match (a, b) {
+            (Some((kind_a, cells_a)), Some((kind_b, cells_b))) => {
+                kind_a == kind_b && sorted(cells_a) == sorted(cells_b)
+            }
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn empty_page_matches_the_editor() {
+        let page = Page {
+            rows: empty_rows(),
+            tetromino: None,
+        };
+        assert_eq!(encode(&[page]), "v115@vhAAgH");
+        let decoded = decode("v115@vhAAgH").unwrap();
+        assert!(decoded.rows == empty_rows());
+        assert!(decoded.tetromino.is_none());
+    }
+
+    #[test]
+    fn one_page_round_trip() {
+        let mut rows = empty_rows();
+        rows[NB_ROWS as usize - 1][1..].fill(Some(TetrisColor::Grey));
+        rows[NB_ROWS as usize - 2][9] = Some(TetrominoKind::J.get_color());
+        let tetromino = Some((TetrominoKind::T, [(3, 20), (4, 20), (5, 20), (4, 19)]));
+        let fumen = encode(&[Page {
+            rows: rows.clone(),
+            tetromino,
+        }]);
+        let decoded = decode(&fumen).unwrap();
+        assert!(decoded.rows == rows);
+        assert!(same_tetromino(decoded.tetromino, tetromino));
+    }
+
+    #[test]
+    fn repeated_fields_round_trip() {
+        let mut rows = empty_rows();
+        rows[NB_ROWS as usize - 1][..4].fill(Some(TetrisColor::Grey));
+        let cells = [(4, 21), (5, 21), (6, 21), (5, 20)];
+        let tetromino = Some((TetrominoKind::T, cells));
+        // the tetromino is locked before the next pages, which don't change the field
+        let mut locked = rows.clone();
+        for (x, y) in cells {
+            locked[y as usize][x as usize] = Some(TetrominoKind::T.get_color());
+        }
+        let pages = [
+            Page {
+                rows: rows.clone(),
+                tetromino,
+            },
+            Page {
+                rows: locked.clone(),
+                tetromino: None,
+            },
+            Page {
+                rows: locked.clone(),
+                tetromino: None,
+            },
+        ];
+        let fumen = encode(&pages);
+        let decoded = decode_pages(&fumen).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert!(decoded[0].rows == rows);
+        assert!(same_tetromino(decoded[0].tetromino, tetromino));
+        for page in &decoded[1..] {
+            assert!(page.rows == locked);
+            assert!(page.tetromino.is_none());
+        }
+        // the first unchanged field is written with its repeat count, the last one isn't written
+        let first = encode(&pages[..1]);
+        let data_len = |fumen: &str| fumen.len() - PREFIX.len();
+        assert_eq!(data_len(&fumen), data_len(&first) + 2 + 1 + 3 + 3);
+    }
+
+    #[test]
+    fn long_page_round_trip() {
+        let mut rows = empty_rows();
+        for (y, row) in rows.iter_mut().enumerate().skip(12) {
+            for (x, cell) in row.iter_mut().enumerate() {
+                if (x + y) % 2 == 0 {
+                    *cell = Some(TetrisColor::Grey);
+                }
+            }
+        }
+        let fumen = encode(&[Page {
+            rows: rows.clone(),
+            tetromino: None,
+        }]);
+        assert!(fumen.len() > PREFIX.len() + 42);
+        assert_eq!(fumen.chars().nth(PREFIX.len() + 42), Some('?'));
+        let decoded = decode(&fumen).unwrap();
+        assert!(decoded.rows == rows);
+    }
+}
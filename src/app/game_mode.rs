@@ -0,0 +1,850 @@
+//! Defines the [GameMode] of the single-player game and how [App] follows its goal.
+use super::records::{DigRecord, SprintRecord, ZenRecord};
+use super::{
+    App, ClearedLines, Modifiers, Outcome, Randomizer, Rules, RunningState, Speed, ViewState,
+};
+use crate::settings::{
+    gravity, BLOCK_SIZE, CLASSIC_ENTRY_DELAY, CLASSIC_GRAVITY, CLASSIC_MAX_START_LEVEL,
+    CLASSIC_POINTS, DEFAULT_FONT_SIZE, DEFAULT_GRID_Y, DEFAULT_WINDOW_WIDTH, DIG_MESSINESS,
+    DIG_RISE_SECONDS, DIG_ROWS, DIG_START_ROWS, LEADERBOARD_SIZE, LEVEL_GRAVITY,
+    MARATHON_LEVEL_LINES, MARATHON_LINES, MARATHON_MAX_START_LEVEL, MASTER_DELAYS, MASTER_GRADES,
+    MASTER_GRAVITY, MASTER_MAX_LEVEL, MASTER_TORIKANS, SPRINT_LINES, SPRINT_SPLIT_LINES,
+    TEXT_COLOR, ULTRA_SECONDS,
+};
+use crate::ui::text::Text;
+use crate::utils::formattings::format_seconds;
+use crate::PlayerConfig;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Number of best scores of the leaderboard shown when an ultra ends.
+const ULTRA_SHOWN_SCORES: usize = 5;
+
+/// Rules of a single-player game, deciding how fast the tetrominos fall and when the game ends.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum GameMode {
+    /// The game lasts until a game over, the tetrominos fall faster as time goes by.
+    Endless,
+    /// The lines must be cleared as fast as possible, the timer starts on the first input.
+    Sprint { lines: u64 },
+    /// As many lines as possible must be cleared before the time runs out.
+    Ultra { seconds: u64 },
+    /// The level goes up every MARATHON_LEVEL_LINES lines, the game is won once the lines are cleared.
+    ///
+    /// Without lines, it's the endless variant.
+    Marathon {
+        start_level: u32,
+        lines: Option<u64>,
+    },
+    /// The rows of garbage must be cleared as fast as possible while new ones rise, the timer starts on the first input.
+    Dig { rows: u64 },
+    /// The level rises with each tetromino and line until MASTER_MAX_LEVEL, the tetrominos falling up to 20G.
+    ///
+    /// A grade is given at the end according to the lines cleared.
+    Master,
+    /// The game never ends, the top of the stack is cleared when it reaches the top of the grid.
+    ///
+    /// The lines and pieces add up over all the sessions.
+    Zen,
+    /// Retro game following the rules and the speed of the NES, starting at the level chosen on the level select.
+    Classic { start_level: u32 },
+    /// The objective of the puzzle of the index among the loaded ones must be reached with the tetrominos given.
+    Puzzle { index: usize },
+    /// The setup of the opener of the index among the loaded ones must be built, as it is or mirrored.
+    Opener { index: usize },
+    /// Endless game whose placements can be undone and whose next tetromino can be chosen,
+    /// the tetrominos falling only with gravity.
+    Practice { gravity: bool },
+}
+
+/// Progress of the player in a [GameMode::Master].
+#[derive(Clone, Copy, Debug)]
+pub(super) struct MasterProgress {
+    level: u32,
+    /// Score deciding the grade, hidden during the game.
+    score: u64,
+    /// Combo multiplier of the score, growing as lines are cleared by consecutive tetrominos.
+    combo: u64,
+}
+
+impl GameMode {
+    /// Returns the mode following this one on the mode button of the single-player game.
+    pub fn next(&self) -> GameMode {
+        match self {
+            GameMode::Endless => GameMode::Sprint {
+                lines: SPRINT_LINES[0],
+            },
+            GameMode::Sprint { lines } => match SPRINT_LINES.iter().position(|l| l == lines) {
+                Some(i) if i + 1 < SPRINT_LINES.len() => GameMode::Sprint {
+                    lines: SPRINT_LINES[i + 1],
+                },
+                _ => GameMode::Ultra {
+                    seconds: ULTRA_SECONDS[0],
+                },
+            },
+            GameMode::Ultra { seconds } => match ULTRA_SECONDS.iter().position(|s| s == seconds) {
+                Some(i) if i + 1 < ULTRA_SECONDS.len() => GameMode::Ultra {
+                    seconds: ULTRA_SECONDS[i + 1],
+                },
+                _ => GameMode::Marathon {
+                    start_level: 1,
+                    lines: Some(MARATHON_LINES),
+                },
+            },
+            GameMode::Marathon {
+                start_level,
+                lines: Some(_),
+            } => GameMode::Marathon {
+                start_level: *start_level,
+                lines: None,
+            },
+            GameMode::Marathon {
+                start_level: _,
+                lines: None,
+            } => GameMode::Dig { rows: DIG_ROWS[0] },
+            GameMode::Dig { rows } => match DIG_ROWS.iter().position(|r| r == rows) {
+                Some(i) if i + 1 < DIG_ROWS.len() => GameMode::Dig {
+                    rows: DIG_ROWS[i + 1],
+                },
+                _ => GameMode::Master,
+            },
+            GameMode::Master => GameMode::Zen,
+            GameMode::Zen => GameMode::Classic { start_level: 0 },
+            GameMode::Classic { start_level: _ } => GameMode::Puzzle { index: 0 },
+            GameMode::Puzzle { index: _ } => GameMode::Opener { index: 0 },
+            GameMode::Opener { index: _ } => GameMode::Practice { gravity: false },
+            GameMode::Practice { gravity: false } => GameMode::Practice { gravity: true },
+            GameMode::Practice { gravity: true } => GameMode::Endless,
+        }
+    }
+
+    /// Returns the marathon starting at the next level, going back to the first after the last.
+    pub fn next_start_level(&self) -> GameMode {
+        match *self {
+            GameMode::Marathon { start_level, lines } => GameMode::Marathon {
+                start_level: start_level % MARATHON_MAX_START_LEVEL + 1,
+                lines,
+            },
+            GameMode::Classic { start_level } => GameMode::Classic {
+                start_level: (start_level + 1) % (CLASSIC_MAX_START_LEVEL + 1),
+            },
+            game_mode => game_mode,
+        }
+    }
+
+    /// Returns the puzzle or the opener following this one, going back to the first of the items after the last.
+    pub fn next_item(&self, nb_items: usize) -> GameMode {
+        match *self {
+            GameMode::Puzzle { index } => GameMode::Puzzle {
+                index: (index + 1) % nb_items,
+            },
+            GameMode::Opener { index } => GameMode::Opener {
+                index: (index + 1) % nb_items,
+            },
+            game_mode => game_mode,
+        }
+    }
+
+    /// Returns the level of a marathon or a classic game once the lines are cleared, or None in the other modes.
+    pub fn level(&self, cleared_lines: u64) -> Option<u32> {
+        match *self {
+            GameMode::Marathon {
+                start_level,
+                lines: _,
+            } => Some(start_level + (cleared_lines / MARATHON_LEVEL_LINES) as u32),
+            GameMode::Classic { start_level } => {
+                // like on the NES, the first level up takes more lines when starting at a high level
+                let start = start_level as u64;
+                let first_level_up =
+                    (10 * start + 10).min((10 * start).saturating_sub(50).max(100));
+                if cleared_lines < first_level_up {
+                    Some(start_level)
+                } else {
+                    Some(start_level + 1 + ((cleared_lines - first_level_up) / 10) as u32)
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the features of the game and the handling of the keys in this mode.
+    pub fn rules(&self) -> Rules {
+        match self {
+            GameMode::Classic { start_level: _ } => Rules::classic(),
+            // only the tetrominos of the puzzle are dealt
+            GameMode::Puzzle { index: _ } => Rules {
+                randomizer: Randomizer::Fixed,
+                ..Rules::default()
+            },
+            _ => Rules::default(),
+        }
+    }
+
+    /// Returns true if the clock waits for the first input of the player after the countdown.
+    pub fn starts_on_input(&self) -> bool {
+        matches!(
+            self,
+            GameMode::Sprint { lines: _ } | GameMode::Dig { rows: _ }
+        )
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameMode::Endless => write!(f, "Endless"),
+            GameMode::Sprint { lines } => write!(f, "Sprint {lines}L"),
+            GameMode::Ultra { seconds } if seconds.is_multiple_of(60) => {
+                write!(f, "Ultra {}min", seconds / 60)
+            }
+            GameMode::Ultra { seconds } => write!(f, "Ultra {seconds}s"),
+            GameMode::Marathon {
+                start_level: _,
+                lines: Some(lines),
+            } => write!(f, "Marathon {lines}L"),
+            GameMode::Marathon {
+                start_level: _,
+                lines: None,
+            } => write!(f, "Endless marathon"),
+            GameMode::Dig { rows } => write!(f, "Dig {rows}L"),
+            GameMode::Master => write!(f, "Master"),
+            GameMode::Zen => write!(f, "Zen"),
+            GameMode::Classic { start_level: _ } => write!(f, "Classic"),
+            GameMode::Puzzle { index: _ } => write!(f, "Puzzle"),
+            GameMode::Opener { index: _ } => write!(f, "Opener trainer"),
+            GameMode::Practice { gravity: false } => write!(f, "Practice"),
+            GameMode::Practice { gravity: true } => write!(f, "Practice with gravity"),
+        }
+    }
+}
+
+impl MasterProgress {
+    pub(super) fn new() -> MasterProgress {
+        MasterProgress {
+            level: 0,
+            score: 0,
+            combo: 1,
+        }
+    }
+
+    /// Returns how fast the tetrominos fall and lock at the current level.
+    fn speed(&self) -> Speed {
+        let &(_, fall_speed_divide, rows_per_fall) = MASTER_GRAVITY
+            .iter()
+            .rev()
+            .find(|(level, _, _)| *level <= self.level)
+            .unwrap();
+        let &(_, entry_delay, freeze) = MASTER_DELAYS
+            .iter()
+            .rev()
+            .find(|(level, _, _)| *level <= self.level)
+            .unwrap();
+        Speed {
+            fall_speed_divide,
+            rows_per_fall,
+            freeze,
+            entry_delay,
+        }
+    }
+
+    /// Scores the lines cleared by a locked tetromino and raises the level.
+    ///
+    /// Every tetromino raises the level by one, except at the level stops (the end of a hundred and the level before the last),
+    /// where only the cleared lines raise it.
+    fn lock(&mut self, cleared_lines: ClearedLines) {
+        let lines = cleared_lines.total;
+        if lines == 0 {
+            self.combo = 1;
+        } else {
+            self.combo += 2 * lines - 2;
+            let bravo = if cleared_lines.perfect_clear { 4 } else { 1 };
+            self.score += (self.level as u64 + lines).div_ceil(4) * lines * self.combo * bravo;
+            self.level = (self.level + lines as u32).min(MASTER_MAX_LEVEL);
+        }
+        if self.level % 100 != 99 && self.level + 1 < MASTER_MAX_LEVEL {
+            self.level += 1;
+        }
+    }
+
+    /// Returns the level ending the current section, shown next to the level.
+    fn section_end(&self) -> u32 {
+        ((self.level / 100 + 1) * 100).min(MASTER_MAX_LEVEL)
+    }
+
+    /// Returns the grade of the player, according to the score.
+    fn grade(&self) -> &'static str {
+        MASTER_GRADES
+            .iter()
+            .rev()
+            .find(|(score, _)| *score <= self.score)
+            .unwrap()
+            .1
+    }
+}
+
+impl App<'_> {
+    /// Returns the mode of the game, the multiplayer games are always endless.
+    pub(super) fn game_mode(&self) -> GameMode {
+        if self.player_config == PlayerConfig::Local {
+            self.game_mode
+        } else {
+            GameMode::Endless
+        }
+    }
+
+    /// Returns how fast the tetrominos fall and lock, given the time elapsed since the start of the game
+    /// and the progress of the first player.
+    pub(super) fn speed(&self) -> Speed {
+        let cleared_lines = match self.local_players.first() {
+            Some(player) => player.player_screen().score,
+            None => 0,
+        };
+        let (fall_speed_divide, freeze) = match self.game_mode() {
+            GameMode::Endless => gravity(self.clock),
+            // only the player gets faster in a race
+            GameMode::Sprint { lines: _ }
+            | GameMode::Ultra { seconds: _ }
+            | GameMode::Dig { rows: _ } => gravity(0.0),
+            // nothing hurries the player in a zen, a puzzle or a training
+            GameMode::Zen
+            | GameMode::Puzzle { index: _ }
+            | GameMode::Opener { index: _ }
+            | GameMode::Practice { gravity: true } => gravity(0.0),
+            // the tetromino never falls by itself and locks once dropped
+            GameMode::Practice { gravity: false } => (u64::MAX, gravity(0.0).1),
+            game_mode @ GameMode::Marathon {
+                start_level: _,
+                lines: _,
+            } => {
+                let (_, freeze) = gravity(0.0);
+                let level = game_mode.level(cleared_lines).unwrap() as usize;
+                let fall_speed_divide = LEVEL_GRAVITY[(level - 1).min(LEVEL_GRAVITY.len() - 1)];
+                (fall_speed_divide, freeze)
+            }
+            GameMode::Master => return self.master.speed(),
+            // the tetromino locks as soon as it can't fall anymore
+            game_mode @ GameMode::Classic { start_level: _ } => {
+                let level = game_mode.level(cleared_lines).unwrap() as usize;
+                return Speed {
+                    entry_delay: CLASSIC_ENTRY_DELAY,
+                    ..Speed::new(CLASSIC_GRAVITY[level.min(CLASSIC_GRAVITY.len() - 1)], 0)
+                };
+            }
+        };
+        Speed::new(fall_speed_divide, freeze)
+    }
+
+    /// Returns true while the clock of the game waits for the first input of the player.
+    pub(super) fn waits_for_first_input(&self) -> bool {
+        self.game_mode().starts_on_input() && self.local_players[0].stats().keys == 0
+    }
+
+    /// Returns the time left before the end of the game, or None if the mode has no time limit.
+    pub(super) fn time_left(&self) -> Option<f64> {
+        let GameMode::Ultra { seconds } = self.game_mode() else {
+            return None;
+        };
+        // the clock of the countdown isn't the one of the game
+        if self.running == RunningState::Starting {
+            Some(seconds as f64)
+        } else {
+            Some((seconds as f64 - self.clock).max(0.0))
+        }
+    }
+
+    /// Returns true if the time of the game runs out during the next update of dt seconds.
+    pub(super) fn is_time_up(&self, dt: f64) -> bool {
+        self.time_left().is_some_and(|time_left| time_left <= dt)
+    }
+
+    /// Ends the ultra, before the players are updated so that nothing counts after the time limit.
+    ///
+    /// The tetromino in the air isn't locked and the score is the one of the last locked tetromino.
+    pub(super) fn time_up(&mut self) {
+        let GameMode::Ultra { seconds } = self.game_mode() else {
+            unreachable!()
+        };
+        self.running = RunningState::NotRunning;
+        self.clock = seconds as f64;
+        let player = &mut self.local_players[0];
+        player.stop();
+        let stats = player.stats();
+        let score = player.player_screen().score;
+
+        let mut contents = vec![
+            String::from("Time's up!"),
+            format!("Score: {score}"),
+            format!("Pieces: {}", stats.pieces),
+            format!("PPS: {:.2}", stats.pieces as f64 / self.clock),
+            format!("KPP: {:.2}", stats.keys as f64 / stats.pieces.max(1) as f64),
+            format!("Modifiers: {}", self.modifiers),
+        ];
+        let rank = self.record_result(Outcome::Finished);
+        contents.push(match rank {
+            Some(rank) => format!("Rank: {} of {}", rank + 1, self.game_mode()),
+            None => format!("Not in the top {LEADERBOARD_SIZE}"),
+        });
+        for (i, other) in self
+            .results
+            .leaderboard(self.game_mode())
+            .iter()
+            .enumerate()
+            .take(ULTRA_SHOWN_SCORES)
+        {
+            let marker = if rank == Some(i) { " <" } else { "" };
+            // the modifiers are marked with a star as they aren't shown in full
+            let modified = if other.modifiers != Modifiers::default() {
+                "*"
+            } else {
+                ""
+            };
+            contents.push(format!(
+                "{}. {} lines{}{}",
+                i + 1,
+                other.score,
+                modified,
+                marker
+            ));
+        }
+        self.show_finish_screen(&contents);
+    }
+
+    /// Returns the score of the game: the points of a classic game, the level of a master or the lines cleared.
+    pub(super) fn mode_score(&self) -> u64 {
+        match self.game_mode() {
+            GameMode::Classic { start_level: _ } => self.classic_points,
+            GameMode::Master => self.master.level as u64,
+            _ => self.local_players[0].player_screen().score,
+        }
+    }
+
+    /// Resets the progress in the game mode and prepares the grid of the player, when the game starts.
+    pub(super) fn start_game_mode(&mut self) {
+        self.splits.clear();
+        self.garbage_rows = 0;
+        self.master = MasterProgress::new();
+        self.classic_points = 0;
+        self.opener_setups.clear();
+        self.finish_texts.clear();
+        let zen = self.game_mode() == GameMode::Zen;
+        let practice = matches!(self.game_mode(), GameMode::Practice { gravity: _ });
+        for player in &mut self.local_players {
+            player.set_top_out(!zen);
+            player.set_undoable(practice);
+        }
+        if zen {
+            // the totals include the previous session from now on
+            self.zen_start = self.records.zen.clone();
+            self.records.save();
+        }
+        if let GameMode::Dig { rows } = self.game_mode() {
+            self.garbage_rows = rows.min(DIG_START_ROWS);
+            self.local_players[0].add_garbage_rows(self.garbage_rows, DIG_MESSINESS);
+        }
+        self.mode_text.set_text(self.initial_mode_text());
+    }
+
+    /// Returns the description of the progress shown under the timer at the start of a game.
+    pub(super) fn initial_mode_text(&self) -> String {
+        match self.game_mode() {
+            GameMode::Marathon {
+                start_level,
+                lines: _,
+            } => format!("Level: {start_level}"),
+            GameMode::Dig { rows } => format!("Garbage: 0/{rows}"),
+            GameMode::Master => format!("Level: 0/{}", MasterProgress::new().section_end()),
+            GameMode::Zen => zen_description(&self.records.zen),
+            GameMode::Classic { start_level } => format!("Points: 0, level: {start_level}"),
+            GameMode::Puzzle { index } => format!("Objective: {}", self.puzzles[index].objective),
+            GameMode::Opener { index } => format!("Opener: {}", self.openers[index].name),
+            _ => String::new(),
+        }
+    }
+
+    /// Follows the progress of the player towards the goal of the mode, once the players are updated.
+    pub(super) fn update_game_mode(&mut self) {
+        let score = self.local_players[0].player_screen().score;
+        let new_locks = self.take_new_locks();
+        match self.game_mode() {
+            GameMode::Sprint { lines } => {
+                let cleared_lines = score.min(lines);
+                while (self.splits.len() as u64 + 1) * SPRINT_SPLIT_LINES <= cleared_lines {
+                    self.splits.push(self.clock);
+                    let split = self.split_description(lines, self.splits.len() - 1);
+                    self.mode_text.set_text(split);
+                }
+                if cleared_lines == lines {
+                    self.finish_sprint(lines);
+                }
+            }
+            game_mode @ GameMode::Marathon {
+                start_level: _,
+                lines,
+            } => {
+                let level = game_mode.level(score).unwrap();
+                self.mode_text.set_text(format!("Level: {level}"));
+                if lines.is_some_and(|lines| score >= lines) {
+                    self.finish_marathon(level);
+                }
+            }
+            GameMode::Dig { rows } => {
+                // the rows rise with time until all the rows to clear were in the grid
+                let risen_rows = (self.clock / DIG_RISE_SECONDS) as u64;
+                let expected_rows = (rows.min(DIG_START_ROWS) + risen_rows).min(rows);
+                if expected_rows > self.garbage_rows {
+                    self.local_players[0]
+                        .add_garbage_rows(expected_rows - self.garbage_rows, DIG_MESSINESS);
+                    self.garbage_rows = expected_rows;
+                }
+                let cleared_rows = self.local_players[0].stats().garbage_lines;
+                self.mode_text
+                    .set_text(format!("Garbage: {cleared_rows}/{rows}"));
+                if cleared_rows >= rows {
+                    self.finish_dig(rows);
+                }
+            }
+            GameMode::Master => {
+                if self.follow_master(new_locks) {
+                    return;
+                }
+                self.mode_text.set_text(format!(
+                    "Level: {}/{}",
+                    self.master.level,
+                    self.master.section_end()
+                ));
+            }
+            GameMode::Zen => {
+                let stats = self.local_players[0].stats();
+                self.records.zen = ZenRecord {
+                    lines: self.zen_start.lines + score,
+                    pieces: self.zen_start.pieces + stats.pieces,
+                    top_clears: self.zen_start.top_clears + stats.top_clears,
+                };
+                self.mode_text.set_text(zen_description(&self.records.zen));
+            }
+            game_mode @ GameMode::Classic { start_level: _ } => {
+                // the points of a clear depend on the level before it
+                let mut lines = score - new_locks.iter().map(|lock| lock.total).sum::<u64>();
+                for cleared_lines in new_locks {
+                    if cleared_lines.total > 0 {
+                        let level = game_mode.level(lines).unwrap() as u64;
+                        let points = CLASSIC_POINTS[cleared_lines.total as usize - 1];
+                        self.classic_points += points * (level + 1);
+                        lines += cleared_lines.total;
+                    }
+                }
+                self.mode_text.set_text(format!(
+                    "Points: {}, level: {}",
+                    self.classic_points,
+                    game_mode.level(score).unwrap()
+                ));
+            }
+            GameMode::Puzzle { index } => {
+                self.follow_puzzle(index, new_locks);
+            }
+            GameMode::Opener { index } => {
+                self.follow_opener(index, new_locks);
+            }
+            GameMode::Endless
+            | GameMode::Ultra { seconds: _ }
+            | GameMode::Practice { gravity: _ } => {}
+        }
+    }
+
+    /// Shows the title of the level select over the grid between two classic games,
+    /// and returns the maximal start level to choose from, or None if the level select isn't shown.
+    pub(super) fn prepare_level_select(&mut self) -> Option<u32> {
+        if !matches!(self.game_mode(), GameMode::Classic { start_level: _ })
+            || self.running != RunningState::NotRunning
+        {
+            return None;
+        }
+        if self.finish_texts.is_empty() {
+            self.show_finish_screen(&[String::from("Select a level")]);
+        }
+        Some(CLASSIC_MAX_START_LEVEL)
+    }
+
+    /// Shows or hides the buttons of the level select when a classic game ends or restarts.
+    pub(super) fn refresh_level_select(&mut self) {
+        if self.view_state == ViewState::Local
+            && matches!(self.game_mode(), GameMode::Classic { start_level: _ })
+        {
+            self.set_view(ViewState::Local);
+        }
+    }
+
+    /// Saves the totals of the zen being played, so that the next session adds up to them.
+    pub(super) fn save_zen(&self) {
+        if self.game_mode() == GameMode::Zen && self.running != RunningState::Starting {
+            self.records.save();
+        }
+    }
+
+    /// Shows the results of the game mode when the game is over, before its goal was reached.
+    pub(super) fn game_over_game_mode(&mut self) {
+        match self.game_mode() {
+            GameMode::Master => {
+                // the lines cleared right before the game over still count
+                let new_locks = self.take_new_locks();
+                if !self.follow_master(new_locks) {
+                    self.finish_master("Game over");
+                }
+            }
+            GameMode::Classic { start_level: _ } => {
+                self.update_game_mode();
+                self.record_result(Outcome::GameOver);
+                self.show_finish_screen(&[
+                    String::from("Game over"),
+                    format!("Points: {}", self.classic_points),
+                    format!("Modifiers: {}", self.modifiers),
+                    String::from("Select a level"),
+                ]);
+                self.refresh_level_select();
+            }
+            GameMode::Puzzle { index } => {
+                // the last tetromino was locked or the stack topped out
+                let new_locks = self.take_new_locks();
+                if !self.follow_puzzle(index, new_locks) {
+                    self.finish_puzzle(index, false);
+                }
+            }
+            GameMode::Opener { index } => {
+                let new_locks = self.take_new_locks();
+                if !self.follow_opener(index, new_locks) {
+                    self.finish_opener(index, Err("Game over"));
+                }
+            }
+            _ if self.view_state == ViewState::Local => {
+                self.record_result(Outcome::GameOver);
+                self.show_game_over_stats();
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the lines cleared by each tetromino locked by the first player since the last call.
+    ///
+    /// The locks of the other players are dropped so that they don't pile up.
+    fn take_new_locks(&mut self) -> Vec<ClearedLines> {
+        let mut new_locks = Vec::new();
+        for (id, player) in self.local_players.iter_mut().enumerate() {
+            let locks = player.take_new_locks();
+            if id == 0 {
+                new_locks = locks;
+            }
+        }
+        new_locks
+    }
+
+    /// Raises the level of the master with the locked tetrominos, and ends it once the last level is reached
+    /// or if a level is reached too late.
+    ///
+    /// Returns true if the master ended.
+    fn follow_master(&mut self, new_locks: Vec<ClearedLines>) -> bool {
+        for cleared_lines in new_locks {
+            let previous_level = self.master.level;
+            self.master.lock(cleared_lines);
+            if let Some(&(level, _)) = MASTER_TORIKANS.iter().find(|(level, time_limit)| {
+                previous_level < *level && *level <= self.master.level && self.clock > *time_limit
+            }) {
+                self.master.level = level;
+                self.finish_master("Time limit reached");
+                return true;
+            }
+            if self.master.level == MASTER_MAX_LEVEL {
+                self.finish_master("Congratulations!");
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Ends the master and reveals the grade of the player.
+    fn finish_master(&mut self, title: &str) {
+        self.running = RunningState::NotRunning;
+        let player = &mut self.local_players[0];
+        player.stop();
+        let outcome = if player.get_game_over() {
+            Outcome::GameOver
+        } else {
+            Outcome::Finished
+        };
+        let stats = player.stats();
+        let contents = [
+            String::from(title),
+            format!("Grade: {}", self.master.grade()),
+            format!("Level: {}", self.master.level),
+            format!("Time: {}", format_seconds(self.clock)),
+            format!("Lines: {}", player.player_screen().score),
+            format!("Pieces: {}", stats.pieces),
+            format!("PPS: {:.2}", stats.pieces as f64 / self.clock),
+            format!("Modifiers: {}", self.modifiers),
+        ];
+        self.record_result(outcome);
+        self.show_finish_screen(&contents);
+    }
+
+    /// Ends the dig race, shows its results on the finish screen and saves it if it's a personal best.
+    fn finish_dig(&mut self, rows: u64) {
+        self.running = RunningState::NotRunning;
+        let player = &mut self.local_players[0];
+        player.stop();
+        let stats = player.stats();
+        let record = DigRecord {
+            time: self.clock,
+            pieces: stats.pieces,
+            keys: stats.keys,
+            lines: player.player_screen().score,
+            modifiers: self.modifiers,
+        };
+
+        let previous_best = self.records.dig.get(&rows);
+        // the modifiers change the game too much to compare its time with the personal best of the plain dig race
+        let is_best = record.modifiers == Modifiers::default()
+            && match previous_best {
+                Some(best) => record.time < best.time,
+                None => true,
+            };
+        let mut contents = vec![
+            if is_best {
+                String::from("New personal best!")
+            } else {
+                format!("{} finished!", self.game_mode())
+            },
+            format!("Time: {}", format_seconds(record.time)),
+            format!("Garbage cleared: {rows}"),
+            format!("Lines cleared: {}", record.lines),
+            format!("Pieces: {}", record.pieces),
+            format!("PPS: {:.2}", record.pieces as f64 / record.time),
+            format!(
+                "KPP: {:.2}",
+                record.keys as f64 / record.pieces.max(1) as f64
+            ),
+            format!("Modifiers: {}", record.modifiers),
+        ];
+        if let (false, Some(best)) = (is_best, previous_best) {
+            contents.insert(2, format!("Best: {}", format_seconds(best.time)));
+        }
+        self.record_result(Outcome::Finished);
+        self.show_finish_screen(&contents);
+
+        if is_best {
+            self.records.dig.insert(rows, record);
+            self.records.save();
+        }
+    }
+
+    /// Ends the marathon in victory and congratulates the player.
+    fn finish_marathon(&mut self, level: u32) {
+        self.running = RunningState::NotRunning;
+        let player = &mut self.local_players[0];
+        player.stop();
+        let score = player.player_screen().score;
+        let stats = player.stats();
+        let contents = [
+            String::from("Congratulations!"),
+            format!("You won the {}", self.game_mode()),
+            format!("Lines: {score}"),
+            format!("Level: {level}"),
+            format!("Time: {}", format_seconds(self.clock)),
+            format!("Pieces: {}", stats.pieces),
+            format!("PPS: {:.2}", stats.pieces as f64 / self.clock),
+            format!("KPP: {:.2}", stats.keys as f64 / stats.pieces.max(1) as f64),
+            format!("Modifiers: {}", self.modifiers),
+        ];
+        self.record_result(Outcome::Finished);
+        self.show_finish_screen(&contents);
+    }
+
+    /// Returns the i-th split time of the sprint compared to the one of the personal best.
+    fn split_description(&self, lines: u64, i: usize) -> String {
+        let split = self.splits[i];
+        let mut description = format!("{}L: {:.2}s", (i as u64 + 1) * SPRINT_SPLIT_LINES, split);
+        if let Some(best) = self
+            .records
+            .sprint
+            .get(&lines)
+            .and_then(|record| record.splits.get(i))
+        {
+            description += &format!(" ({:+.2})", split - best);
+        }
+        description
+    }
+
+    /// Ends the sprint, shows its results on the finish screen and saves it if it's a personal best.
+    fn finish_sprint(&mut self, lines: u64) {
+        self.running = RunningState::NotRunning;
+        let player = &mut self.local_players[0];
+        player.stop();
+        let stats = player.stats();
+        let record = SprintRecord {
+            time: self.clock,
+            pieces: stats.pieces,
+            keys: stats.keys,
+            splits: self.splits.clone(),
+            modifiers: self.modifiers,
+        };
+
+        let previous_best = self.records.sprint.get(&lines);
+        // the modifiers change the game too much to compare its time with the personal best of the plain sprint
+        let is_best = record.modifiers == Modifiers::default()
+            && match previous_best {
+                Some(best) => record.time < best.time,
+                None => true,
+            };
+        let mut contents = vec![
+            if is_best {
+                String::from("New personal best!")
+            } else {
+                format!("{} finished!", self.game_mode())
+            },
+            format!("Time: {}", format_seconds(record.time)),
+            format!("Pieces: {}", record.pieces),
+            format!("PPS: {:.2}", record.pieces as f64 / record.time),
+            format!(
+                "KPP: {:.2}",
+                record.keys as f64 / record.pieces.max(1) as f64
+            ),
+            format!("Modifiers: {}", record.modifiers),
+        ];
+        if let (false, Some(best)) = (is_best, previous_best) {
+            contents.insert(2, format!("Best: {}", format_seconds(best.time)));
+        }
+        for i in 0..self.splits.len() {
+            contents.push(self.split_description(lines, i));
+        }
+        self.record_result(Outcome::Finished);
+        self.show_finish_screen(&contents);
+
+        if is_best {
+            self.records.sprint.insert(lines, record);
+            self.records.save();
+        }
+    }
+
+    /// Shows the lines of results over the grid until the next game.
+    pub(super) fn show_finish_screen(&mut self, contents: &[String]) {
+        self.finish_texts = contents
+            .iter()
+            .enumerate()
+            .map(|(i, content)| {
+                Text::new(
+                    content,
+                    DEFAULT_FONT_SIZE,
+                    DEFAULT_WINDOW_WIDTH as f64 / 2.0,
+                    DEFAULT_GRID_Y + (3.0 + 1.2 * i as f64) * BLOCK_SIZE,
+                    *TEXT_COLOR,
+                )
+            })
+            .collect();
+    }
+}
+
+/// Returns the totals of the zen sessions shown under the timer, with the number of times the top was cleared as a penalty.
+fn zen_description(zen: &ZenRecord) -> String {
+    format!(
+        "Total: {} lines, {} pieces, {} penalties",
+        zen.lines, zen.pieces, zen.top_clears
+    )
+}
@@ -0,0 +1,127 @@
+//! Defines the [Launch]es of the game chosen on the command line, which skip the menus.
+use super::{remote::MessageType, App, GameMode, ViewState};
+use crate::settings::{
+    DIG_ROWS, GUEST_PORT, HOST_PORT, MARATHON_LINES, SPRINT_LINES, ULTRA_SECONDS,
+};
+use crate::PlayerConfig;
+use local_ip_address::local_ip;
+use rand::Rng;
+
+/// What the game opens on.
+#[derive(Debug, PartialEq)]
+pub enum Launch {
+    MainMenu,
+    /// A single-player game of the mode, started right away.
+    Local(GameMode),
+    /// A two-player game on the same keyboard, started right away.
+    TwoLocal,
+    /// A room waiting for a guest, listening on the address if given, on the local IP and HOST_PORT otherwise.
+    Host(Option<String>),
+    /// The room listening on the address.
+    Join(String),
+}
+
+impl GameMode {
+    /// Names of the modes on the command line, each one being the first variant of the mode on the mode button.
+    pub const NAMES: [&'static str; 11] = [
+        "endless", "sprint", "ultra", "marathon", "dig", "master", "zen", "classic", "puzzle",
+        "opener", "practice",
+    ];
+
+    /// Returns the mode with the name, in any case, if it's one of [GameMode::NAMES].
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        let game_mode = match name.to_lowercase().as_str() {
+            "endless" => GameMode::Endless,
+            "sprint" => GameMode::Sprint {
+                lines: SPRINT_LINES[0],
+            },
+            "ultra" => GameMode::Ultra {
+                seconds: ULTRA_SECONDS[0],
+            },
+            "marathon" => GameMode::Marathon {
+                start_level: 1,
+                lines: Some(MARATHON_LINES),
+            },
+            "dig" => GameMode::Dig { rows: DIG_ROWS[0] },
+            "master" => GameMode::Master,
+            "zen" => GameMode::Zen,
+            "classic" => GameMode::Classic { start_level: 0 },
+            "puzzle" => GameMode::Puzzle { index: 0 },
+            "opener" => GameMode::Opener { index: 0 },
+            "practice" => GameMode::Practice { gravity: false },
+            _ => return None,
+        };
+        Some(game_mode)
+    }
+}
+
+/// Returns the address with the local IP and the port, or why the local IP can't be found.
+pub(super) fn local_address(port: &str) -> Result<String, String> {
+    local_ip()
+        .map(|ip| ip.to_string() + port)
+        .map_err(|error| format!("couldn't find the local IP address: {error}"))
+}
+
+impl App<'_> {
+    /// Makes every game use the seed instead of a random one, so that the tetrominos come in the same order.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    /// Returns the seed of a new game, the one given with [set_seed()](App::set_seed()) if any.
+    pub(super) fn new_seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| rand::thread_rng().gen())
+    }
+
+    /// Returns the address on which a hosted room listens, or why the local IP can't be found.
+    pub(super) fn host_address(&self) -> Result<String, String> {
+        match &self.host_address {
+            Some(address) => Ok(address.clone()),
+            None => local_address(&HOST_PORT),
+        }
+    }
+
+    /// Joins the room listening on remote_ip, listening on local_ip for its messages.
+    pub(super) fn join_room(&mut self, local_ip: String, remote_ip: String) {
+        self.set_player_config(PlayerConfig::TwoRemote {
+            local_ip: local_ip.clone(),
+            remote_ip,
+        });
+        self.set_view(ViewState::Remote);
+        self.send_message(MessageType::Hello(local_ip));
+        self.local_players[0].send_serialized();
+    }
+
+    /// Opens the game on the launch instead of the main menu.
+    ///
+    /// Fails if the launch needs the local IP address and the computer has none.
+    pub fn launch(&mut self, launch: Launch) -> Result<(), String> {
+        match launch {
+            Launch::MainMenu => {}
+            Launch::Local(game_mode) => {
+                self.game_mode = game_mode;
+                self.mode_text.set_text(self.initial_mode_text());
+                self.set_view(ViewState::Local);
+                self.restart();
+            }
+            Launch::TwoLocal => {
+                self.set_player_config(PlayerConfig::TwoLocal);
+                self.set_view(ViewState::TwoLocal);
+                self.restart();
+            }
+            Launch::Host(address) => {
+                let address = match address {
+                    Some(address) => address,
+                    None => local_address(&HOST_PORT)?,
+                };
+                self.host_address = Some(address);
+                self.set_view(ViewState::CreateRoom);
+            }
+            Launch::Join(remote_ip) => {
+                let local_ip = local_address(&GUEST_PORT)?;
+                self.join_room(local_ip, remote_ip);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,314 @@
+//! Defines the [Opener]s of the opener trainer, read from files of setups, and how [App] checks the placements.
+use super::records::OpenerRecord;
+use super::{
+    App, ClearedLines, GameMode, Modifiers, Outcome, RunningState, TetrisGrid, TetrominoKind,
+    ViewState,
+};
+use crate::settings::{NB_COLUMNS, NB_ROWS};
+use crate::utils::{formattings::format_seconds, storage};
+use serde::Deserialize;
+
+/// Openers shipped with the game, the other ones are in the files of OPENERS_DIR in the data directory.
+static BUILT_IN_OPENERS: &str = include_str!("../../openers/openers.json");
+static OPENERS_DIR: &str = "openers";
+
+/// Setup to build with the first tetrominos of a game, placement by placement.
+#[derive(Deserialize, Clone)]
+pub struct Opener {
+    pub name: String,
+    /// Tetrominos dealt first, the next ones being dealt by bags of 7. Without any, all the bags are random.
+    #[serde(default)]
+    pub sequence: Vec<TetrominoKind>,
+    /// Tetrominos placed with each bag, as rows at the bottom of the grid from the highest one.
+    ///
+    /// A '.' is a cell without a block of the bag, any other character is the letter of the tetromino of the block,
+    /// which appears at most once in a bag.
+    pub bags: Vec<Vec<String>>,
+}
+
+/// Cells a tetromino must be locked on to build the setup.
+#[derive(Clone)]
+pub(super) struct Target {
+    kind: TetrominoKind,
+    cells: Vec<(usize, usize)>,
+}
+
+/// Returns the built-in openers followed by the ones of the data directory.
+///
+/// The openers that can't be built are left out.
+pub(super) fn load_openers() -> Vec<Opener> {
+    let built_in: Vec<Opener> =
+        serde_json::from_str(BUILT_IN_OPENERS).expect("the built-in openers can be read");
+    built_in
+        .into_iter()
+        .chain(
+            storage::load_all::<Vec<Opener>>(OPENERS_DIR)
+                .into_iter()
+                .flatten(),
+        )
+        .filter(|opener| match opener.targets() {
+            Ok(_) => true,
+            Err(error) => {
+                println!("left out the opener {}: {error}", opener.name);
+                false
+            }
+        })
+        .collect()
+}
+
+impl Opener {
+    /// Returns the placements of the setup, or an error if the setup can't be built.
+    fn targets(&self) -> Result<Vec<Target>, String> {
+        let mut targets: Vec<Target> = vec![];
+        for bag in &self.bags {
+            if bag.len() > (NB_ROWS - 2) as usize {
+                return Err(format!("a bag is higher than {} rows", NB_ROWS - 2));
+            }
+            let mut bag_targets: Vec<Target> = vec![];
+            // the rows are at the bottom of the grid
+            let top = NB_ROWS as usize - bag.len();
+            for (i, row) in bag.iter().enumerate() {
+                if row.chars().count() != NB_COLUMNS as usize {
+                    return Err(format!("the row \"{row}\" isn't {NB_COLUMNS} cells wide"));
+                }
+                for (x, c) in row.chars().enumerate().filter(|(_, c)| *c != '.') {
+                    let kind = kind(c).ok_or(format!("'{c}' isn't a tetromino"))?;
+                    match bag_targets.iter_mut().find(|target| target.kind == kind) {
+                        Some(target) => target.cells.push((x, top + i)),
+                        None => bag_targets.push(Target {
+                            kind,
+                            cells: vec![(x, top + i)],
+                        }),
+                    }
+                }
+            }
+            if let Some(target) = bag_targets.iter().find(|target| target.cells.len() != 4) {
+                return Err(format!(
+                    "{} blocks of {}",
+                    target.cells.len(),
+                    target.kind.get()
+                ));
+            }
+            targets.extend(bag_targets);
+        }
+        let mut rows = vec![0; NB_ROWS as usize];
+        for (x, y) in targets.iter().flat_map(|target| target.cells.iter()) {
+            if rows[*y] & (1 << x) != 0 {
+                return Err(format!("two bags fill the cell ({x}, {y})"));
+            }
+            rows[*y] |= 1 << x;
+        }
+        // the setup would be broken by the clear
+        if rows.iter().any(|row| *row == (1 << NB_COLUMNS) - 1) {
+            return Err(String::from("a row is full"));
+        }
+        Ok(targets)
+    }
+
+    /// Returns how the tetrominos are dealt, shown before the opener is started.
+    fn sequence_description(&self) -> String {
+        if self.sequence.is_empty() {
+            String::from("Sequence: random bags")
+        } else {
+            let kinds: Vec<String> = self.sequence.iter().map(TetrominoKind::get).collect();
+            format!("Sequence: {}", kinds.join(" "))
+        }
+    }
+}
+
+impl Target {
+    /// Returns the target of the setup flipped horizontally.
+    fn mirrored(&self) -> Target {
+        let kind = match self.kind {
+            TetrominoKind::S => TetrominoKind::Z,
+            TetrominoKind::Z => TetrominoKind::S,
+            TetrominoKind::J => TetrominoKind::L,
+            TetrominoKind::L => TetrominoKind::J,
+            kind => kind,
+        };
+        let cells = self
+            .cells
+            .iter()
+            .map(|(x, y)| (NB_COLUMNS as usize - 1 - x, *y))
+            .collect();
+        Target { kind, cells }
+    }
+}
+
+/// Returns true if every block of the grid is on a cell of the setup, with the color of the tetromino of the cell.
+fn follows(setup: &[Target], grid: &TetrisGrid) -> bool {
+    grid.rows().iter().enumerate().all(|(y, row)| {
+        row.iter().enumerate().all(|(x, cell)| match cell {
+            Some(color) => setup
+                .iter()
+                .any(|target| target.cells.contains(&(x, y)) && *color == target.kind.get_color()),
+            None => true,
+        })
+    })
+}
+
+/// Returns true if all the cells of the setup are filled.
+fn is_built(setup: &[Target], grid: &TetrisGrid) -> bool {
+    setup
+        .iter()
+        .flat_map(|target| target.cells.iter())
+        .all(|(x, y)| grid.rows()[*y][*x].is_some())
+}
+
+impl App<'_> {
+    /// Starts building the opener right away, without a countdown so that it can be retried instantly.
+    pub(super) fn start_opener(&mut self, index: usize) {
+        let opener = self.openers[index].clone();
+        let rules = self.game_mode().rules();
+        // the setups are made for a grid of the default size which stays the same way round
+        let modifiers = Modifiers {
+            big: false,
+            mirror: false,
+            ..self.modifiers
+        };
+        self.settings_manager.seed = self.new_seed();
+        let player = &mut self.local_players[0];
+        player.set_rules(rules);
+        player.set_modifiers(modifiers);
+        player.renew(self.settings_manager.seed);
+        if !opener.sequence.is_empty() {
+            player.load(&[], &opener.sequence, None);
+        }
+        self.start_game_mode();
+        let setup = opener.targets().unwrap();
+        let mirrored = setup.iter().map(Target::mirrored).collect();
+        self.opener_setups = vec![setup, mirrored];
+        self.clock = 0.0;
+        self.running = RunningState::Running;
+        self.set_view(ViewState::Local);
+    }
+
+    /// Checks the placements of the tetrominos locked since the last call against the setup and its mirror,
+    /// and ends the attempt once the setup is built or broken.
+    ///
+    /// Returns true if the attempt ended.
+    pub(super) fn follow_opener(&mut self, index: usize, new_locks: Vec<ClearedLines>) -> bool {
+        if new_locks.is_empty() {
+            return false;
+        }
+        if new_locks.iter().any(|lock| lock.total > 0) {
+            self.finish_opener(index, Err("A line was cleared"));
+            return true;
+        }
+        let grid = &self.local_players[0].player_screen().grid;
+        self.opener_setups.retain(|setup| follows(setup, grid));
+        match self
+            .opener_setups
+            .iter()
+            .position(|setup| is_built(setup, grid))
+        {
+            Some(variant) => self.finish_opener(index, Ok(variant == 1)),
+            None if self.opener_setups.is_empty() => {
+                self.finish_opener(index, Err("Wrong placement"))
+            }
+            None => return false,
+        }
+        true
+    }
+
+    /// Ends the attempt, either built (mirrored or not) or failed for the given reason, and counts it in the records.
+    pub(super) fn finish_opener(&mut self, index: usize, result: Result<bool, &str>) {
+        self.running = RunningState::NotRunning;
+        self.opener_setups.clear();
+        let player = &mut self.local_players[0];
+        player.stop();
+        let stats = player.stats();
+        let opener = &self.openers[index];
+        let record = self.records.openers.entry(opener.name.clone()).or_default();
+        record.attempts += 1;
+        let mut contents = match result {
+            Ok(false) => vec![String::from("Setup built!")],
+            Ok(true) => vec![String::from("Mirrored setup built!")],
+            Err(reason) => vec![String::from(reason)],
+        };
+        if result.is_ok() {
+            record.successes += 1;
+        }
+        contents.extend([
+            opener.name.clone(),
+            format!("Time: {}", format_seconds(self.clock)),
+            format!("Pieces: {}", stats.pieces),
+            success_rate(record),
+            String::from("Press R to retry"),
+        ]);
+        self.records.save();
+        self.record_result(if result.is_ok() {
+            Outcome::Finished
+        } else {
+            Outcome::Failed
+        });
+        self.show_finish_screen(&contents);
+    }
+
+    /// Shows the opener over the grid before it's started.
+    pub(super) fn prepare_opener_intro(&mut self) {
+        let GameMode::Opener { index } = self.game_mode() else {
+            return;
+        };
+        if self.running != RunningState::NotRunning || !self.finish_texts.is_empty() {
+            return;
+        }
+        let opener = &self.openers[index];
+        let record = self
+            .records
+            .openers
+            .get(&opener.name)
+            .copied()
+            .unwrap_or_default();
+        let contents = [
+            opener.name.clone(),
+            format!("Bags: {}", opener.bags.len()),
+            opener.sequence_description(),
+            success_rate(&record),
+            String::from("Mirrored setups count too"),
+        ];
+        self.show_finish_screen(&contents);
+    }
+
+    /// Returns the cells of the setup still to fill, outlined on the grid during the attempt.
+    pub(super) fn opener_outlines(&self) -> Vec<(usize, usize)> {
+        let (Some(setup), Some(player)) = (self.opener_setups.first(), self.local_players.first())
+        else {
+            return vec![];
+        };
+        let grid = &player.player_screen().grid;
+        setup
+            .iter()
+            .flat_map(|target| target.cells.iter().copied())
+            .filter(|(x, y)| grid.rows()[*y][*x].is_none())
+            .collect()
+    }
+}
+
+/// Returns the tetromino of the letter of a setup.
+fn kind(c: char) -> Option<TetrominoKind> {
+    match c {
+        'I' => Some(TetrominoKind::I),
+        'O' => Some(TetrominoKind::O),
+        'T' => Some(TetrominoKind::T),
+        'S' => Some(TetrominoKind::S),
+        'Z' => Some(TetrominoKind::Z),
+        'J' => Some(TetrominoKind::J),
+        'L' => Some(TetrominoKind::L),
+        _ => None,
+    }
+}
+
+/// Returns the description of the success rate of the opener.
+fn success_rate(record: &OpenerRecord) -> String {
+    if record.attempts == 0 {
+        String::from("Success rate: no attempt yet")
+    } else {
+        format!(
+            "Success rate: {}/{} ({:.0}%)",
+            record.successes,
+            record.attempts,
+            100.0 * record.successes as f64 / record.attempts as f64
+        )
+    }
+}
@@ -5,22 +5,31 @@
 mod back_end;
 mod circular_buffer;
 mod handle_key_player;
+mod history;
 mod local_player;
+mod pc_hint;
 mod player_screen;
 mod pressed_keys;
 mod update_player;
 
-pub use self::back_end::Tetromino;
+pub use self::back_end::{
+    finesse, lines_of_garbage, reachable_placements, Bitboard, ClearedLines, Move, Occupancy,
+    PcProblem, PcStep, Placement, Randomizer, RotationSystem, TSpin, TetrisGrid, Tetromino,
+    TetrominoKind,
+};
 use self::{
-    back_end::{TetrisGrid, TetrominoKind},
-    circular_buffer::CircularBuffer,
-    pressed_keys::PressedKeys,
+    circular_buffer::CircularBuffer, history::History, pc_hint::PcHint, pressed_keys::PressedKeys,
+};
+use crate::assets::TetrisColor;
+use crate::settings::{
+    CLASSIC_AUTO_REPEAT_RATE, CLASSIC_AUTO_SHIFT_DELAY, KEY_REPEAT_DELAY, KEY_REPEAT_RATE,
+    NB_NEXT_TETROMINO,
 };
-use crate::settings::NB_NEXT_TETROMINO;
 use rand::SeedableRng;
 use rand_pcg::Pcg32;
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
 
 /// Local player contains all the informations relative to one player.
 ///
@@ -47,9 +56,262 @@ pub struct LocalPlayer {
     remote_ip: String,
     /// garbage_to_be_added is set before the update and reset during the update.
     garbage_to_be_added: u64,
+    /// Number of tetromino in a bag.
+    bag_size: u32,
     /// Random generator for the next pieces of tetromino.
     #[serde(skip, default = "new_pcg")]
     rng: Pcg32,
+    /// Random generator for the empty column of the garbage, independent from the pieces.
+    #[serde(skip, default = "new_pcg")]
+    garbage_rng: Pcg32,
+    /// Perfect clear solver suggesting the next placement, only when practicing.
+    #[serde(skip)]
+    pc_hint: Option<PcHint>,
+    /// Counters of the current game.
+    stats: PlayerStats,
+    /// Lines cleared by each tetromino locked since the last [take_new_locks()](LocalPlayer::take_new_locks()).
+    #[serde(skip)]
+    new_locks: Vec<ClearedLines>,
+    /// Frame of the last update and entry delay given then, to delay the next tetromino when one is locked.
+    frame_counter: u64,
+    entry_delay: u64,
+    /// The active tetromino waits at the top of the grid until this frame.
+    entry_frame: u64,
+    /// Whether reaching the top of the grid is a game over, otherwise the top of the stack is cleared.
+    top_out: bool,
+    /// Features and handling of the game, set by the game mode.
+    rules: Rules,
+    /// DAS and ARR preferred by the player, which replace the ones of the rules with the modern handling.
+    handling: Option<(u64, u64)>,
+    /// Last kind of tetromino dealt, for the randomizers depending on it.
+    last_dealt: Option<TetrominoKind>,
+    /// Changes to the game chosen by the player on top of the game mode.
+    modifiers: Modifiers,
+    /// Cells of the active tetromino after its last rotation and the index of the wall-kick used, to detect the T-spins.
+    last_rotation: Option<([(i8, i8); 4], usize)>,
+    /// States of the game to undo the placements, only when practicing.
+    #[serde(skip)]
+    history: Option<History>,
+    /// Rows of the grid before each tetromino locked since the start of the game and the tetromino, to export the game.
+    #[serde(skip)]
+    placements: Vec<(Vec<Vec<Option<TetrisColor>>>, Tetromino)>,
+    /// Number of presses on the keys bound to a command since the active tetromino appeared, the hold excepted.
+    piece_presses: u32,
+    /// Number of key presses above the finesse of the last tetromino locked, 0 if it had no finesse fault.
+    finesse_extra: u32,
+    /// Whether a tetromino locked with a finesse fault is undone to be placed again, only when practicing.
+    retry_finesse_faults: bool,
+}
+
+/// Features of the game and handling of the keys, which differ between the modern and the retro game modes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Rules {
+    pub hold: bool,
+    pub ghost: bool,
+    pub hard_drop: bool,
+    /// Number of next tetrominos shown, at most NB_NEXT_TETROMINO.
+    pub nb_previews: usize,
+    pub rotation_system: RotationSystem,
+    pub randomizer: Randomizer,
+    /// Number of updates a key must be pressed before the tetromino moves continuously (DAS).
+    pub auto_shift_delay: u64,
+    /// Number of updates between two moves when a key is pressed continuously (ARR).
+    pub auto_repeat_rate: u64,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            hold: true,
+            ghost: true,
+            hard_drop: true,
+            nb_previews: NB_NEXT_TETROMINO,
+            rotation_system: RotationSystem::Srs,
+            randomizer: Randomizer::Bag,
+            auto_shift_delay: *KEY_REPEAT_DELAY,
+            auto_repeat_rate: *KEY_REPEAT_RATE,
+        }
+    }
+}
+
+impl Rules {
+    /// Returns the rules of the NES: no hold, ghost nor hard drop, a single next tetromino,
+    /// the NES rotation and randomizer and the NES delays of the keys.
+    pub fn classic() -> Rules {
+        Rules {
+            hold: false,
+            ghost: false,
+            hard_drop: false,
+            nb_previews: 1,
+            rotation_system: RotationSystem::Nes,
+            randomizer: Randomizer::NesReroll,
+            auto_shift_delay: CLASSIC_AUTO_SHIFT_DELAY,
+            auto_repeat_rate: CLASSIC_AUTO_REPEAT_RATE,
+        }
+    }
+}
+
+/// Optional changes to the game, which can be combined with any game mode.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub struct Modifiers {
+    /// The locked blocks fade away after INVISIBLE_SECONDS.
+    pub invisible: bool,
+    /// Each block of the tetrominos covers 2x2 cells, the grid having half the resolution.
+    pub big: bool,
+    /// The grid is flipped horizontally every MIRROR_PIECES locked tetrominos.
+    pub mirror: bool,
+    pub no_ghost: bool,
+    /// The next tetrominos are masked.
+    pub hidden_queue: bool,
+}
+
+/// One of the [Modifiers], to toggle it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Modifier {
+    Invisible,
+    Big,
+    Mirror,
+    NoGhost,
+    HiddenQueue,
+}
+
+impl Modifier {
+    pub const ALL: [Modifier; 5] = [
+        Modifier::Invisible,
+        Modifier::Big,
+        Modifier::Mirror,
+        Modifier::NoGhost,
+        Modifier::HiddenQueue,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Modifier::Invisible => "Invisible",
+            Modifier::Big => "Big",
+            Modifier::Mirror => "Mirror",
+            Modifier::NoGhost => "No ghost",
+            Modifier::HiddenQueue => "Hidden queue",
+        }
+    }
+}
+
+impl Modifiers {
+    pub fn is_set(&self, modifier: Modifier) -> bool {
+        match modifier {
+            Modifier::Invisible => self.invisible,
+            Modifier::Big => self.big,
+            Modifier::Mirror => self.mirror,
+            Modifier::NoGhost => self.no_ghost,
+            Modifier::HiddenQueue => self.hidden_queue,
+        }
+    }
+
+    pub fn toggle(&mut self, modifier: Modifier) {
+        let flag = match modifier {
+            Modifier::Invisible => &mut self.invisible,
+            Modifier::Big => &mut self.big,
+            Modifier::Mirror => &mut self.mirror,
+            Modifier::NoGhost => &mut self.no_ghost,
+            Modifier::HiddenQueue => &mut self.hidden_queue,
+        };
+        *flag = !*flag;
+    }
+}
+
+impl Display for Modifiers {
+    /// Lists the names of the modifiers that are set, or "None".
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = Modifier::ALL
+            .into_iter()
+            .filter(|&modifier| self.is_set(modifier))
+            .map(Modifier::name)
+            .collect();
+        if names.is_empty() {
+            write!(f, "None")
+        } else {
+            write!(f, "{}", names.join(", "))
+        }
+    }
+}
+
+/// How fast the tetrominos fall and lock, given to each [update()](LocalPlayer::update()).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Speed {
+    /// Number of updates between two falls of the active tetromino.
+    pub fall_speed_divide: u64,
+    /// Number of rows the active tetromino falls at once, the height of the grid for 20G.
+    pub rows_per_fall: u32,
+    /// Number of updates before the active tetromino freezes once at the bottom.
+    pub freeze: u64,
+    /// Number of updates between a freeze and the moment the next tetromino can move (ARE).
+    pub entry_delay: u64,
+}
+
+impl Speed {
+    /// Returns the speed of a tetromino falling one row at a time and appearing as soon as the previous one freezes.
+    pub fn new(fall_speed_divide: u64, freeze: u64) -> Speed {
+        Speed {
+            fall_speed_divide,
+            rows_per_fall: 1,
+            freeze,
+            entry_delay: 0,
+        }
+    }
+}
+
+/// Counters of the actions of a player since the start of the game.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug)]
+pub struct PlayerStats {
+    /// Number of tetrominos locked in the grid.
+    pub pieces: u64,
+    /// Number of presses on the keys bound to a command, used for the keys per piece.
+    pub keys: u64,
+    /// Number of cleared lines that contained garbage.
+    pub garbage_lines: u64,
+    /// Number of times the top of the stack was cleared instead of a game over.
+    pub top_clears: u64,
+    /// Number of tetrominos locked with more key presses than needed.
+    pub finesse_faults: u64,
+    /// Number of lines cleared.
+    pub lines: u64,
+    /// Number of lines of garbage sent by the cleared lines, see [lines_of_garbage].
+    pub attack: u64,
+    /// Number of tetrominos in a row that cleared lines, up to the last one locked.
+    pub combo: u64,
+    /// Highest combo, which counts the tetrominos in a row that cleared lines after the first one.
+    pub max_combo: u64,
+    pub t_spins: u64,
+    pub mini_t_spins: u64,
+    pub tetrises: u64,
+    /// Number of times a tetromino was held.
+    pub holds: u64,
+    /// Number of tetrominos locked of each kind, in the order of [TetrominoKind].
+    pub kinds: [u64; 7],
+}
+
+impl PlayerStats {
+    /// Counts a tetromino of this kind locked in the grid, along with the lines it cleared.
+    fn count_lock(&mut self, kind: TetrominoKind, cleared_lines: ClearedLines) {
+        self.pieces += 1;
+        self.kinds[kind as usize] += 1;
+        self.garbage_lines += cleared_lines.garbage;
+        self.lines += cleared_lines.total;
+        self.attack += lines_of_garbage(cleared_lines.total);
+        if cleared_lines.total > 0 {
+            self.combo += 1;
+            self.max_combo = self.max_combo.max(self.combo - 1);
+        } else {
+            self.combo = 0;
+        }
+        match cleared_lines.t_spin {
+            TSpin::Full => self.t_spins += 1,
+            TSpin::Mini => self.mini_t_spins += 1,
+            TSpin::None => {}
+        }
+        if cleared_lines.total == 4 {
+            self.tetrises += 1;
+        }
+    }
 }
 
 /// Player screen contains all the elements that will appear on the screen relative to one player.
@@ -71,6 +333,12 @@ pub struct PlayerScreen {
     pub fifo_next_tetromino: CircularBuffer<NB_NEXT_TETROMINO, Tetromino>,
     /// The shade of the active tetromino after hard drop.
     pub ghost_tetromino: Option<Tetromino>,
+    /// Whether the held tetromino is rendered.
+    pub has_hold: bool,
+    /// Number of next tetrominos rendered.
+    pub nb_previews: usize,
+    /// Whether the next tetrominos are masked.
+    pub hidden_queue: bool,
     /// Flag not to be modified except in Serialize. Set to true.
     pub serialize_as_msg: RefCell<bool>,
 }
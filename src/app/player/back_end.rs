@@ -1,5 +1,8 @@
 //! Defines the back-end of the tetris game.
+mod bitboard;
 mod block;
+mod move_generator;
+mod perfect_clear;
 mod point;
 mod render;
 mod rotation_state;
@@ -8,12 +11,16 @@ mod tetromino;
 mod tetromino_kind;
 mod translation_rotation;
 
+pub use self::{
+    bitboard::Occupancy,
+    move_generator::{finesse, reachable_placements},
+};
 use self::{
     block::Block, point::Point, rotation_state::RotationState, translation_rotation::Rotation,
 };
 use crate::assets::TetrisColor;
 use graphics::types::Matrix2d;
-use rand::seq::SliceRandom;
+use rand::{seq::SliceRandom, Rng};
 use rand_pcg::Pcg32;
 use serde::{Deserialize, Serialize};
 
@@ -28,7 +35,7 @@ pub struct Tetromino {
 }
 
 /// TetrominoKind describes the 7 types of Tetromino.
-#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum TetrominoKind {
     I,
     O,
@@ -43,19 +50,122 @@ type GridLine = Vec<Option<TetrisColor>>;
 pub type GridMatrix = [GridLine];
 
 /// Tetris grid containing blocks. It actually ontly contains their color as the coordinates of the blocks are given by their index in the matrix.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TetrisGrid {
     pub x: f64,
     pub y: f64,
     nb_columns: u32,
     nb_rows: u32,
-    pub matrix: Vec<GridLine>,
-    line_sum: Vec<u8>,
+    /// Colors of the blocks of the grid, to be modified only through the methods of TetrisGrid.
+    matrix: Vec<GridLine>,
+    /// Filled cells of the matrix, used for the collisions.
+    bitboard: Bitboard,
     pub total_width: f64,
     pub total_height: f64,
     pub visible_width: f64,
     pub visible_height: f64,
+    /// Size of the rendered blocks relative to BLOCK_SIZE, 2 for the half-resolution grid of the big modifier.
+    pub scale: f64,
     pub transform: Matrix2d<f64>,
+    /// Number of updates after which the locked blocks fade away, or None if they stay visible.
+    #[serde(skip)]
+    fade_after: Option<u64>,
+    /// Update at which each cell was filled, only kept when the blocks fade, see [tick()](TetrisGrid::tick()).
+    #[serde(skip)]
+    filled_at: Vec<Vec<u64>>,
+    /// Number of calls to [tick()](TetrisGrid::tick()) since the grid was created.
+    #[serde(skip)]
+    updates: u64,
+}
+
+/// Compact representation of the filled cells of a [TetrisGrid], kept in sync with its matrix.
+///
+/// Each row is a bitmask whose bit x is set if the cell in column x is filled.
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Bitboard {
+    nb_columns: u32,
+    rows: Vec<u16>,
+}
+
+/// Lines cleared at once by locking a [Tetromino] in a [TetrisGrid].
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ClearedLines {
+    pub total: u64,
+    /// Cleared lines that contained blocks of garbage.
+    pub garbage: u64,
+    /// Whether the grid is empty once the lines are cleared.
+    pub perfect_clear: bool,
+    /// T-spin scored by the tetromino, if it was locked right after a rotation.
+    pub t_spin: TSpin,
+}
+
+/// Single input of the player on the active [Tetromino].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Move {
+    Left,
+    Right,
+    RotateClockwise,
+    RotateCounterclockwise,
+    /// Moves the Tetromino as far down as possible without locking it.
+    SoftDrop,
+    HardDrop,
+}
+
+/// Way the [Tetromino] turns.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum RotationSystem {
+    /// Super Rotation System, with wall-kicks.
+    Srs,
+    /// Rotation of the NES, without wall-kicks, the I, S and Z only having two orientations.
+    Nes,
+}
+
+/// Way the next [TetrominoKind] are chosen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Randomizer {
+    /// Shuffled bags, see [new_tetromino_bag()].
+    Bag,
+    /// Random kind rerolled once if it's the same as the previous one, like on the NES.
+    NesReroll,
+    /// Only the kinds given to [LocalPlayer::load()](crate::app::LocalPlayer::load()), in order, then none.
+    Fixed,
+}
+
+/// Kind of T-spin scored when a T [Tetromino] is locked right after a rotation.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug, Serialize, Deserialize)]
+pub enum TSpin {
+    #[default]
+    None,
+    Mini,
+    Full,
+}
+
+/// Final position where a [Tetromino] can be locked, along with the shortest sequence of moves to get there.
+///
+/// The sequence of moves always ends with a [Move::HardDrop].
+#[derive(Clone)]
+pub struct Placement {
+    pub tetromino: Tetromino,
+    pub moves: Vec<Move>,
+    pub t_spin: TSpin,
+}
+
+/// Grid and pieces from which a perfect clear is searched by [PcProblem::solve()].
+pub struct PcProblem {
+    pub board: Bitboard,
+    pub active: TetrominoKind,
+    pub hold: Option<TetrominoKind>,
+    /// Next pieces, the first one comes next.
+    pub queue: Vec<TetrominoKind>,
+    /// Maximal number of rows from the bottom of the grid where blocks can be placed.
+    pub max_height: u32,
+}
+
+/// Step of a perfect clear solution: a [Placement] of the active piece, or of the held one if hold is set.
+#[derive(Clone)]
+pub struct PcStep {
+    pub hold: bool,
+    pub placement: Placement,
 }
 
 /// Movements composed by a translation, then a rotation.
@@ -64,6 +174,33 @@ pub struct TranslationRotation {
     pub(self) rotation: Rotation,
 }
 
+/// Returns the number of lines of garbage sent to the adversary when lines are completed at once.
+pub fn lines_of_garbage(completed_lines: u64) -> u64 {
+    match completed_lines {
+        0 | 1 => 0,
+        4 => 4,
+        _ => completed_lines - 1,
+    }
+}
+
+/// Returns a random TetrominoKind like the NES does: a kind is drawn among 8 outcomes,
+/// and drawn again among the 7 kinds if it's the 8th one or the same as the previous kind.
+pub fn nes_tetromino(previous: Option<TetrominoKind>, rng: &mut Pcg32) -> TetrominoKind {
+    let kinds = [
+        TetrominoKind::I,
+        TetrominoKind::O,
+        TetrominoKind::T,
+        TetrominoKind::S,
+        TetrominoKind::Z,
+        TetrominoKind::J,
+        TetrominoKind::L,
+    ];
+    match kinds.get(rng.gen_range(0..=kinds.len())) {
+        Some(&kind) if Some(kind) != previous => kind,
+        _ => kinds[rng.gen_range(0..kinds.len())],
+    }
+}
+
 /// Returns a random bag of TetrominoKind of the specified size using the given rng.
 pub fn new_tetromino_bag(mut size_of_bag: u32, rng: &mut Pcg32) -> Vec<TetrominoKind> {
     if size_of_bag == 0 {
@@ -0,0 +1,131 @@
+//! Defines the [Bitboard](super::Bitboard) used for fast collision checks and the [Occupancy] of a grid.
+use super::{Bitboard, GridMatrix, TetrisGrid};
+
+/// Tells which cells of a grid are filled.
+///
+/// ## Uses
+/// - anytime a [Tetromino](super::Tetromino) moves, to check its blocks don't collide with anything
+pub trait Occupancy {
+    /// Returns true if the cell is filled or outside the grid.
+    fn is_filled(&self, x: i8, y: i8) -> bool;
+
+    /// Returns how many rows the cells can go down before one of them is stopped by a filled cell or the bottom of the grid.
+    fn drop_distance(&self, cells: &[(i8, i8)]) -> i8 {
+        let mut distance = 0;
+        while cells
+            .iter()
+            .all(|&(x, y)| !self.is_filled(x, y + distance + 1))
+        {
+            distance += 1;
+        }
+        distance
+    }
+}
+
+impl Bitboard {
+    pub fn new(nb_columns: u32, nb_rows: u32) -> Self {
+        assert!(
+            nb_columns <= 16,
+            "a bitboard row can't hold more than 16 columns"
+        );
+        Bitboard {
+            nb_columns,
+            rows: vec![0; nb_rows as usize],
+        }
+    }
+
+    pub fn nb_columns(&self) -> u32 {
+        self.nb_columns
+    }
+
+    pub fn nb_rows(&self) -> u32 {
+        self.rows.len() as u32
+    }
+
+    /// Returns the bitmask of the given row.
+    pub fn row(&self, y: usize) -> u16 {
+        self.rows[y]
+    }
+
+    /// Fills or empties the cell.
+    pub fn set(&mut self, x: usize, y: usize, filled: bool) {
+        if filled {
+            self.rows[y] |= 1 << x;
+        } else {
+            self.rows[y] &= !(1 << x);
+        }
+    }
+
+    /// Returns true if all the cells of the row are filled.
+    pub fn is_row_full(&self, y: usize) -> bool {
+        self.rows[y] == self.full_row()
+    }
+
+    /// Removes the row, the rows above it go down by one.
+    pub fn remove_row(&mut self, y: usize) {
+        self.rows.remove(y);
+        self.rows.insert(0, 0);
+    }
+
+    /// Adds a row at the bottom, the other rows go up by one and the top one is lost.
+    pub fn push_bottom_row(&mut self, row: u16) {
+        self.rows.remove(0);
+        self.rows.push(row & self.full_row());
+    }
+
+    /// Empties all the cells.
+    pub fn clear(&mut self) {
+        for row in self.rows.iter_mut() {
+            *row = 0;
+        }
+    }
+
+    /// Returns the bitmask of a row whose cells are all filled.
+    fn full_row(&self) -> u16 {
+        ((1u32 << self.nb_columns) - 1) as u16
+    }
+}
+
+impl Occupancy for Bitboard {
+    fn is_filled(&self, x: i8, y: i8) -> bool {
+        if x < 0 || y < 0 || x as u32 >= self.nb_columns || y as usize >= self.rows.len() {
+            return true;
+        }
+        self.rows[y as usize] & (1 << x) != 0
+    }
+
+    fn drop_distance(&self, cells: &[(i8, i8)]) -> i8 {
+        if cells
+            .iter()
+            .any(|&(x, _)| x < 0 || x as u32 >= self.nb_columns)
+        {
+            return 0;
+        }
+        let mut distance = 0;
+        loop {
+            let stopped = cells.iter().any(|&(x, y)| {
+                let below = (y + distance + 1) as usize;
+                below >= self.rows.len() || self.rows[below] & (1 << x) != 0
+            });
+            if stopped {
+                return distance;
+            }
+            distance += 1;
+        }
+    }
+}
+
+impl Occupancy for TetrisGrid {
+    fn is_filled(&self, x: i8, y: i8) -> bool {
+        self.bitboard.is_filled(x, y)
+    }
+}
+
+impl Occupancy for GridMatrix {
+    fn is_filled(&self, x: i8, y: i8) -> bool {
+        if x < 0 || y < 0 || y as usize >= self.len() || x as usize >= self[0].len() {
+            return true;
+        }
+        self[y as usize][x as usize].is_some()
+    }
+}
@@ -2,7 +2,7 @@
 use super::{
     point::{Point, Transform},
     translation_rotation::Rotation,
-    GridMatrix, TranslationRotation,
+    Occupancy, TranslationRotation,
 };
 use crate::assets::TetrisColor;
 use serde::{Deserialize, Serialize};
@@ -26,8 +26,12 @@ pub struct Block {
 /// ## Uses
 /// - anytime a [Tetromino](super::Tetromino) moves
 pub(super) trait Collision {
-    /// Applies a given *movement* to a block in a given *matrix*.
-    fn move_to(&self, matrix: &GridMatrix, movement: &TranslationRotation) -> Result<Block, ()>;
+    /// Applies a given *movement* to a block in a given *grid*.
+    fn move_to<G: Occupancy + ?Sized>(
+        &self,
+        grid: &G,
+        movement: &TranslationRotation,
+    ) -> Result<Block, ()>;
 }
 
 impl Block {
@@ -44,16 +48,6 @@ impl Block {
             color: self.color,
         }
     }
-
-    fn check_inside_grid(point: &Point, width: usize, height: usize) -> Result<(), ()> {
-        if point.x < 0 || point.y < 0 {
-            return Err(());
-        }
-        if point.x as usize >= width || point.y as usize >= height {
-            return Err(());
-        }
-        Ok(())
-    }
 }
 
 impl Default for Block {
@@ -66,7 +60,11 @@ impl Default for Block {
 }
 
 impl Collision for Block {
-    fn move_to(&self, matrix: &GridMatrix, movement: &TranslationRotation) -> Result<Block, ()> {
+    fn move_to<G: Occupancy + ?Sized>(
+        &self,
+        grid: &G,
+        movement: &TranslationRotation,
+    ) -> Result<Block, ()> {
         // Apply the TranslationRotation
         let mut copy = self.translation_by(movement);
         match movement.rotation {
@@ -78,12 +76,11 @@ impl Collision for Block {
             }
             _ => {}
         }
-        // Check if the block is still inside the grid
-        Self::check_inside_grid(&copy.position, matrix[0].len(), matrix.len())?;
-        // Check if the block is not on another one
-        match matrix[copy.position.y as usize][copy.position.x as usize] {
-            Some(_) => Err(()),
-            None => Ok(copy),
+        // Check if the block is still inside the grid and not on another one
+        if grid.is_filled(copy.position.x, copy.position.y) {
+            Err(())
+        } else {
+            Ok(copy)
         }
     }
 }
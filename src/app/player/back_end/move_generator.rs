@@ -0,0 +1,343 @@
+//! Defines the move generator that lists every [Placement](super::Placement) reachable by a [Tetromino](super::Tetromino).
+//!
+//! The generator is a breadth-first search over the positions of the Tetromino,
+//! so the sequence of moves found for each final position is one of the shortest.
+use super::{
+    translation_rotation::RotationType, Move, Occupancy, Placement, TSpin, TetrisGrid, Tetromino,
+    TranslationRotation,
+};
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// Moves tried from every position. The hard drop is only used to lock the Tetromino.
+const MOVES: [Move; 5] = [
+    Move::Left,
+    Move::Right,
+    Move::RotateClockwise,
+    Move::RotateCounterclockwise,
+    Move::SoftDrop,
+];
+
+/// Hasher for the small keys of the search, much faster than the default one which resists to HashDoS attacks.
+#[derive(Default)]
+struct KeyHasher(u64);
+
+impl Hasher for KeyHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0.rotate_left(5) ^ byte as u64).wrapping_mul(0x517c_c1b7_2722_0a95);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+type KeyBuildHasher = BuildHasherDefault<KeyHasher>;
+
+/// Position of the Tetromino reached during the search.
+struct Node {
+    tetromino: Tetromino,
+    /// T-spin scored if the Tetromino is locked at this exact position.
+    t_spin: TSpin,
+    /// Index of the previous node and move that led to this one.
+    parent: Option<(usize, Move)>,
+    /// Number of moves from the first node.
+    depth: usize,
+}
+
+impl TetrisGrid {
+    /// Returns every distinct position where the Tetromino can be locked, starting from its current position.
+    ///
+    /// See [reachable_placements()].
+    pub fn reachable_placements(&self, tetromino: &Tetromino) -> Vec<Placement> {
+        reachable_placements(self.bitboard(), tetromino)
+    }
+}
+
+/// Returns every distinct position where the Tetromino can be locked in the grid, starting from its current position.
+///
+/// Tucks (moves after a soft drop) and spins using wall-kicks are included.
+/// Two placements are distinct if their blocks are on different cells or if only one of them is a T-spin.
+pub fn reachable_placements<G: Occupancy + ?Sized>(
+    grid: &G,
+    tetromino: &Tetromino,
+) -> Vec<Placement> {
+    if tetromino
+        .check_possible(grid, TranslationRotation::null())
+        .is_err()
+    {
+        return vec![];
+    }
+
+    /**********************************
+     *   EXPLORING the POSITIONS      *
+     **********************************/
+
+    let mut nodes = vec![Node {
+        tetromino: *tetromino,
+        t_spin: TSpin::None,
+        parent: None,
+        depth: 0,
+    }];
+    let mut visited = HashSet::with_hasher(KeyBuildHasher::default());
+    visited.insert((tetromino.center, tetromino.rotation_status, TSpin::None));
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(index) = queue.pop_front() {
+        for movement in MOVES {
+            let Some((next, t_spin)) = try_move(grid, &nodes[index].tetromino, movement) else {
+                continue;
+            };
+            if visited.insert((next.center, next.rotation_status, t_spin)) {
+                queue.push_back(nodes.len());
+                nodes.push(Node {
+                    tetromino: next,
+                    t_spin,
+                    parent: Some((index, movement)),
+                    depth: nodes[index].depth + 1,
+                });
+            }
+        }
+    }
+
+    /**********************************
+     *   LOCKING from EACH POSITION   *
+     **********************************/
+
+    let mut placements: Vec<Placement> = vec![];
+    let mut placement_index = HashMap::with_hasher(KeyBuildHasher::default());
+    for (index, node) in nodes.iter().enumerate() {
+        let mut locked = node.tetromino;
+        locked.hard_drop(grid);
+        // the T-spin is lost if the hard drop moves the tetromino
+        let t_spin = if locked.center == node.tetromino.center {
+            node.t_spin
+        } else {
+            TSpin::None
+        };
+
+        let ends_with_soft_drop = matches!(node.parent, Some((_, Move::SoftDrop)));
+        let nb_moves = node.depth + 1 - ends_with_soft_drop as usize;
+
+        match placement_index.entry((cells(&locked), t_spin)) {
+            Entry::Occupied(entry) => {
+                let placement: &mut Placement = &mut placements[*entry.get()];
+                if nb_moves < placement.moves.len() {
+                    placement.tetromino = locked;
+                    placement.moves = moves_to(&nodes, index);
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(placements.len());
+                placements.push(Placement {
+                    tetromino: locked,
+                    moves: moves_to(&nodes, index),
+                    t_spin,
+                });
+            }
+        }
+    }
+    placements
+}
+
+/// Returns the minimal number of key presses to lock the Tetromino on the given cells from its current position,
+/// or None if it can't reach them. This is the finesse of the placement.
+///
+/// Holding a key to move the Tetromino against a wall counts as one press (DAS), and the lock as one hard drop.
+pub fn finesse<G: Occupancy + ?Sized>(
+    grid: &G,
+    tetromino: &Tetromino,
+    target: &[(i8, i8); 4],
+) -> Option<u32> {
+    let mut target = *target;
+    target.sort_unstable();
+    let mut visited = HashSet::with_hasher(KeyBuildHasher::default());
+    visited.insert((tetromino.center, tetromino.rotation_status));
+    let mut queue = VecDeque::from([(*tetromino, 0)]);
+    while let Some((current, presses)) = queue.pop_front() {
+        let mut locked = current;
+        locked.hard_drop(grid);
+        if cells(&locked) == target {
+            return Some(presses + 1);
+        }
+        let moved = MOVES
+            .into_iter()
+            .filter_map(|movement| try_move(grid, &current, movement).map(|(next, _)| next));
+        // the tetromino moved as far as possible to the left or to the right
+        let shifted = [Move::Left, Move::Right]
+            .into_iter()
+            .filter_map(|movement| {
+                let mut next = try_move(grid, &current, movement)?.0;
+                while let Some((further, _)) = try_move(grid, &next, movement) {
+                    next = further;
+                }
+                Some(next)
+            });
+        for next in moved.chain(shifted) {
+            if visited.insert((next.center, next.rotation_status)) {
+                queue.push_back((next, presses + 1));
+            }
+        }
+    }
+    None
+}
+
+/// Returns the Tetromino moved by the given move and the T-spin it would score, or None if it couldn't move.
+fn try_move<G: Occupancy + ?Sized>(
+    grid: &G,
+    tetromino: &Tetromino,
+    movement: Move,
+) -> Option<(Tetromino, TSpin)> {
+    let mut next = *tetromino;
+    let t_spin = match movement {
+        Move::Left => {
+            next.left(grid);
+            TSpin::None
+        }
+        Move::Right => {
+            next.right(grid);
+            TSpin::None
+        }
+        Move::RotateClockwise => next
+            .turn(grid, RotationType::Clockwise)
+            .map(|kick| next.t_spin(grid, kick))?,
+        Move::RotateCounterclockwise => next
+            .turn(grid, RotationType::Counterclockwise)
+            .map(|kick| next.t_spin(grid, kick))?,
+        Move::SoftDrop | Move::HardDrop => {
+            next.hard_drop(grid);
+            TSpin::None
+        }
+    };
+    if next.center == tetromino.center && next.rotation_status == tetromino.rotation_status {
+        return None;
+    }
+    Some((next, t_spin))
+}
+
+/// Returns the moves leading from the first node to the node of the given index, then locking the Tetromino.
+fn moves_to(nodes: &[Node], mut index: usize) -> Vec<Move> {
+    let mut moves = Vec::with_capacity(nodes[index].depth + 1);
+    while let Some((parent, movement)) = nodes[index].parent {
+        moves.push(movement);
+        index = parent;
+    }
+    moves.reverse();
+    // a soft drop right before the hard drop is useless
+    if moves.last() == Some(&Move::SoftDrop) {
+        moves.pop();
+    }
+    moves.push(Move::HardDrop);
+    moves
+}
+
+/// Returns the sorted coordinates of the cells occupied by the Tetromino.
+fn cells(tetromino: &Tetromino) -> [(i8, i8); 4] {
+    let mut cells = tetromino.cells();
+    cells.sort_unstable();
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Bitboard, TetrominoKind};
+    use super::*;
+
+    /// Returns an empty grid of the size of the game with the given cells filled.
+    fn grid_with(filled: &[(usize, usize)]) -> Bitboard {
+        let mut grid = Bitboard::new(10, 22);
+        for &(x, y) in filled {
+            grid.set(x, y, true);
+        }
+        grid
+    }
+
+    #[test]
+    fn placements_are_distinct() {
+        let grid = grid_with(&[]);
+        // rotations of the empty grid: O 9, I 7 + 10, T 8 + 9 + 8 + 9
+        for (kind, expected) in [
+            (TetrominoKind::O, 9),
+            (TetrominoKind::I, 17),
+            (TetrominoKind::T, 34),
+        ] {
+            let tetromino = Tetromino::new(kind, &grid).unwrap();
+            let placements = reachable_placements(&grid, &tetromino);
+            let distinct: HashSet<_> = placements
+                .iter()
+                .map(|placement| (cells(&placement.tetromino), placement.t_spin))
+                .collect();
+            assert_eq!(distinct.len(), placements.len());
+            assert_eq!(placements.len(), expected);
+        }
+    }
+
+    #[test]
+    fn tuck_under_overhang() {
+        // a roof over the two left columns, the bottom rows right of the cavity being filled
+        let mut filled = vec![(0, 19), (1, 19)];
+        for x in 4..10 {
+            filled.extend([(x, 20), (x, 21)]);
+        }
+        let grid = grid_with(&filled);
+        let tetromino = Tetromino::new(TetrominoKind::O, &grid).unwrap();
+        let tuck = reachable_placements(&grid, &tetromino)
+            .into_iter()
+            .find(|placement| cells(&placement.tetromino) == [(0, 20), (0, 21), (1, 20), (1, 21)])
+            .expect("the O can be tucked under the roof");
+        let soft_drop = tuck
+            .moves
+            .iter()
+            .position(|movement| *movement == Move::SoftDrop)
+            .expect("the tuck needs a soft drop");
+        assert!(tuck.moves[soft_drop..].contains(&Move::Left));
+    }
+
+    #[test]
+    fn t_spin_with_last_kick_is_full() {
+        let grid = grid_with(&[(1, 17), (0, 19), (8, 21), (9, 21)]);
+        let tetromino = Tetromino::new(TetrominoKind::T, &grid).unwrap();
+        let spin = reachable_placements(&grid, &tetromino)
+            .into_iter()
+            .find(|placement| {
+                placement.t_spin == TSpin::Full
+                    && cells(&placement.tetromino) == [(0, 15), (0, 16), (0, 17), (1, 16)]
+            })
+            .expect("the T can spin into the corner");
+        // replays the moves to find the wall-kick of the last rotation
+        let mut current = tetromino;
+        let mut last_kick = None;
+        for movement in &spin.moves {
+            match movement {
+                Move::RotateClockwise => last_kick = current.turn_clockwise(&grid),
+                Move::RotateCounterclockwise => last_kick = current.turn_counterclockwise(&grid),
+                Move::Left => current.left(&grid),
+                Move::Right => current.right(&grid),
+                Move::SoftDrop | Move::HardDrop => current.hard_drop(&grid),
+            }
+        }
+        assert_eq!(last_kick, Some(4));
+        // only one corner the T points to is filled, without the last kick it would be a mini
+        assert_eq!(current.t_spin(&grid, 0), TSpin::Mini);
+    }
+
+    #[test]
+    fn unreachable_cavity_is_excluded() {
+        // the bottom row is filled apart from a cavity covered by the full row above it
+        let mut filled: Vec<_> = (0..10).map(|x| (x, 20)).collect();
+        filled.extend((4..10).map(|x| (x, 21)));
+        let grid = grid_with(&filled);
+        for kind in [TetrominoKind::I, TetrominoKind::O, TetrominoKind::T] {
+            let tetromino = Tetromino::new(kind, &grid).unwrap();
+            let placements = reachable_placements(&grid, &tetromino);
+            assert!(!placements.is_empty());
+            assert!(placements.iter().all(|placement| placement
+                .tetromino
+                .cells()
+                .iter()
+                .all(|&(_, y)| y < 20)));
+        }
+    }
+}
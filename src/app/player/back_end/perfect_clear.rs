@@ -0,0 +1,287 @@
+//! Defines the perfect clear solver, searching the placements (with hold) that empty the grid.
+//!
+//! The solver is a depth-first search over the sequences of [Placement](super::Placement),
+//! remembering the states already explored as many sequences lead to the same grid.
+use super::{reachable_placements, Bitboard, PcProblem, PcStep, Tetromino, TetrominoKind};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+impl PcProblem {
+    /// Returns the shortest sequence of steps (in lines cleared) leading to a perfect clear, or None if there's none.
+    ///
+    /// The search stops and returns None as soon as cancel is set, so that it can run in a separate thread.
+    pub fn solve(&self, cancel: &AtomicBool) -> Option<Vec<PcStep>> {
+        let mut pieces = vec![self.active];
+        pieces.extend(&self.queue);
+        let filled = filled_cells(&self.board);
+        let nb_columns = self.board.nb_columns();
+
+        // the grid can be cleared in h lines only if the empty cells below can be filled with tetrominos
+        for height in height(&self.board).max(1)..=self.max_height {
+            if !(height * nb_columns - filled).is_multiple_of(4) {
+                continue;
+            }
+            let mut search = Search {
+                pieces: &pieces,
+                visited: HashSet::new(),
+                cancel,
+                path: vec![],
+            };
+            if search.explore(&self.board, 0, self.hold, height) {
+                return Some(search.path);
+            }
+            if cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+struct Search<'a> {
+    /// Active tetromino followed by the queue.
+    pieces: &'a [TetrominoKind],
+    /// Grids, indices of the next piece, held pieces and heights already explored without success.
+    visited: HashSet<(Bitboard, usize, Option<TetrominoKind>, u32)>,
+    cancel: &'a AtomicBool,
+    /// Steps leading to the grid being explored.
+    path: Vec<PcStep>,
+}
+
+impl Search<'_> {
+    /// Returns true if the grid can be perfectly cleared, the steps are then in path.
+    ///
+    /// - index is the index of the active piece in pieces
+    /// - height is the number of rows from the bottom that must be filled then cleared
+    fn explore(
+        &mut self,
+        board: &Bitboard,
+        index: usize,
+        hold: Option<TetrominoKind>,
+        height: u32,
+    ) -> bool {
+        if height == 0 {
+            return true;
+        }
+        if self.cancel.load(Ordering::Relaxed) || index >= self.pieces.len() {
+            return false;
+        }
+        // not enough pieces left to fill the empty cells
+        let empty_cells = height * board.nb_columns() - filled_cells(board);
+        let pieces_left = self.pieces.len() - index + hold.is_some() as usize;
+        if empty_cells as usize > 4 * pieces_left {
+            return false;
+        }
+        if has_unfillable_region(board, height) {
+            return false;
+        }
+        if !self.visited.insert((board.clone(), index, hold, height)) {
+            return false;
+        }
+
+        // (hold, piece to place, index of the next active piece, held piece after placing)
+        let active = self.pieces[index];
+        let mut choices = vec![(false, active, index + 1, hold)];
+        match hold {
+            Some(held) if held != active => choices.push((true, held, index + 1, Some(active))),
+            None if index + 1 < self.pieces.len() && self.pieces[index + 1] != active => {
+                choices.push((true, self.pieces[index + 1], index + 2, Some(active)))
+            }
+            _ => {}
+        }
+
+        let lowest_row = (board.nb_rows() - height) as i8;
+        for (use_hold, kind, next_index, next_hold) in choices {
+            let mut explored_cells = HashSet::new();
+            for placement in reachable_placements(board, &Tetromino::new_unchecked(kind)) {
+                let cells = placement.tetromino.cells();
+                if cells.iter().any(|&(_, y)| y < lowest_row) || !explored_cells.insert(cells) {
+                    continue;
+                }
+                let mut next_board = board.clone();
+                for (x, y) in cells {
+                    next_board.set(x as usize, y as usize, true);
+                }
+                let mut cleared_lines = 0;
+                for y in lowest_row as usize..board.nb_rows() as usize {
+                    if next_board.is_row_full(y) {
+                        next_board.remove_row(y);
+                        cleared_lines += 1;
+                    }
+                }
+
+                self.path.push(PcStep {
+                    hold: use_hold,
+                    placement,
+                });
+                if self.explore(&next_board, next_index, next_hold, height - cleared_lines) {
+                    return true;
+                }
+                self.path.pop();
+            }
+        }
+        false
+    }
+}
+
+/// Returns true if an area of empty cells below the height can't be filled with tetrominos,
+/// because its number of cells isn't a multiple of 4.
+fn has_unfillable_region(board: &Bitboard, height: u32) -> bool {
+    let nb_columns = board.nb_columns() as usize;
+    let nb_rows = board.nb_rows() as usize;
+    let top = nb_rows - height as usize;
+    // the cells already counted in an area, by row
+    let mut seen: Vec<u16> = (top..nb_rows).map(|y| board.row(y)).collect();
+    for start_y in top..nb_rows {
+        for start_x in 0..nb_columns {
+            if seen[start_y - top] & (1 << start_x) != 0 {
+                continue;
+            }
+            seen[start_y - top] |= 1 << start_x;
+            let mut stack = vec![(start_x, start_y)];
+            let mut size: u32 = 0;
+            while let Some((x, y)) = stack.pop() {
+                size += 1;
+                let neighbours = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                for (x, y) in neighbours {
+                    if x < nb_columns
+                        && (top..nb_rows).contains(&y)
+                        && seen[y - top] & (1 << x) == 0
+                    {
+                        seen[y - top] |= 1 << x;
+                        stack.push((x, y));
+                    }
+                }
+            }
+            if !size.is_multiple_of(4) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Returns the number of filled cells of the grid.
+fn filled_cells(board: &Bitboard) -> u32 {
+    (0..board.nb_rows() as usize)
+        .map(|y| board.row(y).count_ones())
+        .sum()
+}
+
+/// Returns the number of rows between the bottom of the grid and its highest filled cell.
+fn height(board: &Bitboard) -> u32 {
+    (0..board.nb_rows())
+        .find(|&y| board.row(y as usize) != 0)
+        .map_or(0, |y| board.nb_rows() - y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a grid of the size of the game whose bottom rows are filled, apart from the given cells.
+    fn grid_without(nb_filled_rows: usize, empty: &[(usize, usize)]) -> Bitboard {
+        let mut grid = Bitboard::new(10, 22);
+        for y in 22 - nb_filled_rows..22 {
+            for x in 0..10 {
+                grid.set(x, y, !empty.contains(&(x, y)));
+            }
+        }
+        grid
+    }
+
+    /// Places the steps on the board with the pieces of the problem, checking that each placement is reachable,
+    /// and returns the board.
+    fn replay(problem: &PcProblem, steps: &[PcStep]) -> Bitboard {
+        let mut board = problem.board.clone();
+        let mut queue = problem.queue.iter().copied();
+        let mut active = Some(problem.active);
+        let mut hold = problem.hold;
+        for step in steps {
+            if step.hold {
+                let held = hold.or_else(|| queue.next());
+                hold = active;
+                active = held;
+            }
+            let kind = active.unwrap();
+            let tetromino = &step.placement.tetromino;
+            assert!(tetromino.kind() == kind);
+            assert!(
+                reachable_placements(&board, &Tetromino::new_unchecked(kind))
+                    .iter()
+                    .any(|placement| placement.tetromino.cells() == tetromino.cells())
+            );
+            for (x, y) in tetromino.cells() {
+                assert_eq!(board.row(y as usize) >> x & 1, 0);
+                board.set(x as usize, y as usize, true);
+            }
+            for y in 0..board.nb_rows() as usize {
+                if board.is_row_full(y) {
+                    board.remove_row(y);
+                }
+            }
+            active = queue.next();
+        }
+        board
+    }
+
+    /// Returns a board of 4 rows whose 4 columns on the right are empty, which the pieces of a bag can clear.
+    fn four_lines() -> PcProblem {
+        let empty: Vec<(usize, usize)> = (6..10)
+            .flat_map(|x| (18..22).map(move |y| (x, y)))
+            .collect();
+        PcProblem {
+            board: grid_without(4, &empty),
+            active: TetrominoKind::S,
+            hold: None,
+            queue: vec![
+                TetrominoKind::L,
+                TetrominoKind::O,
+                TetrominoKind::Z,
+                TetrominoKind::J,
+                TetrominoKind::I,
+                TetrominoKind::T,
+            ],
+            max_height: 4,
+        }
+    }
+
+    #[test]
+    fn solution_clears_the_board() {
+        let problem = four_lines();
+        let steps = problem.solve(&AtomicBool::new(false)).unwrap();
+        assert_eq!(steps.len(), 4);
+        let board = replay(&problem, &steps);
+        assert!((0..board.nb_rows() as usize).all(|y| board.row(y) == 0));
+    }
+
+    #[test]
+    fn impossible_problems_have_no_solution() {
+        // 19 empty cells below the second row, or 9 below the first one
+        let mut board = Bitboard::new(10, 22);
+        board.set(0, 21, true);
+        let odd = PcProblem {
+            board,
+            max_height: 2,
+            ..four_lines()
+        };
+        assert!(odd.solve(&AtomicBool::new(false)).is_none());
+
+        // the 16 empty cells need 4 pieces
+        let too_few = PcProblem {
+            queue: vec![TetrominoKind::L, TetrominoKind::O],
+            ..four_lines()
+        };
+        assert!(too_few.solve(&AtomicBool::new(false)).is_none());
+    }
+
+    #[test]
+    fn cancel_stops_the_search() {
+        assert!(four_lines().solve(&AtomicBool::new(true)).is_none());
+    }
+}
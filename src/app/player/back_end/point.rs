@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 ///
 /// A point moves without knownledge of its surroundings through [Transform]
 /// and thus doesn't implement collisions.
-#[derive(Clone, Copy, Serialize, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub(super) struct Point {
     /// horizontal coordinate, from left to right
     pub(super) x: i8,
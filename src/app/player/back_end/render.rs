@@ -1,8 +1,11 @@
 //! Defines the render functions of types [Block](super::block::Block::render()), [Tetromino](super::Tetromino::render()) and [TetrisGrid](super::TetrisGrid::render()).
 use super::{block::Block, TetrisGrid, Tetromino};
+use crate::assets::Assets;
 use crate::assets::TetrisColor;
-use crate::settings::{BLOCK_SIZE, GRID_BG_COLOR, GRID_COLOR, GRID_THICKNESS};
-use crate::Assets;
+use crate::settings::{
+    BLOCK_SIZE, GRID_BG_COLOR, GRID_COLOR, GRID_THICKNESS, INVISIBLE_FADE_SECONDS,
+    OPENER_TARGET_COLOR, PC_HINT_COLOR, UPDATES_PER_SECOND,
+};
 use graphics::types::{Matrix2d, Rectangle, Scalar};
 use graphics::{draw_state::Blend, Transformed};
 use graphics::{rectangle, DrawState, Image};
@@ -17,7 +20,9 @@ impl TetrisGrid {
         gl: &mut GlGraphics,
         assets: &Assets,
     ) {
-        self.transform = transform.trans(self.x, self.y);
+        self.transform = transform
+            .trans(self.x, self.y)
+            .scale(self.scale, self.scale);
 
         let empty_dims: Rectangle = [
             0.0,
@@ -25,14 +30,14 @@ impl TetrisGrid {
             self.visible_width,
             self.visible_height,
         ];
-        rectangle(GRID_BG_COLOR, empty_dims, self.transform, gl);
-        let outline_rect = graphics::Rectangle::new_border(GRID_COLOR, GRID_THICKNESS * 2.0);
+        rectangle(*GRID_BG_COLOR, empty_dims, self.transform, gl);
+        let outline_rect = graphics::Rectangle::new_border(*GRID_COLOR, GRID_THICKNESS * 2.0);
         outline_rect.draw(empty_dims, draw_state, self.transform, gl);
 
         for (y, row) in self.matrix.iter().enumerate() {
             for (x, _cell) in row.iter().enumerate() {
                 if y > 1 {
-                    let outline_rect = graphics::Rectangle::new_border(GRID_COLOR, GRID_THICKNESS);
+                    let outline_rect = graphics::Rectangle::new_border(*GRID_COLOR, GRID_THICKNESS);
                     let outline_dims = rectangle::square(
                         x as Scalar * BLOCK_SIZE, // + GRID_THICKNESS as Scalar / 20.0,
                         y as Scalar * BLOCK_SIZE, // + GRID_THICKNESS as Scalar / 20.0,
@@ -46,13 +51,55 @@ impl TetrisGrid {
             for (x, cell) in row.iter().enumerate() {
                 match cell {
                     Some(tetris_color) => {
-                        tetris_color.render(x, y, self.transform, draw_state, gl, assets)
+                        let opacity = self.opacity(x, y);
+                        if opacity > 0.0 {
+                            tetris_color.render(
+                                x,
+                                y,
+                                opacity,
+                                self.transform,
+                                draw_state,
+                                gl,
+                                assets,
+                            )
+                        }
                     }
                     None => {}
                 }
             }
         }
     }
+
+    /// Render the outlines of the cells, on top of the TetrisGrid rendered last.
+    pub fn render_outlines(
+        &self,
+        cells: &[(usize, usize)],
+        draw_state: &DrawState,
+        gl: &mut GlGraphics,
+    ) {
+        let outline_rect =
+            graphics::Rectangle::new_border(OPENER_TARGET_COLOR, GRID_THICKNESS * 2.0);
+        for (x, y) in cells {
+            let outline_dims = rectangle::square(
+                *x as Scalar * BLOCK_SIZE,
+                *y as Scalar * BLOCK_SIZE,
+                BLOCK_SIZE,
+            );
+            outline_rect.draw(outline_dims, draw_state, self.transform, gl);
+        }
+    }
+
+    /// Returns the opacity of the block in the cell, going down to 0 at the end of its fade if the blocks fade.
+    fn opacity(&self, x: usize, y: usize) -> f32 {
+        match (self.fade_after, self.age(x, y)) {
+            (Some(fade_after), Some(age)) => {
+                let fade_duration = INVISIBLE_FADE_SECONDS * UPDATES_PER_SECOND as f64;
+                let remaining = fade_after.saturating_sub(age) as f64;
+                (remaining / fade_duration).min(1.0) as f32
+            }
+            _ => 1.0,
+        }
+    }
 }
 
 impl Tetromino {
@@ -73,6 +120,26 @@ impl Tetromino {
             self.blocks[i].render(transform, &draw_state, gl, assets);
         }
     }
+
+    /// Render the Tetromino as a translucent hint.
+    pub fn render_hint(
+        &self,
+        transform: Matrix2d,
+        draw_state: &DrawState,
+        gl: &mut GlGraphics,
+        assets: &Assets,
+    ) {
+        let draw_state = draw_state.blend(Blend::Alpha);
+        for block in self.blocks.iter() {
+            block.render_image(
+                Image::new_color(PC_HINT_COLOR),
+                transform,
+                &draw_state,
+                gl,
+                assets,
+            );
+        }
+    }
 }
 
 // TODO : make all the render signature the same so it can become a trait
@@ -84,6 +151,18 @@ impl Block {
         draw_state: &DrawState,
         gl: &mut GlGraphics,
         assets: &Assets,
+    ) {
+        self.render_image(Image::new(), transform, draw_state, gl, assets);
+    }
+
+    /// Render the Block using the texture from assets with the given image parameters.
+    fn render_image(
+        &self,
+        image: Image,
+        transform: Matrix2d,
+        draw_state: &DrawState,
+        gl: &mut GlGraphics,
+        assets: &Assets,
     ) {
         let dims = rectangle::square(
             self.position.x as Scalar * BLOCK_SIZE,
@@ -91,7 +170,7 @@ impl Block {
             BLOCK_SIZE,
         );
 
-        Image::new().rect(dims).draw(
+        image.rect(dims).draw(
             assets.texture_from_tetris_color(&self.color),
             draw_state,
             transform,
@@ -101,11 +180,13 @@ impl Block {
 }
 
 impl TetrisColor {
-    /// Render the Block using the texture from assets.
+    /// Render the Block using the texture from assets, translucent if the opacity is below 1.
+    #[allow(clippy::too_many_arguments)]
     pub fn render(
         &self,
         x: usize,
         y: usize,
+        opacity: f32,
         transform: Matrix2d,
         draw_state: &DrawState,
         gl: &mut GlGraphics,
@@ -117,9 +198,9 @@ impl TetrisColor {
             BLOCK_SIZE,
         );
 
-        Image::new().rect(dims).draw(
+        Image::new_color([1.0, 1.0, 1.0, opacity]).rect(dims).draw(
             assets.texture_from_tetris_color(self),
-            draw_state,
+            &draw_state.blend(Blend::Alpha),
             transform,
             gl,
         );
@@ -2,7 +2,7 @@
 use serde::{Deserialize, Serialize};
 
 /// Rotation state of a [Tetromino](super::Tetromino) among the 4 possible.
-#[derive(Clone, Copy, Serialize, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub(super) enum RotationState {
     R0,
     R1,
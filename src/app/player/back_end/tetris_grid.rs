@@ -1,7 +1,7 @@
 //! Defines the implementation of a [TetrisGrid](super::TetrisGrid).
-use super::{TetrisGrid, Tetromino};
+use super::{lines_of_garbage, Bitboard, ClearedLines, TetrisGrid, Tetromino};
 use crate::assets::TetrisColor;
-use crate::settings::BLOCK_SIZE;
+use crate::settings::{BLOCK_SIZE, NB_COLUMNS, NB_ROWS};
 use graphics::types::Matrix2d;
 use rand::Rng;
 
@@ -12,29 +12,74 @@ impl TetrisGrid {
             matrix.push(vec![None; nb_columns as usize]);
         }
 
-        let line_sum = vec![0; nb_rows as usize];
         TetrisGrid {
             x,
             y,
             nb_columns,
             nb_rows,
             matrix,
-            line_sum,
+            bitboard: Bitboard::new(nb_columns, nb_rows),
             total_width: nb_columns as f64 * BLOCK_SIZE,
             total_height: nb_rows as f64 * BLOCK_SIZE,
             visible_width: nb_columns as f64 * BLOCK_SIZE,
             visible_height: (nb_rows - 2) as f64 * BLOCK_SIZE,
+            scale: 1.0,
             transform: Matrix2d::default(),
+            fade_after: None,
+            filled_at: Vec::new(),
+            updates: 0,
         }
     }
 
-    /// Push the Tetromino into the grid and return the number of lines completed.
-    pub fn freeze_tetromino(&mut self, tetromino: &mut Tetromino) -> Option<u64> {
+    /// Returns a grid of half the resolution of a grid of the given size, whose blocks are rendered twice as big.
+    ///
+    /// It keeps 2 hidden rows so that its visible part is rendered at the same place as the one of the full grid.
+    pub fn new_big(x: f64, y: f64, nb_columns: u32, nb_rows: u32) -> TetrisGrid {
+        let mut grid = TetrisGrid::new(
+            x,
+            y - 2.0 * BLOCK_SIZE,
+            nb_columns / 2,
+            (nb_rows - 2) / 2 + 2,
+        );
+        grid.scale = 2.0;
+        grid
+    }
+
+    /// Makes the blocks filled from now on fade away after the given number of updates, or stay visible if None.
+    pub fn set_fade(&mut self, fade_after: Option<u64>) {
+        self.fade_after = fade_after;
+        self.filled_at = match fade_after {
+            Some(_) => vec![vec![self.updates; self.nb_columns as usize]; self.nb_rows as usize],
+            None => Vec::new(),
+        };
+    }
+
+    /// Counts an update of the game, making the blocks older.
+    pub fn tick(&mut self) {
+        self.updates += 1;
+    }
+
+    /// Returns the number of updates the block in the cell has been there, or None if the blocks don't fade.
+    pub(super) fn age(&self, x: usize, y: usize) -> Option<u64> {
+        let filled_at = self.filled_at.get(y)?.get(x)?;
+        Some(self.updates - filled_at)
+    }
+
+    /// Returns the number of columns of the grid.
+    pub fn nb_columns(&self) -> u32 {
+        self.nb_columns
+    }
+
+    /// Push the Tetromino into the grid and return the lines completed.
+    pub fn freeze_tetromino(&mut self, tetromino: &mut Tetromino) -> Option<ClearedLines> {
         let mut game_over = true;
         let mut blocks = tetromino.split();
         for block in &mut blocks {
-            self.matrix[block.position.y as usize][block.position.x as usize] = Some(block.color);
-            self.line_sum[block.position.y as usize] += 1;
+            self.set_cell(
+                block.position.x as usize,
+                block.position.y as usize,
+                Some(block.color),
+            );
             // if there's a block below the top of the visible grid, continue playing
             if block.position.y as usize > 1 {
                 game_over = false;
@@ -43,64 +88,138 @@ impl TetrisGrid {
         if game_over {
             return None;
         }
-        let mut score = 0;
+        let mut cleared_lines = ClearedLines::default();
         for y in 0..self.nb_rows {
-            if self.line_sum[y as usize] == self.nb_columns as u8 {
+            if self.bitboard.is_row_full(y as usize) {
                 // TODO neatly separate this in a private function
-                self.matrix.remove(y as usize);
+                let row = self.matrix.remove(y as usize);
                 self.matrix.insert(0, vec![None; self.nb_columns as usize]);
+                self.move_filled_at(y as usize, 0);
 
-                self.line_sum.remove(y as usize);
-                self.line_sum.insert(0, 0);
-                score += 1;
+                self.bitboard.remove_row(y as usize);
+                cleared_lines.total += 1;
+                if row
+                    .iter()
+                    .any(|cell| matches!(cell, Some(TetrisColor::Grey)))
+                {
+                    cleared_lines.garbage += 1;
+                }
             }
         }
-        Some(score)
+        cleared_lines.perfect_clear = cleared_lines.total > 0
+            && (0..self.nb_rows).all(|y| self.bitboard.row(y as usize) == 0);
+        Some(cleared_lines)
     }
 
-    /// Adds the specified number of lines at the bottom of the grid. The lines will be filled with blocks except for one column.
-    pub fn add_garbage(&mut self, completed_lines: u64) {
-        if completed_lines < 2 {
+    /// Adds the garbage sent by an adversary who completed the specified number of lines at the bottom of the grid.
+    /// The lines will be filled with blocks except for one column, chosen with the given rng.
+    pub fn add_garbage<R: Rng>(&mut self, completed_lines: u64, rng: &mut R) {
+        let lines_to_add = lines_of_garbage(completed_lines);
+        if lines_to_add == 0 {
             return;
         }
-        println!(
-            "the garbage creating function was called for {} lines",
-            completed_lines
-        );
-        let lines_to_add = if completed_lines == 4 {
-            4
-        } else {
-            completed_lines - 1
-        };
 
         /*****************************
          *     CHANGING THE GRID     *
          *****************************/
 
         // store the column index that will be empty
-        let mut rng = rand::thread_rng();
         let empty = rng.gen::<u32>() % self.nb_columns;
 
         for _ in 0..lines_to_add {
-            // move the matrix and line_sum one line up
-            self.line_sum
-                .insert(self.nb_rows as usize, (self.nb_columns - 1) as u8);
-            self.line_sum.remove(0);
+            self.push_garbage_row(empty);
+        }
+    }
+
+    /// Adds rows of garbage at the bottom of the grid, each one filled except for one hole.
+    ///
+    /// A row keeps the hole of the row above it if that one is garbage, except with the probability messiness
+    /// (between 0 and 1) where the hole moves to another column chosen with the given rng.
+    pub fn add_garbage_rows<R: Rng>(&mut self, nb_rows: u64, messiness: f64, rng: &mut R) {
+        for _ in 0..nb_rows {
+            let bottom_row = self.bitboard.row(self.nb_rows as usize - 1);
+            let empty_cells = !bottom_row & ((1u32 << self.nb_columns) - 1) as u16;
+            let empty = if empty_cells.count_ones() != 1 {
+                rng.gen_range(0..self.nb_columns)
+            } else if rng.gen_bool(messiness) {
+                let hole = empty_cells.trailing_zeros();
+                (hole + rng.gen_range(1..self.nb_columns)) % self.nb_columns
+            } else {
+                empty_cells.trailing_zeros()
+            };
+            self.push_garbage_row(empty);
+        }
+    }
+
+    /// Moves the grid one line up and fills the bottom line with garbage, except in the column empty.
+    fn push_garbage_row(&mut self, empty: u32) {
+        // move the matrix and bitboard one line up
+        self.bitboard.push_bottom_row(!(1 << empty));
+
+        self.matrix
+            .insert(self.nb_rows as usize, vec![None; self.nb_columns as usize]);
+        for x in 0..self.nb_columns {
+            // add blocks in the entire line except in one column
+            if x != empty {
+                self.matrix[self.nb_rows as usize][x as usize] = Some(TetrisColor::Grey);
+            } else {
+                self.matrix[self.nb_rows as usize][x as usize] = None;
+            }
+        }
+        self.matrix.remove(0);
+        self.move_filled_at(0, self.nb_rows as usize - 1);
+    }
+
+    /// Moves a row of filled_at like the matrix when a row is removed, the inserted row counting as filled now.
+    fn move_filled_at(&mut self, from: usize, to: usize) {
+        if self.filled_at.is_empty() {
+            return;
+        }
+        self.filled_at.remove(from);
+        self.filled_at
+            .insert(to, vec![self.updates; self.nb_columns as usize]);
+    }
 
-            self.matrix
-                .insert(self.nb_rows as usize, vec![None; self.nb_columns as usize]);
+    /// Flips the grid horizontally.
+    pub fn mirror(&mut self) {
+        for y in 0..self.nb_rows as usize {
+            self.matrix[y].reverse();
+            if let Some(row) = self.filled_at.get_mut(y) {
+                row.reverse();
+            }
+            for x in 0..self.nb_columns as usize {
+                self.bitboard.set(x, y, self.matrix[y][x].is_some());
+            }
+        }
+    }
+
+    /// Empties the given number of rows at the top of the grid, hidden rows included.
+    pub fn clear_top_rows(&mut self, nb_rows: u32) {
+        for y in 0..nb_rows.min(self.nb_rows) {
             for x in 0..self.nb_columns {
-                // add blocks in the entire line except in one column
-                if x != empty {
-                    self.matrix[self.nb_rows as usize][x as usize] = Some(TetrisColor::Grey);
-                } else {
-                    self.matrix[self.nb_rows as usize][x as usize] = None;
-                }
+                self.set_cell(x as usize, y as usize, None);
+            }
+        }
+    }
+
+    /// Fills the bottom of the grid with the given rows of cells, from the highest one.
+    ///
+    /// The rows that don't fit in the grid are the highest ones, they are left out like the cells beyond the last column.
+    pub fn set_bottom_rows(&mut self, rows: &[Vec<Option<TetrisColor>>]) {
+        let first_row = rows.len().saturating_sub(self.nb_rows as usize);
+        let top = self.nb_rows as usize - (rows.len() - first_row);
+        for (y, row) in rows[first_row..].iter().enumerate() {
+            for (x, &cell) in row.iter().take(self.nb_columns as usize).enumerate() {
+                self.set_cell(x, top + y, cell);
             }
-            self.matrix.remove(0);
         }
     }
 
+    /// Returns the cells of the rows, from the highest one, the hidden ones included.
+    pub fn rows(&self) -> &[Vec<Option<TetrisColor>>] {
+        &self.matrix
+    }
+
     /// Empty the grid.
     pub fn null(&mut self) {
         for row in self.matrix.iter_mut() {
@@ -108,7 +227,29 @@ impl TetrisGrid {
                 *cell = None;
             }
         }
-        self.line_sum = vec![0; self.nb_rows as usize];
+        self.bitboard.clear();
+    }
+
+    /// Sets the content of a cell of the grid.
+    pub fn set_cell(&mut self, x: usize, y: usize, cell: Option<TetrisColor>) {
+        self.matrix[y][x] = cell;
+        self.bitboard.set(x, y, cell.is_some());
+        if let Some(filled_at) = self.filled_at.get_mut(y) {
+            filled_at[x] = self.updates;
+        }
+    }
+
+    /// Sets a cell of a digit of the countdown, given in the coordinates of a grid of the default size,
+    /// so that the digit stays centered in a smaller grid.
+    fn set_digit_cell(&mut self, x: i32, y: i32, cell: Option<TetrisColor>) {
+        let x = x + (self.nb_columns as i32 - NB_COLUMNS as i32) / 2;
+        let y = y + (self.nb_rows as i32 - NB_ROWS as i32) / 2;
+        self.set_cell(x as usize, y as usize, cell);
+    }
+
+    /// Returns the bitboard of the filled cells of the grid.
+    pub fn bitboard(&self) -> &Bitboard {
+        &self.bitboard
     }
 
     /// Draw a 1 with blocks of the same color as tetromino.
@@ -116,17 +257,17 @@ impl TetrisGrid {
         let tetris_color = tetromino.blocks[0].color;
         self.null();
         // starting from the top of the number
-        // careful, it's matrix[y][x] and y increases towards the bottom
-        self.matrix[9][5] = Some(tetris_color);
-        self.matrix[10][4] = Some(tetris_color);
-        self.matrix[10][5] = Some(tetris_color);
-        //? self.matrix[11][3] = Some(tetris_color);
-        self.matrix[11][5] = Some(tetris_color);
-        self.matrix[12][5] = Some(tetris_color);
-        self.matrix[13][3] = Some(tetris_color);
-        self.matrix[13][4] = Some(tetris_color);
-        self.matrix[13][5] = Some(tetris_color);
-        self.matrix[13][6] = Some(tetris_color);
+        // careful, y increases towards the bottom
+        self.set_digit_cell(5, 9, Some(tetris_color));
+        self.set_digit_cell(4, 10, Some(tetris_color));
+        self.set_digit_cell(5, 10, Some(tetris_color));
+        //? self.set_digit_cell(3, 11, Some(tetris_color));
+        self.set_digit_cell(5, 11, Some(tetris_color));
+        self.set_digit_cell(5, 12, Some(tetris_color));
+        self.set_digit_cell(3, 13, Some(tetris_color));
+        self.set_digit_cell(4, 13, Some(tetris_color));
+        self.set_digit_cell(5, 13, Some(tetris_color));
+        self.set_digit_cell(6, 13, Some(tetris_color));
     }
 
     /// Draw a 2 with blocks of the same color as tetromino.
@@ -134,17 +275,17 @@ impl TetrisGrid {
         let tetris_color = tetromino.blocks[0].color;
         self.null();
         // starting from the top of the number
-        // careful, it's matrix[y][x] and y increases towards the bottom
-        self.matrix[9][4] = Some(tetris_color);
-        self.matrix[9][5] = Some(tetris_color);
-        self.matrix[10][3] = Some(tetris_color);
-        self.matrix[10][6] = Some(tetris_color);
-        self.matrix[11][5] = Some(tetris_color);
-        self.matrix[12][4] = Some(tetris_color);
-        self.matrix[13][3] = Some(tetris_color);
-        self.matrix[13][4] = Some(tetris_color);
-        self.matrix[13][5] = Some(tetris_color);
-        self.matrix[13][6] = Some(tetris_color);
+        // careful, y increases towards the bottom
+        self.set_digit_cell(4, 9, Some(tetris_color));
+        self.set_digit_cell(5, 9, Some(tetris_color));
+        self.set_digit_cell(3, 10, Some(tetris_color));
+        self.set_digit_cell(6, 10, Some(tetris_color));
+        self.set_digit_cell(5, 11, Some(tetris_color));
+        self.set_digit_cell(4, 12, Some(tetris_color));
+        self.set_digit_cell(3, 13, Some(tetris_color));
+        self.set_digit_cell(4, 13, Some(tetris_color));
+        self.set_digit_cell(5, 13, Some(tetris_color));
+        self.set_digit_cell(6, 13, Some(tetris_color));
     }
 
     /// Draw a 3 with blocks of the same color as tetromino.
@@ -152,15 +293,62 @@ impl TetrisGrid {
         let tetris_color = tetromino.blocks[0].color;
         self.null();
         // starting from the top of the number
-        // careful, it's matrix[y][x] and y increases towards the bottom
-        self.matrix[9][4] = Some(tetris_color);
-        self.matrix[9][5] = Some(tetris_color);
-        self.matrix[10][3] = Some(tetris_color);
-        self.matrix[10][6] = Some(tetris_color);
-        self.matrix[11][5] = Some(tetris_color);
-        self.matrix[12][3] = Some(tetris_color);
-        self.matrix[12][6] = Some(tetris_color);
-        self.matrix[13][4] = Some(tetris_color);
-        self.matrix[13][5] = Some(tetris_color);
+        // careful, y increases towards the bottom
+        self.set_digit_cell(4, 9, Some(tetris_color));
+        self.set_digit_cell(5, 9, Some(tetris_color));
+        self.set_digit_cell(3, 10, Some(tetris_color));
+        self.set_digit_cell(6, 10, Some(tetris_color));
+        self.set_digit_cell(5, 11, Some(tetris_color));
+        self.set_digit_cell(3, 12, Some(tetris_color));
+        self.set_digit_cell(6, 12, Some(tetris_color));
+        self.set_digit_cell(4, 13, Some(tetris_color));
+        self.set_digit_cell(5, 13, Some(tetris_color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Occupancy, TetrominoKind};
+    use super::*;
+
+    /// Panics if a cell of the bitboard doesn't match the one of the matrix.
+    fn assert_in_sync(grid: &TetrisGrid) {
+        for (y, row) in grid.rows().iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                assert_eq!(
+                    grid.bitboard().is_filled(x as i8, y as i8),
+                    cell.is_some(),
+                    "cell ({x}, {y})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bitboard_follows_the_matrix() {
+        let mut grid = TetrisGrid::new(0.0, 0.0, NB_COLUMNS, NB_ROWS);
+        let bottom = NB_ROWS as usize - 1;
+        // the bottom row is filled apart from the four cells where the I falls
+        for x in (0..3).chain(7..10) {
+            grid.set_cell(x, bottom, Some(TetrisColor::Grey));
+        }
+        grid.set_cell(0, bottom - 1, Some(TetrisColor::Red));
+        assert_in_sync(&grid);
+
+        let mut tetromino = Tetromino::new(TetrominoKind::I, &grid).unwrap();
+        tetromino.hard_drop(&grid);
+        let cleared_lines = grid.freeze_tetromino(&mut tetromino).unwrap();
+        assert_eq!(cleared_lines.total, 1);
+        assert!(grid.rows()[bottom][0].is_some());
+        assert_in_sync(&grid);
+
+        grid.mirror();
+        assert!(grid.rows()[bottom][9].is_some());
+        assert_in_sync(&grid);
+
+        grid.push_garbage_row(4);
+        assert!(grid.rows()[bottom][4].is_none());
+        assert!(grid.rows()[bottom - 1][9].is_some());
+        assert_in_sync(&grid);
     }
 }
@@ -4,119 +4,175 @@ use super::{
     point::{Point, Transform},
     rotation_state::{RotationState, RotationStateUpdate},
     translation_rotation::RotationType,
-    GridLine, GridMatrix, Tetromino, TetrominoKind, TranslationRotation,
+    Occupancy, TSpin, Tetromino, TetrominoKind, TranslationRotation,
 };
 use core::fmt::Display;
 use std::fmt::Formatter;
 
 impl Tetromino {
     /// Moves the Tetromino down one cell if it's possible.
-    pub fn fall(&mut self, matrix: &[GridLine]) -> Result<(), ()> {
-        self.blocks = self.check_possible(matrix, TranslationRotation::fall())?;
+    pub(crate) fn fall<G: Occupancy + ?Sized>(&mut self, grid: &G) -> Result<(), ()> {
+        self.blocks = self.check_possible(grid, TranslationRotation::fall())?;
         self.center.go_down();
         Ok(())
     }
 
     /// Moves the Tetromino as far down as possible.
-    pub fn hard_drop(&mut self, matrix: &GridMatrix) {
-        match self.fall(matrix) {
-            Err(()) => {}
-            Ok(()) => self.hard_drop(matrix),
+    pub fn hard_drop<G: Occupancy + ?Sized>(&mut self, grid: &G) {
+        let distance = grid.drop_distance(&self.cells());
+        for block in self.blocks.iter_mut() {
+            block.position.y += distance;
         }
+        self.center.y += distance;
     }
 
     /// Moves the Tetromino one cell to the left if it's possible.
-    pub fn left(&mut self, matrix: &GridMatrix) {
-        if let Ok(new_blocks) = self.check_possible(matrix, TranslationRotation::left()) {
+    pub fn left<G: Occupancy + ?Sized>(&mut self, grid: &G) {
+        if let Ok(new_blocks) = self.check_possible(grid, TranslationRotation::left()) {
             self.blocks = new_blocks;
             self.center.go_left();
         }
     }
 
     /// Moves the Tetromino one cell to the right if it's possible.
-    pub fn right(&mut self, matrix: &GridMatrix) {
-        if let Ok(new_blocks) = self.check_possible(matrix, TranslationRotation::right()) {
+    pub fn right<G: Occupancy + ?Sized>(&mut self, grid: &G) {
+        if let Ok(new_blocks) = self.check_possible(grid, TranslationRotation::right()) {
             self.blocks = new_blocks;
             self.center.go_right();
         }
     }
 
-    /// Turns the Tetromino clockwise if it's possible, eventually using wall-kicks.
-    pub fn turn_clockwise(&mut self, matrix: &GridMatrix) {
+    /// Turns the Tetromino clockwise if it's possible, eventually using wall-kicks, and returns the index of the wall-kick used.
+    pub fn turn_clockwise<G: Occupancy + ?Sized>(&mut self, grid: &G) -> Option<usize> {
+        self.turn(grid, RotationType::Clockwise)
+    }
+
+    /// Turns the Tetromino counterclockwise if it's possible, eventually using wall-kicks, and returns the index of the wall-kick used.
+    pub fn turn_counterclockwise<G: Occupancy + ?Sized>(&mut self, grid: &G) -> Option<usize> {
+        self.turn(grid, RotationType::Counterclockwise)
+    }
+
+    /// Turns the Tetromino like on the NES if it's possible: without wall-kicks,
+    /// the I, S and Z going back and forth between two orientations.
+    pub fn turn_without_kicks<G: Occupancy + ?Sized>(
+        &mut self,
+        grid: &G,
+        clockwise: bool,
+    ) -> Option<usize> {
         if self.kind == TetrominoKind::O {
-            return;
+            return None;
         };
-        let wall_kicks_translations = TetrominoKind::wall_kicks_translations(
-            &self.kind,
-            RotationType::Clockwise,
-            self.rotation_status,
-        );
-        for wall_kick in &wall_kicks_translations {
-            match self.check_possible(
-                matrix,
-                TranslationRotation::new(*wall_kick, RotationType::Clockwise, &self.center),
-            ) {
-                Err(()) => {
-                    continue;
-                }
-                Ok(new_blocks) => {
-                    self.blocks = new_blocks;
-                    self.rotation_status.clockwise();
-                    self.center += *wall_kick;
-                    return;
-                }
+        let rtype = match (self.kind, self.rotation_status) {
+            (TetrominoKind::I | TetrominoKind::S | TetrominoKind::Z, RotationState::R0) => {
+                RotationType::Clockwise
             }
-        }
+            (TetrominoKind::I | TetrominoKind::S | TetrominoKind::Z, _) => {
+                RotationType::Counterclockwise
+            }
+            _ if clockwise => RotationType::Clockwise,
+            _ => RotationType::Counterclockwise,
+        };
+        // the first translation is the rotation without wall-kick
+        let wall_kicks_translations =
+            TetrominoKind::wall_kicks_translations(&self.kind, rtype, self.rotation_status);
+        self.try_wall_kicks(grid, rtype, &wall_kicks_translations[..1])
     }
 
-    /// Turns the Tetromino counterclockwise if it's possible, eventually using wall-kicks.
-    pub fn turn_counterclockwise(&mut self, matrix: &GridMatrix) {
+    /// Turns the Tetromino if it's possible and returns the index of the wall-kick that was used.
+    pub(super) fn turn<G: Occupancy + ?Sized>(
+        &mut self,
+        grid: &G,
+        rtype: RotationType,
+    ) -> Option<usize> {
         if self.kind == TetrominoKind::O {
-            return;
+            return None;
         };
-        let wall_kicks_translations = TetrominoKind::wall_kicks_translations(
-            &self.kind,
-            RotationType::Counterclockwise,
-            self.rotation_status,
-        );
-        for wall_kick in &wall_kicks_translations {
+        let wall_kicks_translations =
+            TetrominoKind::wall_kicks_translations(&self.kind, rtype, self.rotation_status);
+        self.try_wall_kicks(grid, rtype, &wall_kicks_translations)
+    }
+
+    /// Turns the Tetromino with the first of the wall-kicks that is possible and returns its index.
+    fn try_wall_kicks<G: Occupancy + ?Sized>(
+        &mut self,
+        grid: &G,
+        rtype: RotationType,
+        wall_kicks_translations: &[Point],
+    ) -> Option<usize> {
+        for (kick, wall_kick) in wall_kicks_translations.iter().enumerate() {
             match self.check_possible(
-                matrix,
-                TranslationRotation::new(*wall_kick, RotationType::Counterclockwise, &self.center),
+                grid,
+                TranslationRotation::new(*wall_kick, rtype, &self.center),
             ) {
                 Err(()) => {
                     continue;
                 }
                 Ok(new_blocks) => {
                     self.blocks = new_blocks;
-                    self.rotation_status.counterclockwise();
+                    match rtype {
+                        RotationType::Clockwise => self.rotation_status.clockwise(),
+                        RotationType::Counterclockwise => self.rotation_status.counterclockwise(),
+                    }
                     self.center += *wall_kick;
-                    return;
+                    return Some(kick);
                 }
             }
         }
+        None
+    }
+
+    /// Returns the kind of T-spin the Tetromino would score if it was locked right after a rotation using the given wall-kick.
+    ///
+    /// It uses the 3-corner rule: at least 3 of the 4 cells diagonal to the center of the T must be filled (or outside the grid).
+    /// The T-spin is a mini one unless both corners the T is pointing to are filled or the last wall-kick was used.
+    pub fn t_spin<G: Occupancy + ?Sized>(&self, grid: &G, kick: usize) -> TSpin {
+        if self.kind != TetrominoKind::T {
+            return TSpin::None;
+        }
+        let is_filled = |dx: i8, dy: i8| grid.is_filled(self.center.x + dx, self.center.y + dy);
+        // corners in order : top left, top right, bottom right, bottom left
+        let corners = [
+            is_filled(-1, -1),
+            is_filled(1, -1),
+            is_filled(1, 1),
+            is_filled(-1, 1),
+        ];
+        if corners.iter().filter(|filled| **filled).count() < 3 {
+            return TSpin::None;
+        }
+        // the two corners on the side the T is pointing to
+        let (front_a, front_b) = match self.rotation_status {
+            RotationState::R0 => (0, 1),
+            RotationState::R1 => (1, 2),
+            RotationState::R2 => (2, 3),
+            RotationState::R3 => (3, 0),
+        };
+        if (corners[front_a] && corners[front_b]) || kick == 4 {
+            TSpin::Full
+        } else {
+            TSpin::Mini
+        }
     }
 
     /// Returns the resulting position of the Tetromino Blocks if the movement is possible.
-    pub fn check_possible(
+    pub(crate) fn check_possible<G: Occupancy + ?Sized>(
         &self,
-        matrix: &GridMatrix,
+        grid: &G,
         movement: TranslationRotation,
     ) -> Result<[Block; 4], ()> {
-        let mut new_blocks = vec![];
-        for i in 0..4 {
-            new_blocks.push(self.blocks[i].move_to(matrix, &movement)?);
+        let mut blocks = self.blocks;
+        for block in blocks.iter_mut() {
+            *block = block.move_to(grid, &movement)?;
         }
-        let blocks = [new_blocks[0], new_blocks[1], new_blocks[2], new_blocks[3]];
         Ok(blocks)
     }
 
     /// Returns an Option eventually containing a Tetromino if its starting position is empty.
-    pub fn new(kind: TetrominoKind, matrix: &GridMatrix) -> Option<Tetromino> {
+    pub fn new<G: Occupancy + ?Sized>(kind: TetrominoKind, grid: &G) -> Option<Tetromino> {
         let positions = kind.get_initial_position();
         let color = kind.get_color();
         for i in 1..5 {
-            if matrix[positions[2 * i + 1] as usize][positions[2 * i] as usize].is_some() {
+            if grid.is_filled(positions[2 * i], positions[2 * i + 1]) {
                 return None;
             }
         }
@@ -165,6 +221,25 @@ impl Tetromino {
         ];
     }
 
+    /// Moves the Tetromino the given number of columns to the right, or to the left if negative, without checking the grid.
+    pub fn shift(&mut self, columns: i8) {
+        for block in self.blocks.iter_mut() {
+            block.position.x += columns;
+        }
+        self.center.x += columns;
+    }
+
+    /// Returns the kind of the Tetromino.
+    pub fn kind(&self) -> TetrominoKind {
+        self.kind
+    }
+
+    /// Returns the coordinates (x, y) of the 4 cells occupied by the Tetromino.
+    pub fn cells(&self) -> [(i8, i8); 4] {
+        self.blocks
+            .map(|block| (block.position.x, block.position.y))
+    }
+
     /// Returns a ghost copy of the Tetromino.
     pub fn make_ghost_copy(&mut self) -> Tetromino {
         let mut ghost = *self;
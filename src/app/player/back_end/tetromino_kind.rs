@@ -6,7 +6,7 @@ use crate::assets::TetrisColor;
 
 impl TetrominoKind {
     /// Returns the name of the TetrominoKind variant.
-    pub(super) fn get(&self) -> String {
+    pub fn get(&self) -> String {
         match self {
             TetrominoKind::I => "I".to_owned(),
             TetrominoKind::O => "O".to_owned(),
@@ -18,6 +18,19 @@ impl TetrominoKind {
         }
     }
 
+    /// Returns the kind following this one in the order I, O, T, S, Z, J, L, going back to I after L.
+    pub fn next(&self) -> TetrominoKind {
+        match self {
+            TetrominoKind::I => TetrominoKind::O,
+            TetrominoKind::O => TetrominoKind::T,
+            TetrominoKind::T => TetrominoKind::S,
+            TetrominoKind::S => TetrominoKind::Z,
+            TetrominoKind::Z => TetrominoKind::J,
+            TetrominoKind::J => TetrominoKind::L,
+            TetrominoKind::L => TetrominoKind::I,
+        }
+    }
+
     /// Returns the initial position of the center and the blocks of a tetromino.
     pub(super) fn get_initial_position(&self) -> [i8; 10] {
         // cf https://tetris.fandom.com/wiki/SRS#Spawn_Orientation_and_Location
@@ -34,7 +47,7 @@ impl TetrominoKind {
     }
 
     /// Returns the color associated with the TetrominoKind.
-    pub(super) fn get_color(&self) -> TetrisColor {
+    pub fn get_color(&self) -> TetrisColor {
         match self {
             TetrominoKind::I => TetrisColor::Cyan,
             TetrominoKind::O => TetrisColor::Yellow,
@@ -9,6 +9,7 @@ pub(super) enum Rotation {
 }
 
 /// Rotation types.
+#[derive(Clone, Copy, PartialEq)]
 pub(super) enum RotationType {
     Clockwise,
     Counterclockwise,
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt::Formatter;
 
 /// Push back pop front circular buffer.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct CircularBuffer<const K: usize, T: Default + Copy + Serialize + Display>
 where
     [T; K]: Serialize + for<'a> Deserialize<'a>,
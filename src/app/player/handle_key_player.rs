@@ -1,14 +1,9 @@
 //! Defines handle_key functions of [LocalPlayer].
 //!
-//! [handle_key_press()](LocalPlayer::handle_key_press()) is called when a key is pressed.
-//! [handle_key_release()](LocalPlayer::handle_key_release()) is called when a key is released.
-use super::LocalPlayer;
-use crate::{
-    app::GameFlowChange,
-    app::RunningState,
-    settings::{Keybindings, PAUSE_KEYS, RESTART_KEYS},
-};
-use piston::Key;
+//! [handle_key_press()](LocalPlayer::handle_key_press()) is called when a key or a controller input is pressed.
+//! [handle_key_release()](LocalPlayer::handle_key_release()) is called when it's released.
+use super::{back_end::Move, LocalPlayer};
+use crate::{app::GameFlowChange, app::RunningState, input::Input, settings::Keybindings};
 
 impl LocalPlayer {
     /// handle_key_press is called when a key is pressed.
@@ -24,7 +19,7 @@ impl LocalPlayer {
     pub fn handle_key_press(
         &mut self,
         keybindings: &Keybindings,
-        key: Key,
+        key: Input,
         running: RunningState,
     ) -> GameFlowChange {
         /******************************
@@ -35,15 +30,15 @@ impl LocalPlayer {
             return GameFlowChange::Other;
         }
 
-        self.keyboard.set_pressed(key);
+        self.keyboard.set_pressed(key, self.rules.auto_shift_delay);
 
         /******************************
          *       UNACTIVE GAME        *
          ******************************/
 
-        // the unactive game only listens to the RESTART_KEYS
+        // the unactive game only listens to the restart keys
         if running == RunningState::NotRunning {
-            if self.keyboard.is_any_last_pressed(&RESTART_KEYS) {
+            if self.keyboard.is_any_last_pressed(&keybindings.restart_keys) {
                 return GameFlowChange::Restart;
             } else {
                 return GameFlowChange::Other;
@@ -54,15 +49,16 @@ impl LocalPlayer {
          * (ABOUT TO BE) PAUSED GAME  *
          ******************************/
 
-        // the paused game only listens to the PAUSE_KEYS
+        // the paused game only listens to the pause keys
         if running == RunningState::Paused {
-            if self.keyboard.is_any_last_pressed(&PAUSE_KEYS) {
+            if self.keyboard.is_any_last_pressed(&keybindings.pause_keys) {
                 return GameFlowChange::Resume;
             } else {
                 return GameFlowChange::Other;
             }
-        // the game pauses if PAUSE_KEYS are pressed
-        } else if running == RunningState::Running && self.keyboard.is_any_last_pressed(&PAUSE_KEYS)
+        // the game pauses if the pause keys are pressed
+        } else if running == RunningState::Running
+            && self.keyboard.is_any_last_pressed(&keybindings.pause_keys)
         {
             return GameFlowChange::Pause;
         }
@@ -71,15 +67,24 @@ impl LocalPlayer {
          *         ACTIVE GAME        *
          ******************************/
 
+        if keybindings.is_bound(&key) {
+            self.stats.keys += 1;
+        }
+        // the next tetromino can't move before the end of the entry delay
+        if self.frame_counter < self.entry_frame {
+            return GameFlowChange::Other;
+        }
+        if keybindings.is_bound(&key) && !keybindings.hold_tetromino_keys.contains(&key) {
+            self.piece_presses += 1;
+        }
+
         // Pressed once events
         if self
             .keyboard
             .is_any_last_pressed(&keybindings.rotate_clockwise_keys)
         {
             // rotate once the tetromino
-            self.player_screen
-                .active_tetromino
-                .turn_clockwise(&self.player_screen.grid.matrix);
+            self.apply_move(Move::RotateClockwise);
         }
         // it's not an if else in case the player put the same keybindings for both clock and counter...
         if self
@@ -87,76 +92,42 @@ impl LocalPlayer {
             .is_any_last_pressed(&keybindings.rotate_counterclockwise_keys)
         {
             // rotate once the tetromino
-            self.player_screen
-                .active_tetromino
-                .turn_counterclockwise(&self.player_screen.grid.matrix);
+            self.apply_move(Move::RotateCounterclockwise);
         }
 
-        if self
-            .keyboard
-            .is_any_last_pressed(&keybindings.hold_tetromino_keys)
+        if self.rules.hold
+            && self
+                .keyboard
+                .is_any_last_pressed(&keybindings.hold_tetromino_keys)
         {
             // hold the tetromino
-            if let Some(mut saved) = self.player_screen.saved_tetromino {
-                self.player_screen.active_tetromino.reset_position();
-
-                std::mem::swap(&mut saved, &mut self.player_screen.active_tetromino);
-                self.player_screen.saved_tetromino = Some(saved);
-            } else {
-                self.player_screen.active_tetromino.reset_position();
-
-                self.player_screen.saved_tetromino = Some(self.player_screen.active_tetromino);
-                self.get_new_tetromino();
-            }
+            self.hold();
         }
 
         // move the tetromino left or right
         if self.keyboard.is_any_last_pressed(&keybindings.left_keys) {
-            self.player_screen
-                .active_tetromino
-                .left(&self.player_screen.grid.matrix);
+            self.apply_move(Move::Left);
         }
         // it's not an if else in case the player put the same keybindings for both left and right...
         if self.keyboard.is_any_last_pressed(&keybindings.right_keys) {
-            self.player_screen
-                .active_tetromino
-                .right(&self.player_screen.grid.matrix);
+            self.apply_move(Move::Right);
         }
 
-        if self
-            .keyboard
-            .is_any_last_pressed(&keybindings.hard_drop_keys)
+        if self.rules.hard_drop
+            && self
+                .keyboard
+                .is_any_last_pressed(&keybindings.hard_drop_keys)
         {
             // hard drop the tetromino
-            self.player_screen
-                .active_tetromino
-                .hard_drop(&self.player_screen.grid.matrix);
-            match self
-                .player_screen
-                .grid
-                .freeze_tetromino(&mut self.player_screen.active_tetromino)
-            {
-                Some(completed_lines) => {
-                    self.player_screen.new_completed_lines = completed_lines;
-                    if self.player_screen.new_completed_lines != 0 {
-                        println!(
-                            "{} lines were completed",
-                            self.player_screen.new_completed_lines
-                        );
-                    }
-                    self.player_screen.score += self.player_screen.new_completed_lines;
-                    self.get_new_tetromino();
-                }
-                None => {
-                    self.declare_game_over();
-                    return GameFlowChange::GameOver;
-                }
+            self.apply_move(Move::HardDrop);
+            if self.get_game_over() {
+                return GameFlowChange::GameOver;
             }
         }
         GameFlowChange::Other
     }
 
-    pub fn handle_key_release(&mut self, key: Key) {
+    pub fn handle_key_release(&mut self, key: Input) {
         self.keyboard.set_released(key);
     }
 }
@@ -0,0 +1,142 @@
+//! Defines [History], which keeps the state of the game at each placement so that the placements can be undone when practicing.
+use super::back_end::{TetrisGrid, Tetromino, TetrominoKind};
+use super::{circular_buffer::CircularBuffer, LocalPlayer, PlayerStats};
+use crate::settings::NB_NEXT_TETROMINO;
+use rand_pcg::Pcg32;
+
+/// State of a [LocalPlayer] when a tetromino appears, restored to undo the placements that followed.
+#[derive(Clone)]
+struct Snapshot {
+    grid: TetrisGrid,
+    score: u64,
+    active_tetromino: Tetromino,
+    saved_tetromino: Option<Tetromino>,
+    fifo_next_tetromino: CircularBuffer<NB_NEXT_TETROMINO, Tetromino>,
+    bag_of_tetromino: Vec<TetrominoKind>,
+    /// Position of the random generator, so that the same tetrominos are dealt after an undo.
+    rng: Pcg32,
+    last_dealt: Option<TetrominoKind>,
+    stats: PlayerStats,
+}
+
+/// Placements of the game that can be undone and redone.
+pub(super) struct History {
+    /// States before the placements, the last one is the state before the last placement.
+    undo: Vec<Snapshot>,
+    /// States after the undone placements, the last one is the state after the last undone placement.
+    redo: Vec<Snapshot>,
+    /// State when the active tetromino appeared.
+    current: Snapshot,
+}
+
+impl LocalPlayer {
+    /// Starts or stops keeping the states of the game to undo the placements, from the current state.
+    pub fn set_undoable(&mut self, undoable: bool) {
+        self.history = undoable.then(|| History {
+            undo: vec![],
+            redo: vec![],
+            current: self.snapshot(),
+        });
+    }
+
+    /// Goes back to the state before the last placement.
+    ///
+    /// Returns false if there's no placement to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(history) = &mut self.history else {
+            return false;
+        };
+        let Some(previous) = history.undo.pop() else {
+            return false;
+        };
+        let current = std::mem::replace(&mut history.current, previous.clone());
+        history.redo.push(current);
+        self.restore(previous);
+        true
+    }
+
+    /// Places again the last undone placement, if any.
+    pub fn redo(&mut self) {
+        let Some(history) = &mut self.history else {
+            return;
+        };
+        let Some(next) = history.redo.pop() else {
+            return;
+        };
+        let current = std::mem::replace(&mut history.current, next.clone());
+        history.undo.push(current);
+        self.restore(next);
+    }
+
+    /// Undoes the placements back to the first tetromino of the bag, or of the previous bag if no tetromino of the bag was placed.
+    pub fn undo_to_bag_start(&mut self) {
+        while self.undo() {
+            if self
+                .stats
+                .pieces
+                .is_multiple_of(self.bag_size.max(1) as u64)
+            {
+                break;
+            }
+        }
+    }
+
+    /// Replaces the next tetromino by the tetromino of the following kind.
+    pub fn change_next_tetromino(&mut self) {
+        let Some(next) = self.player_screen.fifo_next_tetromino.pop() else {
+            return;
+        };
+        self.player_screen
+            .fifo_next_tetromino
+            .push_front(Tetromino::new_unchecked(next.kind().next()));
+        self.search_perfect_clear();
+    }
+
+    /// Keeps the state before the last placement, once the next tetromino appeared.
+    pub(super) fn record_placement(&mut self) {
+        if self.history.is_none() {
+            return;
+        }
+        let snapshot = self.snapshot();
+        let history = self.history.as_mut().unwrap();
+        let previous = std::mem::replace(&mut history.current, snapshot);
+        history.undo.push(previous);
+        // a new placement replaces the undone ones
+        history.redo.clear();
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            grid: self.player_screen.grid.clone(),
+            score: self.player_screen.score,
+            active_tetromino: self.player_screen.active_tetromino,
+            saved_tetromino: self.player_screen.saved_tetromino,
+            fifo_next_tetromino: self.player_screen.fifo_next_tetromino.clone(),
+            bag_of_tetromino: self.bag_of_tetromino.clone(),
+            rng: self.rng.clone(),
+            last_dealt: self.last_dealt,
+            stats: self.stats,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.player_screen.grid = snapshot.grid;
+        self.player_screen.score = snapshot.score;
+        self.player_screen.active_tetromino = snapshot.active_tetromino;
+        self.player_screen.saved_tetromino = snapshot.saved_tetromino;
+        self.player_screen.fifo_next_tetromino = snapshot.fifo_next_tetromino;
+        self.player_screen.new_completed_lines = 0;
+        self.bag_of_tetromino = snapshot.bag_of_tetromino;
+        self.rng = snapshot.rng;
+        self.last_dealt = snapshot.last_dealt;
+        self.stats = snapshot.stats;
+        // the tetromino doesn't lock nor wait as if it had just appeared
+        self.freeze_frame = 0;
+        self.entry_frame = 0;
+        self.last_rotation = None;
+        self.piece_presses = 0;
+        self.new_locks.clear();
+        self.update_ghost();
+        self.search_perfect_clear();
+    }
+}
@@ -1,9 +1,14 @@
 //! Defines the general implementation of [LocalPlayer].
-use super::back_end::{new_tetromino_bag, TetrisGrid, Tetromino, TranslationRotation};
+use super::back_end::{
+    finesse, nes_tetromino, new_tetromino_bag, ClearedLines, Move, PcProblem, Randomizer,
+    RotationSystem, TSpin, TetrisGrid, Tetromino, TetrominoKind, TranslationRotation,
+};
 use super::{
-    circular_buffer::CircularBuffer, pressed_keys::PressedKeys, LocalPlayer, PlayerScreen,
+    circular_buffer::CircularBuffer, pressed_keys::PressedKeys, LocalPlayer, Modifiers, PcHint,
+    PlayerScreen, PlayerStats, Rules,
 };
-use crate::{app::Countdown, assets::Assets, once, settings::*, PlayerConfig};
+use crate::assets::{Assets, TetrisColor};
+use crate::{app::Countdown, once, settings::*, PlayerConfig};
 use graphics::types::Matrix2d;
 use opengl_graphics::GlGraphics;
 use piston_window::Context;
@@ -11,6 +16,9 @@ use rand::SeedableRng;
 use rand_pcg::Pcg32;
 use std::net::TcpStream;
 
+/// Stream of the random generator of the garbage.
+const GARBAGE_RNG_STREAM: u64 = 0xda3e_39cb_94b9_5bdb;
+
 impl LocalPlayer {
     pub fn new(player_config: &PlayerConfig) -> Self {
         let grid = TetrisGrid::new(DEFAULT_GRID_X, DEFAULT_GRID_Y, NB_COLUMNS, NB_ROWS);
@@ -38,6 +46,9 @@ impl LocalPlayer {
             saved_tetromino: None,
             fifo_next_tetromino,
             ghost_tetromino: None,
+            has_hold: true,
+            nb_previews: NB_NEXT_TETROMINO,
+            hidden_queue: false,
             serialize_as_msg: true.into(),
         };
 
@@ -49,48 +60,202 @@ impl LocalPlayer {
             sender,
             remote_ip,
             garbage_to_be_added: 0,
+            bag_size: BAG_SIZE,
             rng,
+            garbage_rng: Pcg32::seed_from_u64(0),
+            pc_hint: None,
+            stats: PlayerStats::default(),
+            new_locks: Vec::new(),
+            frame_counter: 0,
+            entry_delay: 0,
+            entry_frame: 0,
+            top_out: true,
+            rules: Rules::default(),
+            handling: None,
+            last_dealt: None,
+            modifiers: Modifiers::default(),
+            last_rotation: None,
+            history: None,
+            placements: Vec::new(),
+            piece_presses: 0,
+            finesse_extra: 0,
+            retry_finesse_faults: false,
         }
     }
 
     pub fn renew(&mut self, seed: u64) {
-        self.player_screen.grid.null();
+        self.rng = Pcg32::seed_from_u64(seed);
+        // another stream of the same seed so that receiving garbage doesn't change the next pieces
+        self.garbage_rng = Pcg32::new(seed, GARBAGE_RNG_STREAM);
+        self.bag_of_tetromino.clear();
+        self.reset();
+    }
+
+    /// Starts a game right away from the given state instead of an empty grid, like a puzzle.
+    ///
+    /// - rows are placed at the bottom of the grid, see [TetrisGrid::set_bottom_rows()]
+    /// - pieces are dealt first, the first one being the active tetromino, with the [Randomizer::Fixed] they are the only ones
+    /// - hold is the held tetromino
+    pub fn load(
+        &mut self,
+        rows: &[Vec<Option<TetrisColor>>],
+        pieces: &[TetrominoKind],
+        hold: Option<TetrominoKind>,
+    ) {
+        // the bag is dealt from its end
+        self.bag_of_tetromino = pieces.iter().rev().copied().collect();
+        self.reset();
+        self.player_screen.grid.set_bottom_rows(rows);
+        self.player_screen.saved_tetromino = hold.map(Tetromino::new_unchecked);
+        self.update_ghost();
+        self.search_perfect_clear();
+    }
+
+    /// Empties the grid, deals the first tetrominos from the bag and resets the counters of the game.
+    fn reset(&mut self) {
+        let mut grid = if self.modifiers.big {
+            TetrisGrid::new_big(DEFAULT_GRID_X, DEFAULT_GRID_Y, NB_COLUMNS, NB_ROWS)
+        } else {
+            TetrisGrid::new(DEFAULT_GRID_X, DEFAULT_GRID_Y, NB_COLUMNS, NB_ROWS)
+        };
+        grid.set_fade(
+            self.modifiers
+                .invisible
+                .then_some((INVISIBLE_SECONDS * UPDATES_PER_SECOND as f64) as u64),
+        );
+        self.player_screen.grid = grid;
         self.player_screen.score = 0;
         self.player_screen.saved_tetromino = None;
         self.player_screen.ghost_tetromino = None;
-        self.rng = Pcg32::seed_from_u64(seed);
-        self.bag_of_tetromino = new_tetromino_bag(BAG_SIZE, &mut self.rng);
-        self.player_screen.active_tetromino = Tetromino::new(
-            self.bag_of_tetromino.pop().unwrap(),
-            &self.player_screen.grid.matrix[..],
-        )
-        .unwrap();
+        self.last_dealt = None;
+        self.last_rotation = None;
+        let first_kind = self.deal().unwrap();
+        self.player_screen.active_tetromino = self.spawn(Tetromino::new_unchecked(first_kind));
         self.player_screen.fifo_next_tetromino =
             CircularBuffer::<NB_NEXT_TETROMINO, Tetromino>::new();
         for _ in 0..NB_NEXT_TETROMINO {
-            if let Some(t) = self.bag_of_tetromino.pop() {
+            if let Some(kind) = self.deal() {
                 self.player_screen
                     .fifo_next_tetromino
-                    .push(Tetromino::new_unchecked(t));
-            } else {
-                self.bag_of_tetromino = new_tetromino_bag(BAG_SIZE, &mut self.rng);
-                if let Some(t) = self.bag_of_tetromino.pop() {
-                    self.player_screen
-                        .fifo_next_tetromino
-                        .push(Tetromino::new_unchecked(t));
-                } else {
-                    unreachable!();
-                }
+                    .push(Tetromino::new_unchecked(kind));
             }
         }
+        self.player_screen.has_hold = self.rules.hold;
+        self.player_screen.nb_previews = self.rules.nb_previews;
+        self.player_screen.hidden_queue = self.modifiers.hidden_queue;
         self.freeze_frame = 0;
+        self.entry_frame = 0;
         self.player_screen.game_over = false;
+        self.stats = PlayerStats::default();
+        self.new_locks.clear();
+        self.placements.clear();
+        self.piece_presses = 0;
+        self.finesse_extra = 0;
+    }
+
+    /// Sets the features of the game and the handling of the keys, used from the next [renew()](LocalPlayer::renew()).
+    ///
+    /// The modern handling is replaced by the one preferred by the player, if any.
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = match self.handling {
+            Some((auto_shift_delay, auto_repeat_rate))
+                if rules.rotation_system == RotationSystem::Srs =>
+            {
+                Rules {
+                    auto_shift_delay,
+                    auto_repeat_rate,
+                    ..rules
+                }
+            }
+            _ => rules,
+        };
+    }
+
+    /// Sets the DAS and ARR preferred by the player, or goes back to the default handling with None.
+    pub fn set_handling(&mut self, handling: Option<(u64, u64)>) {
+        self.handling = handling;
+        let rules = if self.rules.rotation_system == RotationSystem::Srs {
+            Rules {
+                auto_shift_delay: *KEY_REPEAT_DELAY,
+                auto_repeat_rate: *KEY_REPEAT_RATE,
+                ..self.rules
+            }
+        } else {
+            self.rules
+        };
+        self.set_rules(rules);
+    }
+
+    /// Sets the changes to the game chosen on top of the game mode, used from the next [renew()](LocalPlayer::renew()).
+    pub fn set_modifiers(&mut self, modifiers: Modifiers) {
+        self.modifiers = modifiers;
+    }
+
+    /// Sets the number of tetromino in a bag, used from the next [renew()](LocalPlayer::renew()).
+    pub fn set_bag_size(&mut self, bag_size: u32) {
+        self.bag_size = bag_size;
+    }
+
+    /// Sets whether reaching the top of the grid is a game over, or clears the top of the stack.
+    pub fn set_top_out(&mut self, top_out: bool) {
+        self.top_out = top_out;
+    }
+
+    pub fn player_screen(&self) -> &PlayerScreen {
+        &self.player_screen
+    }
+
+    pub fn stats(&self) -> PlayerStats {
+        self.stats
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Returns the rows of the grid before each tetromino locked since the start of the game and the tetromino,
+    /// the ones undone in the practice left out.
+    pub fn placements(&self) -> &[(Vec<Vec<Option<TetrisColor>>>, Tetromino)] {
+        let nb_pieces = (self.stats.pieces as usize).min(self.placements.len());
+        &self.placements[..nb_pieces]
+    }
+
+    /// Returns the number of key presses above the finesse of the last tetromino locked, 0 if it had no finesse fault.
+    pub fn finesse_extra(&self) -> u32 {
+        self.finesse_extra
+    }
+
+    pub fn retries_finesse_faults(&self) -> bool {
+        self.retry_finesse_faults
+    }
+
+    /// Starts or stops undoing the tetrominos locked with a finesse fault, when the placements can be undone.
+    pub fn toggle_finesse_retry(&mut self) {
+        self.retry_finesse_faults = !self.retry_finesse_faults;
+    }
+
+    /// Returns the lines cleared by each tetromino locked since the last call, in order.
+    pub fn take_new_locks(&mut self) -> Vec<ClearedLines> {
+        std::mem::take(&mut self.new_locks)
     }
 
     pub fn add_garbage(&mut self, completed_lines: u64) {
         self.garbage_to_be_added = completed_lines;
     }
 
+    /// Adds rows of garbage at once at the bottom of the grid, see [TetrisGrid::add_garbage_rows()].
+    pub fn add_garbage_rows(&mut self, nb_rows: u64, messiness: f64) {
+        self.player_screen
+            .grid
+            .add_garbage_rows(nb_rows, messiness, &mut self.garbage_rng);
+        self.update_ghost();
+        self.search_perfect_clear();
+    }
+
     pub fn get_lines_completed(&mut self) -> u64 {
         let lines = self.player_screen.new_completed_lines;
         self.player_screen.new_completed_lines = 0;
@@ -100,12 +265,41 @@ impl LocalPlayer {
     pub fn start(&mut self) {
         self.player_screen.grid.null();
         self.player_screen.game_over = false;
+        self.update_ghost();
+        self.search_perfect_clear();
+    }
+
+    /// Shows or hides the next placement leading to a perfect clear.
+    pub fn toggle_pc_hint(&mut self) {
+        // the solver plays with the hold and the wall-kicks, on a grid of the default size
+        if !self.rules.hold
+            || self.rules.rotation_system != RotationSystem::Srs
+            || self.modifiers.big
+        {
+            return;
+        }
+        if self.pc_hint.take().is_none() {
+            self.pc_hint = Some(PcHint::new());
+            self.search_perfect_clear();
+        }
+    }
+
+    /// Hides the next placement leading to a perfect clear, stopping its search.
+    pub fn hide_pc_hint(&mut self) {
+        self.pc_hint = None;
     }
 
     pub fn get_game_over(&self) -> bool {
         self.player_screen.game_over
     }
 
+    /// Ends the game without a game over, when the time runs out.
+    ///
+    /// The tetromino in the air stays where it is but loses its ghost, it will never be locked.
+    pub fn stop(&mut self) {
+        self.player_screen.ghost_tetromino = None;
+    }
+
     pub fn declare_game_over(&mut self) {
         self.player_screen.game_over = true;
         self.player_screen.saved_tetromino = None;
@@ -119,6 +313,14 @@ impl LocalPlayer {
         assets: &mut Assets,
     ) {
         self.player_screen.render(transform, ctx, gl, assets);
+        if let Some(step) = self.pc_hint.as_ref().and_then(PcHint::next_step) {
+            step.placement.tetromino.render_hint(
+                self.player_screen.grid.transform,
+                &ctx.draw_state,
+                gl,
+                assets,
+            );
+        }
     }
 
     pub(in crate::app) fn countdown(&mut self, i: &Countdown) {
@@ -139,34 +341,226 @@ impl LocalPlayer {
     }
 }
 
+/// Actions of the player on the active tetromino, triggered by the keyboard or by a bot.
+impl LocalPlayer {
+    /// Applies a single move to the active tetromino. A hard drop also locks it.
+    pub fn apply_move(&mut self, movement: Move) {
+        let grid = &self.player_screen.grid;
+        let active = &mut self.player_screen.active_tetromino;
+        let kick = match movement {
+            Move::Left => {
+                active.left(grid);
+                None
+            }
+            Move::Right => {
+                active.right(grid);
+                None
+            }
+            Move::RotateClockwise => match self.rules.rotation_system {
+                RotationSystem::Srs => active.turn_clockwise(grid),
+                RotationSystem::Nes => active.turn_without_kicks(grid, true),
+            },
+            Move::RotateCounterclockwise => match self.rules.rotation_system {
+                RotationSystem::Srs => active.turn_counterclockwise(grid),
+                RotationSystem::Nes => active.turn_without_kicks(grid, false),
+            },
+            Move::SoftDrop => {
+                active.hard_drop(grid);
+                None
+            }
+            Move::HardDrop => {
+                active.hard_drop(grid);
+                self.lock_active_tetromino();
+                return;
+            }
+        };
+        if let Some(kick) = kick {
+            self.last_rotation = Some((active.cells(), kick));
+        }
+    }
+
+    /// Moves the active tetromino one cell down if it's possible, without locking it.
+    pub fn fall(&mut self) {
+        let _ = self
+            .player_screen
+            .active_tetromino
+            .fall(&self.player_screen.grid);
+    }
+
+    /// Swaps the active tetromino with the held one, or with the next one if none is held.
+    pub fn hold(&mut self) {
+        self.stats.holds += 1;
+        self.player_screen.active_tetromino.reset_position();
+        if let Some(saved) = self.player_screen.saved_tetromino {
+            self.player_screen.saved_tetromino = Some(self.player_screen.active_tetromino);
+            self.player_screen.active_tetromino = self.spawn(saved);
+            self.last_rotation = None;
+            self.piece_presses = 0;
+        } else {
+            self.player_screen.saved_tetromino = Some(self.player_screen.active_tetromino);
+            self.get_new_tetromino();
+        }
+        self.search_perfect_clear();
+    }
+}
+
 impl LocalPlayer {
+    /// Freezes the active tetromino in the grid, counts the completed lines and gets a new active tetromino.
+    ///
+    /// If the tetromino froze above the visible grid, it's game over, unless the player can't top out !
+    pub(super) fn lock_active_tetromino(&mut self) {
+        // the tetromino must not have moved since its last rotation to score a T-spin
+        let active = &self.player_screen.active_tetromino;
+        let kind = active.kind();
+        let t_spin = match self.last_rotation {
+            Some((cells, kick)) if cells == active.cells() => {
+                active.t_spin(&self.player_screen.grid, kick)
+            }
+            _ => TSpin::None,
+        };
+        // the placements undone in the practice are replaced
+        self.placements.truncate(self.stats.pieces as usize);
+        self.placements
+            .push((self.player_screen.grid.rows().to_vec(), *active));
+        // the finesse is counted from the position where the tetromino appeared, for the modern handling
+        if self.rules.hard_drop {
+            let spawned = self.spawn(*active);
+            self.finesse_extra = finesse(
+                self.player_screen.grid.bitboard(),
+                &spawned,
+                &active.cells(),
+            )
+            .map_or(0, |minimum| self.piece_presses.saturating_sub(minimum));
+            if self.finesse_extra > 0 {
+                self.stats.finesse_faults += 1;
+            }
+        }
+        let mut cleared_lines = match self
+            .player_screen
+            .grid
+            .freeze_tetromino(&mut self.player_screen.active_tetromino)
+        {
+            Some(cleared_lines) => cleared_lines,
+            None if !self.top_out => {
+                self.clear_top();
+                ClearedLines::default()
+            }
+            None => {
+                self.declare_game_over();
+                return;
+            }
+        };
+        cleared_lines.t_spin = t_spin;
+        // if lines were clearing by freezing the tetromino, set the attribute new_completed_lines
+        self.stats.count_lock(kind, cleared_lines);
+        self.player_screen.new_completed_lines = cleared_lines.total;
+        self.player_screen.score += cleared_lines.total;
+        self.new_locks.push(cleared_lines);
+        if self.modifiers.mirror && self.stats.pieces.is_multiple_of(MIRROR_PIECES) {
+            self.player_screen.grid.mirror();
+        }
+        self.entry_frame = self.frame_counter + self.entry_delay;
+        self.get_new_tetromino();
+        if !self.player_screen.game_over {
+            self.record_placement();
+            if self.finesse_extra > 0 && self.retry_finesse_faults {
+                // the fault is still counted once the placement is undone
+                let finesse_faults = self.stats.finesse_faults;
+                if self.undo() {
+                    self.stats.finesse_faults = finesse_faults;
+                }
+            }
+        }
+    }
+
     /// Sets a new active_tetromino when the precedent one is frozen.
     pub(super) fn get_new_tetromino(&mut self) {
-        // Refill the bag if necessary
-        if self.bag_of_tetromino.is_empty() {
-            self.bag_of_tetromino = new_tetromino_bag(BAG_SIZE, &mut self.rng);
-        }
+        // Take the next tetromino, or the held one once there's none left
+        let next = match self.player_screen.fifo_next_tetromino.pop() {
+            Some(next) => next,
+            None => match self.player_screen.saved_tetromino.take() {
+                Some(held) => held,
+                None => {
+                    self.declare_game_over();
+                    return;
+                }
+            },
+        };
+        self.last_rotation = None;
+        self.piece_presses = 0;
         // Check if there's enough place on the grid for a new tetromino
-        let possible_active = self.player_screen.fifo_next_tetromino.pop().unwrap();
+        let possible_active = self.spawn(next);
         if possible_active
-            .check_possible(&self.player_screen.grid.matrix, TranslationRotation::null())
+            .check_possible(&self.player_screen.grid, TranslationRotation::null())
             .is_err()
         {
-            // If not, it's a lock out situation
-            // set the game_over flag and return the tetromino to the bag
-            self.declare_game_over();
+            if self.top_out {
+                // If not, it's a lock out situation
+                // set the game_over flag and return the tetromino to the bag
+                self.declare_game_over();
+                self.player_screen.fifo_next_tetromino.push_front(next);
+                return;
+            }
+            // otherwise, make some place at the top
+            self.clear_top();
+        }
+        // Add a new tetromino to the file to replace the one that was taken
+        if let Some(kind) = self.deal() {
             self.player_screen
                 .fifo_next_tetromino
-                .push_front(possible_active);
-            return;
+                .push(Tetromino::new_unchecked(kind));
         }
-        // Add a new tetromino to the file to replace the one that was taken
-        self.player_screen
-            .fifo_next_tetromino
-            .push(Tetromino::new_unchecked(
-                self.bag_of_tetromino.pop().unwrap(),
-            ));
         self.player_screen.active_tetromino = possible_active;
+        self.search_perfect_clear();
+    }
+
+    /// Returns the tetromino at its starting position, centered in the grid if it isn't as wide as the default one.
+    fn spawn(&self, mut tetromino: Tetromino) -> Tetromino {
+        tetromino.reset_position();
+        tetromino.shift((self.player_screen.grid.nb_columns() as i8 - NB_COLUMNS as i8) / 2);
+        tetromino
+    }
+
+    /// Returns the kind of the next tetromino added to the queue, according to the randomizer,
+    /// or None if there's none left to deal.
+    fn deal(&mut self) -> Option<TetrominoKind> {
+        let kind = match self.rules.randomizer {
+            Randomizer::Bag => {
+                // Refill the bag if necessary
+                if self.bag_of_tetromino.is_empty() {
+                    self.bag_of_tetromino = new_tetromino_bag(self.bag_size, &mut self.rng);
+                }
+                self.bag_of_tetromino.pop().unwrap()
+            }
+            Randomizer::NesReroll => nes_tetromino(self.last_dealt, &mut self.rng),
+            Randomizer::Fixed => self.bag_of_tetromino.pop()?,
+        };
+        self.last_dealt = Some(kind);
+        Some(kind)
+    }
+
+    /// Clears the top of the stack when it reaches the top of the grid and the player can't top out.
+    fn clear_top(&mut self) {
+        self.player_screen.grid.clear_top_rows(ZEN_CLEARED_ROWS + 2);
+        self.stats.top_clears += 1;
+    }
+
+    /// Restarts the search of a perfect clear with the current grid and pieces, if the hint is shown.
+    pub(super) fn search_perfect_clear(&mut self) {
+        let Some(pc_hint) = &mut self.pc_hint else {
+            return;
+        };
+        let player_screen = &self.player_screen;
+        pc_hint.search(PcProblem {
+            board: player_screen.grid.bitboard().clone(),
+            active: player_screen.active_tetromino.kind(),
+            hold: player_screen.saved_tetromino.map(|held| held.kind()),
+            queue: (0..)
+                .map_while(|i| player_screen.next_tetromino(i))
+                .map(|tetromino| tetromino.kind())
+                .collect(),
+            max_height: PC_MAX_HEIGHT,
+        });
     }
 
     /// Sends the player screen to the remote player and resets the new_completed_lines attribute.
@@ -0,0 +1,60 @@
+//! Defines [PcHint], which searches perfect clears in a separate thread to suggest the next placement.
+use super::back_end::{PcProblem, PcStep};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+/// Perfect clear solver running in a separate thread, restarted every time the pieces or the grid change.
+pub(super) struct PcHint {
+    /// Solution of the last search, None while searching or if there's no perfect clear.
+    solution: Arc<Mutex<Option<Vec<PcStep>>>>,
+    /// Set to stop the last search once its result is outdated.
+    cancel: Arc<AtomicBool>,
+}
+
+impl PcHint {
+    pub(super) fn new() -> Self {
+        PcHint {
+            solution: Arc::new(Mutex::new(None)),
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Stops the last search and starts a new one in a separate thread.
+    pub(super) fn search(&mut self, problem: PcProblem) {
+        self.stop();
+        self.cancel = Arc::new(AtomicBool::new(false));
+        self.solution = Arc::new(Mutex::new(None));
+
+        let cancel = Arc::clone(&self.cancel);
+        let solution = Arc::clone(&self.solution);
+        thread::spawn(move || {
+            let result = problem.solve(&cancel);
+            *solution.lock().unwrap() = result;
+        });
+    }
+
+    /// Stops the last search.
+    pub(super) fn stop(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the first step of the solution found by the last search, if any.
+    pub(super) fn next_step(&self) -> Option<PcStep> {
+        self.solution
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|steps| steps.first().cloned())
+    }
+}
+
+impl Drop for PcHint {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
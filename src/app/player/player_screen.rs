@@ -25,10 +25,31 @@ impl PlayerScreen {
             saved_tetromino: None,
             fifo_next_tetromino: CircularBuffer::<NB_NEXT_TETROMINO, Tetromino>::new(),
             ghost_tetromino: None,
+            has_hold: true,
+            nb_previews: NB_NEXT_TETROMINO,
+            hidden_queue: false,
             serialize_as_msg: true.into(),
         }
     }
 
+    /// Returns the i-th next tetromino in the queue, starting from 0.
+    pub fn next_tetromino(&self, i: usize) -> Option<Tetromino> {
+        self.fifo_next_tetromino.get(i)
+    }
+
+    /// Returns the tetrominos that can be played, along with whether they need a hold first.
+    ///
+    /// Holding gives the held tetromino, or the next one if none is held, both at their starting position.
+    pub fn playable_tetrominos(&self) -> Vec<(bool, Tetromino)> {
+        let mut tetrominos = vec![(false, self.active_tetromino)];
+        if let Some(held) = self.saved_tetromino.or_else(|| self.next_tetromino(0)) {
+            if held.kind() != self.active_tetromino.kind() {
+                tetrominos.push((true, held));
+            }
+        }
+        tetrominos
+    }
+
     pub fn render(
         &mut self,
         transform: Matrix2d,
@@ -42,7 +63,7 @@ impl PlayerScreen {
             // the score is centered under the hold piece rectangle
             self.grid.x - 4.0 * BLOCK_SIZE, // 4.0 = 1.0 (margin between borders) + 1.0 (margin inside) + 2.0 (half TETROMINO_MAX_WIDTH)
             DEFAULT_SCORE_TEXT_Y,
-            TEXT_COLOR,
+            *TEXT_COLOR,
         );
         score_text.render(transform, ctx, gl, &mut assets.main_font);
 
@@ -55,43 +76,74 @@ impl PlayerScreen {
         self.active_tetromino
             .render(self.grid.transform, &ctx.draw_state, gl, assets);
 
+        if self.has_hold {
+            self.render_hold(transform, ctx, gl, assets);
+        }
+        self.render_next_tetrominos(transform, ctx, gl, assets);
+    }
+
+    /// Returns the transform of the top left corner of a grid of the default size,
+    /// around which the boxes are placed whatever the size of the grid.
+    fn frame_transform(&self, transform: Matrix2d) -> Matrix2d {
+        transform.trans(DEFAULT_GRID_X, DEFAULT_GRID_Y)
+    }
+
+    /// Renders the held tetromino in its box on the left of the grid.
+    fn render_hold(
+        &self,
+        transform: Matrix2d,
+        ctx: &Context,
+        gl: &mut GlGraphics,
+        assets: &mut Assets,
+    ) {
         // drawing a border for the hold piece
-        let transform = self.grid.transform.trans(
+        let transform = self.frame_transform(transform).trans(
             -(BLOCK_SIZE + TETROMINO_MAX_WIDTH + BLOCK_SIZE + BLOCK_SIZE),
-            self.grid.total_height - self.grid.visible_height,
+            2.0 * BLOCK_SIZE,
         );
         let rectangle_width = BLOCK_SIZE + TETROMINO_MAX_WIDTH + BLOCK_SIZE;
         let rectangle_height = BLOCK_SIZE + TETROMINO_MAX_HEIGHT + BLOCK_SIZE;
         let dims: Rectangle = [0.0, 0.0, rectangle_width, rectangle_height];
-        rectangle(GRID_BG_COLOR, dims, transform, gl);
-        let outline_rect = graphics::Rectangle::new_border(GRID_COLOR, GRID_THICKNESS);
+        rectangle(*GRID_BG_COLOR, dims, transform, gl);
+        let outline_rect = graphics::Rectangle::new_border(*GRID_COLOR, GRID_THICKNESS);
         outline_rect.draw(dims, &ctx.draw_state, transform, gl);
 
         // drawing the hold piece
         if let Some(saved) = self.saved_tetromino {
-            let transform = self.grid.transform.trans(
-                -self.grid.total_width * (NB_COLUMNS - 1) as f64 / NB_COLUMNS as f64,
+            let transform = self.frame_transform(transform).trans(
+                -((NB_COLUMNS - 1) as f64) * BLOCK_SIZE,
                 TETROMINO_MAX_HEIGHT + BLOCK_SIZE,
             );
             saved.render(transform, &ctx.draw_state, gl, assets);
         }
+    }
 
+    /// Renders the next tetrominos in their box on the right of the grid.
+    fn render_next_tetrominos(
+        &self,
+        transform: Matrix2d,
+        ctx: &Context,
+        gl: &mut GlGraphics,
+        assets: &mut Assets,
+    ) {
         // drawing a border for the fifo of next pieces
-        let transform = self.grid.transform.trans(
-            self.grid.total_width * (NB_COLUMNS + 1) as f64 / NB_COLUMNS as f64,
-            self.grid.total_height - self.grid.visible_height,
-        );
+        let transform = self
+            .frame_transform(transform)
+            .trans((NB_COLUMNS + 1) as f64 * BLOCK_SIZE, 2.0 * BLOCK_SIZE);
         let width = BLOCK_SIZE + TETROMINO_MAX_WIDTH + BLOCK_SIZE;
-        let height = BLOCK_SIZE + (BLOCK_SIZE + TETROMINO_MAX_HEIGHT) * NB_NEXT_TETROMINO as f64;
+        let height = BLOCK_SIZE + (BLOCK_SIZE + TETROMINO_MAX_HEIGHT) * self.nb_previews as f64;
         let dims: Rectangle = [0.0, 0.0, width, height];
-        rectangle(GRID_BG_COLOR, dims, transform, gl);
-        let outline_rect = graphics::Rectangle::new_border(GRID_COLOR, GRID_THICKNESS);
+        rectangle(*GRID_BG_COLOR, dims, transform, gl);
+        let outline_rect = graphics::Rectangle::new_border(*GRID_COLOR, GRID_THICKNESS);
         outline_rect.draw(dims, &ctx.draw_state, transform, gl);
 
-        // drawing the next pieces
-        for i in 0..NB_NEXT_TETROMINO {
-            let transform = self.grid.transform.trans(
-                self.grid.total_width * (NB_COLUMNS - 1) as f64 / NB_COLUMNS as f64,
+        // drawing the next pieces, unless they are masked
+        if self.hidden_queue {
+            return;
+        }
+        for i in 0..self.nb_previews {
+            let transform = self.frame_transform(transform).trans(
+                (NB_COLUMNS - 1) as f64 * BLOCK_SIZE,
                 (BLOCK_SIZE + TETROMINO_MAX_HEIGHT) * (i as f64 + 1.0),
             );
             if let Some(tetromino) = self.fifo_next_tetromino.get(i) {
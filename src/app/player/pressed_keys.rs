@@ -1,5 +1,5 @@
-//! Defines [PressedKeys] that stores the pressed keys and the last pressed key.
-use crate::settings::KEY_REPEAT_DELAY;
+//! Defines [PressedKeys] that stores the pressed inputs, keys or controller inputs, and the last pressed one.
+use crate::input::Input;
 use piston::Key;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -7,29 +7,30 @@ use std::collections::HashMap;
 /// Pressed keys struct.
 #[derive(Serialize, Deserialize)]
 pub(super) struct PressedKeys {
-    last_pressed_key: Key,
+    last_pressed_key: Input,
     /// the delay is initialized on key press, then is decremented until it reaches 0 and long press is triggered.
-    delay_to_long_press: HashMap<Key, u64>,
+    delay_to_long_press: HashMap<Input, u64>,
 }
 
 impl PressedKeys {
     pub(super) fn new() -> PressedKeys {
         PressedKeys {
-            last_pressed_key: Key::A,
+            last_pressed_key: Input::Key(Key::A),
             delay_to_long_press: HashMap::new(),
         }
     }
 
-    pub(super) fn set_pressed(&mut self, key: Key) {
+    /// Sets the key as the last pressed one, the long press is triggered after the given number of updates.
+    pub(super) fn set_pressed(&mut self, key: Input, delay_to_long_press: u64) {
         self.last_pressed_key = key;
-        self.delay_to_long_press.insert(key, KEY_REPEAT_DELAY);
+        self.delay_to_long_press.insert(key, delay_to_long_press);
     }
 
-    pub(super) fn set_released(&mut self, key: Key) {
+    pub(super) fn set_released(&mut self, key: Input) {
         self.delay_to_long_press.remove(&key);
     }
 
-    pub(super) fn is_any_last_pressed(&self, keys: &[Key]) -> bool {
+    pub(super) fn is_any_last_pressed(&self, keys: &[Input]) -> bool {
         for key in keys {
             if self.is_last_pressed(*key) {
                 return true;
@@ -38,7 +39,7 @@ impl PressedKeys {
         false
     }
 
-    pub(super) fn is_any_delay_pressed(&self, keys: &[Key]) -> bool {
+    pub(super) fn is_any_delay_pressed(&self, keys: &[Input]) -> bool {
         for key in keys {
             if self.is_delay_pressed(*key) {
                 return true;
@@ -56,11 +57,11 @@ impl PressedKeys {
         }
     }
 
-    fn is_last_pressed(&self, key: Key) -> bool {
+    fn is_last_pressed(&self, key: Input) -> bool {
         self.last_pressed_key == key
     }
 
-    fn is_delay_pressed(&self, key: Key) -> bool {
+    fn is_delay_pressed(&self, key: Input) -> bool {
         match self.delay_to_long_press.get(&key) {
             Some(counter) => *counter == 0,
             None => false,
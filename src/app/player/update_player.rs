@@ -1,8 +1,8 @@
 //! Defines the update function of [LocalPlayer].
 //!
 //! [update()](LocalPlayer::update()) is called before each render when the game is active.
-use super::{back_end::TranslationRotation, LocalPlayer};
-use crate::settings::Keybindings;
+use super::{back_end::TranslationRotation, LocalPlayer, Speed};
+use crate::settings::{Keybindings, KEY_REPEAT_RATE};
 
 impl LocalPlayer {
     /// update is called before each render so that the informations on the screen are as recent as possible.
@@ -14,18 +14,13 @@ impl LocalPlayer {
     ///
     /// It's also responsible for :
     /// - updating the keyboard clock
+    /// - aging the blocks of the grid, which fade with the invisible modifier
     /// - updating the ghost tetromino
     /// - adding garbage
     /// - sending the serialized data to the remote
     ///
     /// When the game is paused or inactive, update should not be called.
-    pub fn update(
-        &mut self,
-        keybindings: &Keybindings,
-        frame_counter: u64,
-        fall_speed_divide: u64,
-        freeze: u64,
-    ) {
+    pub fn update(&mut self, keybindings: &Keybindings, frame_counter: u64, speed: Speed) {
         /* Actions in this function have to be carefully ordered so that there are no uncoherences.
          *
          * For instance, garbage has to be added AFTER the tetromino is moved because it hasn't been rendered yet
@@ -36,39 +31,84 @@ impl LocalPlayer {
          *   MOVING the ACTIVE_TETROMINO  *
          **********************************/
 
+        self.frame_counter = frame_counter;
+        self.entry_delay = speed.entry_delay;
+        // the next tetromino waits at the top of the grid during the entry delay
+        if frame_counter >= self.entry_frame {
+            self.move_active_tetromino(keybindings, frame_counter, speed);
+        }
+
+        /**********************************
+         *          AT EVERY TICK         *
+         *              ---               *
+         *      preparing the new render  *
+         **********************************/
+
+        // Updates the time for the keyboard
+        self.keyboard.update();
+
+        // Makes the locked blocks older
+        self.player_screen.grid.tick();
+
+        // Updates the ghost_tetromino
+        self.update_ghost();
+
+        // Adds garbage to the grid
+        if self.garbage_to_be_added != 0 {
+            self.player_screen
+                .grid
+                .add_garbage(self.garbage_to_be_added, &mut self.garbage_rng);
+            self.garbage_to_be_added = 0;
+            self.search_perfect_clear();
+        }
+
+        // Send the player_screen data if necessary
+        if self.sender {
+            self.send_serialized();
+        }
+    }
+
+    /// Moves the active tetromino down and sideways on long key presses, makes it fall and freezes it at the bottom.
+    fn move_active_tetromino(
+        &mut self,
+        keybindings: &Keybindings,
+        frame_counter: u64,
+        speed: Speed,
+    ) {
         /**********************************
-         *         EVERY 5 TICKS          *
+         *     EVERY AUTO REPEAT RATE     *
          *              ---               *
          *     "continuous" actions       *
          **********************************/
 
         // Translate the tetromino down on a key press
-        if frame_counter % 5 == 0 {
-            if self.keyboard.is_any_delay_pressed(&keybindings.fall_keys)
-                && self
-                    .player_screen
-                    .active_tetromino
-                    .fall(&self.player_screen.grid.matrix)
-                    .is_err()
-                && self.freeze_frame < frame_counter
-            {
-                // if the tetromino reaches the bottom, set the freeze_frame
-                self.freeze_frame = frame_counter + freeze;
-            }
-            // Translate the tetromino right or left on a long key press
+        if frame_counter.is_multiple_of(*KEY_REPEAT_RATE)
+            && self.keyboard.is_any_delay_pressed(&keybindings.fall_keys)
+            && self
+                .player_screen
+                .active_tetromino
+                .fall(&self.player_screen.grid)
+                .is_err()
+            && self.freeze_frame < frame_counter
+        {
+            // if the tetromino reaches the bottom, set the freeze_frame
+            self.freeze_frame = frame_counter + speed.freeze;
+        }
+        // Translate the tetromino right or left on a long key press
+        if frame_counter.is_multiple_of(self.rules.auto_repeat_rate) {
             if self.keyboard.is_any_delay_pressed(&keybindings.left_keys)
                 && !self.keyboard.is_any_delay_pressed(&keybindings.right_keys)
             {
                 self.player_screen
                     .active_tetromino
-                    .left(&self.player_screen.grid.matrix);
+                    .left(&self.player_screen.grid);
             }
             if self.keyboard.is_any_delay_pressed(&keybindings.right_keys)
                 && !self.keyboard.is_any_delay_pressed(&keybindings.left_keys)
             {
                 self.player_screen
                     .active_tetromino
-                    .right(&self.player_screen.grid.matrix);
+                    .right(&self.player_screen.grid);
             }
         }
 
@@ -80,16 +120,12 @@ impl LocalPlayer {
          **********************************/
 
         // move the tetromino down to emulate its fall
-        if frame_counter % fall_speed_divide == 0
-            && self
-                .player_screen
-                .active_tetromino
-                .fall(&self.player_screen.grid.matrix)
-                .is_err()
+        if frame_counter % speed.fall_speed_divide == 0
+            && self.fall_rows(speed.rows_per_fall).is_err()
             && self.freeze_frame < frame_counter
         {
             // if the tetromino reaches the bottom, set the freeze_frame
-            self.freeze_frame = frame_counter + freeze;
+            self.freeze_frame = frame_counter + speed.freeze;
         }
 
         /**********************************
@@ -103,54 +139,31 @@ impl LocalPlayer {
             && self
                 .player_screen
                 .active_tetromino
-                .check_possible(&self.player_screen.grid.matrix, TranslationRotation::fall())
+                .check_possible(&self.player_screen.grid, TranslationRotation::fall())
                 .is_err()
         {
-            match self
-                .player_screen
-                .grid
-                .freeze_tetromino(&mut self.player_screen.active_tetromino)
-            {
-                // if lines were clearing by freezing the tetromino, set the attribute new_completed_lines
-                Some(completed_lines) => {
-                    self.player_screen.new_completed_lines = completed_lines;
-                    if self.player_screen.new_completed_lines != 0 {
-                        println!(
-                            "{} lines were completed",
-                            self.player_screen.new_completed_lines
-                        );
-                    }
-                    self.player_screen.score += self.player_screen.new_completed_lines;
-                    self.get_new_tetromino();
-                }
-                // if the tetromino froze above the visible grid, it's game over !
-                None => self.declare_game_over(),
-            }
+            self.lock_active_tetromino();
         }
+    }
 
-        /**********************************
-         *          AT EVERY TICK         *
-         *              ---               *
-         *      preparing the new render  *
-         **********************************/
-
-        // Updates the time for the keyboard
-        self.keyboard.update();
+    /// Moves the active tetromino down by the given number of rows, or as far as possible if it reaches the bottom before.
+    fn fall_rows(&mut self, nb_rows: u32) -> Result<(), ()> {
+        for _ in 0..nb_rows {
+            self.player_screen
+                .active_tetromino
+                .fall(&self.player_screen.grid)?;
+        }
+        Ok(())
+    }
 
-        // Updates the ghost_tetromino
+    /// Moves the ghost tetromino under the active tetromino, if the rules show it.
+    pub(super) fn update_ghost(&mut self) {
+        if !self.rules.ghost || self.modifiers.no_ghost {
+            self.player_screen.ghost_tetromino = None;
+            return;
+        }
         let mut ghost = self.player_screen.active_tetromino.make_ghost_copy();
-        ghost.hard_drop(&self.player_screen.grid.matrix);
+        ghost.hard_drop(&self.player_screen.grid);
         self.player_screen.ghost_tetromino = Some(ghost);
-
-        // Adds garbage to the grid
-        self.player_screen
-            .grid
-            .add_garbage(self.garbage_to_be_added);
-        self.garbage_to_be_added = 0;
-
-        // Send the player_screen data if necessary
-        if self.sender {
-            self.send_serialized();
-        }
     }
 }
@@ -0,0 +1,102 @@
+//! Defines the [PracticeAction]s of the practice mode, triggered by keys or by the buttons next to the grid.
+use super::{App, GameMode, RunningState, ViewState};
+use crate::settings::{BAG_START_KEYS, FINESSE_RETRY_KEYS, NEXT_PIECE_KEYS, REDO_KEYS, UNDO_KEYS};
+use piston_window::Key;
+
+/// Action on the placements or the tetrominos of a [GameMode::Practice].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PracticeAction {
+    Undo,
+    Redo,
+    /// Replaces the next tetromino by the one of the following kind.
+    NextPiece,
+    /// Undoes the placements back to the first tetromino of the bag.
+    BagStart,
+    /// Starts or stops undoing the placements with a finesse fault, to place the tetromino again.
+    RetryFaults,
+}
+
+impl PracticeAction {
+    pub const ALL: [PracticeAction; 5] = [
+        PracticeAction::Undo,
+        PracticeAction::Redo,
+        PracticeAction::NextPiece,
+        PracticeAction::BagStart,
+        PracticeAction::RetryFaults,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PracticeAction::Undo => "Undo",
+            PracticeAction::Redo => "Redo",
+            PracticeAction::NextPiece => "Change next",
+            PracticeAction::BagStart => "Bag start",
+            PracticeAction::RetryFaults => "Retry faults",
+        }
+    }
+
+    fn keys(self) -> &'static [Key] {
+        match self {
+            PracticeAction::Undo => &UNDO_KEYS,
+            PracticeAction::Redo => &REDO_KEYS,
+            PracticeAction::NextPiece => &NEXT_PIECE_KEYS,
+            PracticeAction::BagStart => &BAG_START_KEYS,
+            PracticeAction::RetryFaults => &FINESSE_RETRY_KEYS,
+        }
+    }
+
+    /// Returns the action triggered by the key, if any.
+    pub(super) fn from_key(key: Key) -> Option<PracticeAction> {
+        PracticeAction::ALL
+            .into_iter()
+            .find(|action| action.keys().contains(&key))
+    }
+}
+
+impl App<'_> {
+    /// Returns the labels of the buttons of the practice actions, with their keys, or none outside of the practice.
+    pub(super) fn practice_labels(&self) -> Vec<String> {
+        if !matches!(self.game_mode(), GameMode::Practice { gravity: _ }) {
+            return vec![];
+        }
+        PracticeAction::ALL
+            .into_iter()
+            .map(|action| match action {
+                PracticeAction::RetryFaults => {
+                    let state = if self.local_players[0].retries_finesse_faults() {
+                        "on"
+                    } else {
+                        "off"
+                    };
+                    format!("{}: {state} ({:?})", action.name(), action.keys()[0])
+                }
+                _ => format!("{} ({:?})", action.name(), action.keys()[0]),
+            })
+            .collect()
+    }
+
+    /// Applies the action to the player while the practice is running, the retry of the faults being toggled at any time.
+    pub(super) fn practice(&mut self, action: PracticeAction) {
+        if !matches!(self.game_mode(), GameMode::Practice { gravity: _ }) {
+            return;
+        }
+        if action == PracticeAction::RetryFaults {
+            self.local_players[0].toggle_finesse_retry();
+            self.set_view(ViewState::Local);
+            return;
+        }
+        if self.running != RunningState::Running {
+            return;
+        }
+        let player = &mut self.local_players[0];
+        match action {
+            PracticeAction::Undo => {
+                player.undo();
+            }
+            PracticeAction::Redo => player.redo(),
+            PracticeAction::NextPiece => player.change_next_tetromino(),
+            PracticeAction::BagStart => player.undo_to_bag_start(),
+            PracticeAction::RetryFaults => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,310 @@
+//! Defines the [Profile]s of the players, which keep their keybindings, handling, theme and stats between two launches.
+//!
+//! Each profile is a file of the `profiles` directory of the data directory, and the profiles chosen for the seats are kept in `seats.json`.
+use super::{App, ViewState};
+use crate::settings::{
+    Keybindings, BG_COLOR, DEFAULT_PROFILE, KEY_REPEAT_DELAY, KEY_REPEAT_RATE, NIGHT_BG_COLOR,
+    PROFILE_AUTO_REPEAT_RATES, PROFILE_AUTO_SHIFT_DELAYS, PROFILE_NAME_MAX_LENGTH, SAND_BG_COLOR,
+};
+use crate::utils::storage;
+use crate::PlayerConfig;
+use serde::{Deserialize, Serialize};
+
+/// Directory of the data directory with a file per profile.
+static PROFILES_DIR: &str = "profiles";
+/// File of the data directory with the names of the profiles of the two seats.
+static SEATS_FILE: &str = "seats.json";
+
+/// Colors of the screen chosen by a profile.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Theme {
+    #[default]
+    Forest,
+    Night,
+    Sand,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Forest, Theme::Night, Theme::Sand];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Forest => "Forest",
+            Theme::Night => "Night",
+            Theme::Sand => "Sand",
+        }
+    }
+
+    pub fn background(self) -> graphics::types::Color {
+        match self {
+            Theme::Forest => *BG_COLOR,
+            Theme::Night => NIGHT_BG_COLOR,
+            Theme::Sand => SAND_BG_COLOR,
+        }
+    }
+}
+
+/// Totals of the games played with a profile.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+pub struct ProfileStats {
+    pub games: u64,
+    /// Versus won and single-player games finished.
+    pub wins: u64,
+    pub pieces: u64,
+    pub lines: u64,
+    pub attack: u64,
+    pub seconds: f64,
+}
+
+/// Named player with their own controls, chosen for a seat in the settings.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub keybindings: Keybindings,
+    /// DAS in updates, used with the modern handling.
+    pub auto_shift_delay: u64,
+    /// ARR in updates, used with the modern handling.
+    pub auto_repeat_rate: u64,
+    #[serde(default)]
+    pub theme: Theme,
+    #[serde(default)]
+    pub stats: ProfileStats,
+}
+
+impl Profile {
+    fn file_name(&self) -> String {
+        format!("{PROFILES_DIR}/{}.json", self.name)
+    }
+
+    fn save(&self) {
+        if let Err(error) = storage::save(&self.file_name(), self) {
+            println!("couldn't save the profile {}: {error}", self.name);
+        }
+    }
+}
+
+/// Returns the saved profiles, sorted by name, and the indices of the ones chosen for the two seats.
+pub(super) fn load_profiles() -> (Vec<Profile>, [Option<usize>; 2]) {
+    let mut profiles: Vec<Profile> = storage::load_all(PROFILES_DIR);
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    let names: [Option<String>; 2] = storage::load(SEATS_FILE).unwrap_or_default();
+    let seats = names
+        .map(|name| name.and_then(|name| profiles.iter().position(|profile| profile.name == name)));
+    (profiles, seats)
+}
+
+/// Returns the value following the given one in the choices, the first one if it isn't among them.
+fn next_choice(choices: &[u64], value: u64) -> u64 {
+    choices
+        .iter()
+        .position(|&choice| choice == value)
+        .map_or(choices[0], |i| choices[(i + 1) % choices.len()])
+}
+
+/// Returns why the name can't be the one of a new profile, if it can't.
+fn invalid_name(name: &str, profiles: &[Profile]) -> Option<&'static str> {
+    if name.is_empty() {
+        Some("Type a name first")
+    } else if name.chars().count() > PROFILE_NAME_MAX_LENGTH {
+        Some("Name too long")
+    } else if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_')
+    {
+        Some("Letters, digits, spaces, - and _ only")
+    } else if profiles.iter().any(|profile| profile.name == name) {
+        Some("Name already taken")
+    } else {
+        None
+    }
+}
+
+impl App<'_> {
+    fn seat_profile(&self, seat: usize) -> Option<&Profile> {
+        self.seats[seat].map(|index| &self.profiles[index])
+    }
+
+    /// Returns the name of the profile of the seat, or the one of a guest on it.
+    pub(super) fn profile_name(&self, seat: usize) -> String {
+        match self.seat_profile(seat) {
+            Some(profile) => profile.name.clone(),
+            None if seat == 0 => String::from(DEFAULT_PROFILE),
+            None => format!("{DEFAULT_PROFILE} {}", seat + 1),
+        }
+    }
+
+    /// Returns the background of the theme of the first seat.
+    pub(super) fn background(&self) -> graphics::types::Color {
+        self.seat_profile(0)
+            .map_or(*BG_COLOR, |profile| profile.theme.background())
+    }
+
+    /// Gives the keybindings and the handling of their profiles to the seats, the default ones to the guests.
+    pub(super) fn apply_profiles(&mut self) {
+        let two_local = self.player_config == PlayerConfig::TwoLocal;
+        for seat in 0..self.keybindings_manager.len() {
+            let profile = self.seats[seat].map(|index| &self.profiles[index]);
+            self.keybindings_manager[seat] = match profile {
+                Some(profile) => profile.keybindings.clone(),
+                None if two_local => Keybindings::new_two_local(seat),
+                None => Keybindings::new(),
+            };
+            if let Some(player) = self.local_players.get_mut(seat) {
+                player.set_handling(
+                    profile.map(|profile| (profile.auto_shift_delay, profile.auto_repeat_rate)),
+                );
+            }
+        }
+    }
+
+    /// Returns the labels of the buttons of the profile of the seat, only the one choosing it for a guest.
+    pub(super) fn profile_labels(&self, seat: usize) -> Vec<String> {
+        match self.seat_profile(seat) {
+            Some(profile) => vec![
+                format!("Profile: {}", profile.name),
+                format!("Theme: {}", profile.theme.name()),
+                format!("DAS: {}", profile.auto_shift_delay),
+                format!("ARR: {}", profile.auto_repeat_rate),
+            ],
+            None => vec![String::from("Profile: Guest")],
+        }
+    }
+
+    fn save_seats(&self) {
+        let names = [0, 1].map(|seat| self.seat_profile(seat).map(|profile| &profile.name));
+        if let Err(error) = storage::save(SEATS_FILE, &names) {
+            println!("couldn't save the seats: {error}");
+        }
+    }
+
+    /// Applies the changes of the profiles and shows them in the settings.
+    fn refresh_profiles(&mut self) {
+        self.apply_profiles();
+        self.set_view(ViewState::Settings);
+    }
+
+    /// Gives the seat the next profile, a guest coming after the last one, skipping the profile of the other seat.
+    pub(super) fn next_profile(&mut self, seat: usize) {
+        let other = self.seats[1 - seat];
+        let mut next = self.seats[seat];
+        loop {
+            next = match next {
+                None if self.profiles.is_empty() => None,
+                None => Some(0),
+                Some(index) if index + 1 < self.profiles.len() => Some(index + 1),
+                Some(_) => None,
+            };
+            if next.is_none() || next != other {
+                break;
+            }
+        }
+        self.seats[seat] = next;
+        self.save_seats();
+        let summary = self.seat_profile(seat).map_or(String::new(), |profile| {
+            let stats = profile.stats;
+            format!(
+                "{}: {} games, {} wins, {} lines",
+                profile.name, stats.games, stats.wins, stats.lines
+            )
+        });
+        self.settings_text.set_text(summary);
+        self.refresh_profiles();
+    }
+
+    /// Applies the change to the profile of the seat and saves it, if the seat has one.
+    fn change_profile(&mut self, seat: usize, change: impl FnOnce(&mut Profile)) {
+        if let Some(index) = self.seats[seat] {
+            let profile = &mut self.profiles[index];
+            change(profile);
+            profile.save();
+            self.refresh_profiles();
+        }
+    }
+
+    pub(super) fn next_theme(&mut self, seat: usize) {
+        self.change_profile(seat, |profile| {
+            let i = Theme::ALL.iter().position(|&theme| theme == profile.theme);
+            profile.theme = Theme::ALL[i.map_or(0, |i| (i + 1) % Theme::ALL.len())];
+        });
+    }
+
+    pub(super) fn next_auto_shift_delay(&mut self, seat: usize) {
+        self.change_profile(seat, |profile| {
+            profile.auto_shift_delay =
+                next_choice(&PROFILE_AUTO_SHIFT_DELAYS, profile.auto_shift_delay);
+        });
+    }
+
+    pub(super) fn next_auto_repeat_rate(&mut self, seat: usize) {
+        self.change_profile(seat, |profile| {
+            profile.auto_repeat_rate =
+                next_choice(&PROFILE_AUTO_REPEAT_RATES, profile.auto_repeat_rate);
+        });
+    }
+
+    /// Keeps the keybindings changed in the settings in the profile of the seat, if it has one.
+    pub(super) fn save_profile_keybindings(&mut self, seat: usize) {
+        if let Some(index) = self.seats[seat] {
+            let profile = &mut self.profiles[index];
+            profile.keybindings = self.keybindings_manager[seat].clone();
+            profile.save();
+        }
+    }
+
+    /// Creates a profile named after the text typed on the side of the seat, with the current keybindings of the seat,
+    /// and gives it to the seat.
+    pub(super) fn create_profile(&mut self, seat: usize, name: &str) {
+        let name = name.trim();
+        if let Some(reason) = invalid_name(name, &self.profiles) {
+            self.settings_text.set_text(String::from(reason));
+            return;
+        }
+        let profile = Profile {
+            name: String::from(name),
+            keybindings: self.keybindings_manager[seat].clone(),
+            auto_shift_delay: *KEY_REPEAT_DELAY,
+            auto_repeat_rate: *KEY_REPEAT_RATE,
+            theme: Theme::default(),
+            stats: ProfileStats::default(),
+        };
+        profile.save();
+        // the profiles stay sorted by name, so the indices of the seats move
+        let names = [0, 1].map(|seat| self.seat_profile(seat).map(|profile| profile.name.clone()));
+        self.profiles.push(profile);
+        self.profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        self.seats = names.map(|name| {
+            name.and_then(|name| {
+                self.profiles
+                    .iter()
+                    .position(|profile| profile.name == name)
+            })
+        });
+        self.seats[seat] = self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name);
+        self.save_seats();
+        self.settings_text
+            .set_text(format!("Profile {name} created"));
+        self.refresh_profiles();
+    }
+
+    /// Adds the game that just ended to the stats of the profile of the seat, if it has one.
+    pub(super) fn count_profile_game(&mut self, seat: usize, won: bool) {
+        let Some(index) = self.seats[seat] else {
+            return;
+        };
+        let Some(player) = self.local_players.get(seat) else {
+            return;
+        };
+        let stats = player.stats();
+        let profile = &mut self.profiles[index];
+        profile.stats.games += 1;
+        profile.stats.wins += won as u64;
+        profile.stats.pieces += stats.pieces;
+        profile.stats.lines += stats.lines;
+        profile.stats.attack += stats.attack;
+        profile.stats.seconds += self.clock;
+        profile.save();
+    }
+}
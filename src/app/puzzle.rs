@@ -0,0 +1,312 @@
+//! Defines the [Puzzle]s of the puzzle mode, read from packs of puzzles, and how [App] checks their objective.
+use super::{
+    App, Bitboard, ClearedLines, GameMode, Modifiers, Outcome, RunningState, TSpin, TetrominoKind,
+    ViewState,
+};
+use crate::assets::TetrisColor;
+use crate::settings::{NB_COLUMNS, NB_ROWS};
+use crate::utils::{formattings::format_seconds, storage};
+use serde::Deserialize;
+use std::fmt;
+
+/// Pack of puzzles shipped with the game, the other packs are the files of PUZZLES_DIR in the data directory.
+static BUILT_IN_PACK: &str = include_str!("../../puzzles/basics.json");
+static PUZZLES_DIR: &str = "puzzles";
+
+/// Puzzles of a file.
+#[derive(Deserialize)]
+struct PuzzlePack {
+    name: String,
+    puzzles: Vec<Puzzle>,
+}
+
+/// Grid and tetrominos given to the player, with an objective to reach before the tetrominos run out.
+#[derive(Deserialize, Clone)]
+pub struct Puzzle {
+    /// Name of the pack of the puzzle, set when the pack is read.
+    #[serde(skip)]
+    pub pack: String,
+    pub name: String,
+    /// Rows at the bottom of the grid, from the highest one.
+    ///
+    /// A '.' is an empty cell, the letter of a tetromino a block of its color and any other character a block of garbage.
+    pub board: Vec<String>,
+    /// Tetrominos dealt in order, the first one being the active tetromino.
+    pub pieces: Vec<TetrominoKind>,
+    #[serde(default)]
+    pub hold: Option<TetrominoKind>,
+    pub objective: Objective,
+}
+
+/// Goal of a [Puzzle], checked each time a tetromino is locked.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Objective {
+    /// The grid must be emptied.
+    ClearAll,
+    TSpinTriple,
+    /// The lines must be cleared with at most the given number of tetrominos.
+    ClearLines {
+        lines: u64,
+        pieces: u64,
+    },
+    /// All the tetrominos must be locked without leaving an empty cell under a block.
+    NoHoles,
+}
+
+/// State of a puzzle once a tetromino is locked.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum PuzzleState {
+    Ongoing,
+    Solved,
+    Failed,
+}
+
+/// Returns the puzzles of the built-in pack followed by the ones of the packs of the data directory.
+///
+/// The puzzles that can't be played are left out.
+pub(super) fn load_puzzles() -> Vec<Puzzle> {
+    let built_in: PuzzlePack =
+        serde_json::from_str(BUILT_IN_PACK).expect("the built-in puzzles can be read");
+    let mut puzzles = vec![];
+    for pack in std::iter::once(built_in).chain(storage::load_all::<PuzzlePack>(PUZZLES_DIR)) {
+        for mut puzzle in pack.puzzles {
+            puzzle.pack = pack.name.clone();
+            match puzzle.validate() {
+                Ok(()) => puzzles.push(puzzle),
+                Err(error) => println!("left out the puzzle {}: {error}", puzzle.id()),
+            }
+        }
+    }
+    puzzles
+}
+
+impl Puzzle {
+    /// Returns the name of the puzzle, unique among all the packs, used to remember the solved puzzles.
+    pub fn id(&self) -> String {
+        format!("{}/{}", self.pack, self.name)
+    }
+
+    /// Returns the rows of cells of the board, from the highest one.
+    pub fn rows(&self) -> Vec<Vec<Option<TetrisColor>>> {
+        self.board
+            .iter()
+            .map(|row| row.chars().map(cell).collect())
+            .collect()
+    }
+
+    /// Returns the number of tetrominos the player can lock, the held one included.
+    fn nb_pieces(&self) -> u64 {
+        self.pieces.len() as u64 + self.hold.is_some() as u64
+    }
+
+    /// Returns an error if the puzzle can't be played.
+    fn validate(&self) -> Result<(), String> {
+        if self.pieces.is_empty() {
+            return Err(String::from("there are no pieces"));
+        }
+        if self.board.len() > (NB_ROWS - 2) as usize {
+            return Err(format!("the board is higher than {} rows", NB_ROWS - 2));
+        }
+        match self
+            .board
+            .iter()
+            .find(|row| row.chars().count() != NB_COLUMNS as usize)
+        {
+            Some(row) => Err(format!("the row \"{row}\" isn't {NB_COLUMNS} cells wide")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Objective {
+    /// Returns the state of the puzzle once a tetromino is locked.
+    ///
+    /// - lock is the lines cleared by the tetromino
+    /// - pieces and lines are the numbers of tetrominos locked and lines cleared since the start of the puzzle
+    /// - board is the grid once the tetromino is locked
+    /// - out_of_pieces is whether it was the last tetromino
+    fn check(
+        &self,
+        lock: ClearedLines,
+        pieces: u64,
+        lines: u64,
+        board: &Bitboard,
+        out_of_pieces: bool,
+    ) -> PuzzleState {
+        let solved = match *self {
+            Objective::ClearAll => lock.perfect_clear,
+            Objective::TSpinTriple => lock.t_spin == TSpin::Full && lock.total == 3,
+            Objective::ClearLines { lines: goal, .. } => lines >= goal,
+            Objective::NoHoles if has_holes(board) => return PuzzleState::Failed,
+            Objective::NoHoles => out_of_pieces,
+        };
+        let failed = match *self {
+            Objective::ClearLines {
+                lines: _,
+                pieces: max_pieces,
+            } => pieces >= max_pieces,
+            _ => false,
+        };
+        if solved {
+            PuzzleState::Solved
+        } else if failed || out_of_pieces {
+            PuzzleState::Failed
+        } else {
+            PuzzleState::Ongoing
+        }
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Objective::ClearAll => write!(f, "Clear all"),
+            Objective::TSpinTriple => write!(f, "T-spin triple"),
+            Objective::ClearLines { lines, pieces } => {
+                write!(f, "{lines} lines in {pieces} pieces")
+            }
+            Objective::NoHoles => write!(f, "No holes"),
+        }
+    }
+}
+
+impl App<'_> {
+    /// Starts the puzzle right away, without a countdown so that it can be retried instantly.
+    pub(super) fn start_puzzle(&mut self, index: usize) {
+        let puzzle = &self.puzzles[index];
+        let rules = self.game_mode().rules();
+        // the puzzles are made for a grid of the default size
+        let modifiers = Modifiers {
+            big: false,
+            ..self.modifiers
+        };
+        let player = &mut self.local_players[0];
+        player.set_rules(rules);
+        player.set_modifiers(modifiers);
+        player.load(&puzzle.rows(), &puzzle.pieces, puzzle.hold);
+        self.start_game_mode();
+        self.clock = 0.0;
+        self.running = RunningState::Running;
+        self.set_view(ViewState::Local);
+    }
+
+    /// Checks the objective of the puzzle after each tetromino locked since the last call,
+    /// and ends the puzzle once it's solved or failed.
+    ///
+    /// Returns true if the puzzle ended.
+    pub(super) fn follow_puzzle(&mut self, index: usize, new_locks: Vec<ClearedLines>) -> bool {
+        let puzzle = &self.puzzles[index];
+        let player = &self.local_players[0];
+        let board = player.player_screen().grid.bitboard();
+        let mut pieces = player.stats().pieces - new_locks.len() as u64;
+        let mut lines =
+            player.player_screen().score - new_locks.iter().map(|lock| lock.total).sum::<u64>();
+        let mut state = PuzzleState::Ongoing;
+        for lock in new_locks {
+            pieces += 1;
+            lines += lock.total;
+            let out_of_pieces = pieces == puzzle.nb_pieces();
+            state = puzzle
+                .objective
+                .check(lock, pieces, lines, board, out_of_pieces);
+            if state != PuzzleState::Ongoing {
+                break;
+            }
+        }
+        match state {
+            PuzzleState::Ongoing => return false,
+            PuzzleState::Solved => self.finish_puzzle(index, true),
+            PuzzleState::Failed => self.finish_puzzle(index, false),
+        }
+        true
+    }
+
+    /// Ends the puzzle, shows whether it's solved and remembers it if it is.
+    pub(super) fn finish_puzzle(&mut self, index: usize, solved: bool) {
+        self.running = RunningState::NotRunning;
+        let player = &mut self.local_players[0];
+        player.stop();
+        let pieces = player.stats().pieces;
+        let puzzle = &self.puzzles[index];
+        let contents = [
+            String::from(if solved { "Solved!" } else { "Failed" }),
+            puzzle.name.clone(),
+            format!("Objective: {}", puzzle.objective),
+            format!("Pieces: {pieces}/{}", puzzle.nb_pieces()),
+            format!("Time: {}", format_seconds(self.clock)),
+            String::from(if solved {
+                "Next puzzle with the button above"
+            } else {
+                "Press R to retry"
+            }),
+        ];
+        if solved && self.records.puzzles.insert(puzzle.id()) {
+            self.records.save();
+        }
+        self.record_result(if solved {
+            Outcome::Finished
+        } else {
+            Outcome::Failed
+        });
+        self.show_finish_screen(&contents);
+    }
+
+    /// Shows the puzzle over the grid before it's started.
+    pub(super) fn prepare_puzzle_intro(&mut self) {
+        let GameMode::Puzzle { index } = self.game_mode() else {
+            return;
+        };
+        if self.running != RunningState::NotRunning || !self.finish_texts.is_empty() {
+            return;
+        }
+        let puzzle = &self.puzzles[index];
+        let pieces: Vec<String> = puzzle
+            .pieces
+            .iter()
+            .map(|kind| kind.get().to_string())
+            .collect();
+        let mut contents = vec![
+            puzzle.name.clone(),
+            format!("From {}", puzzle.pack),
+            format!("Objective: {}", puzzle.objective),
+            format!("Pieces: {}", pieces.join(" ")),
+        ];
+        if let Some(hold) = puzzle.hold {
+            contents.push(format!("Hold: {}", hold.get()));
+        }
+        if self.records.puzzles.contains(&puzzle.id()) {
+            contents.push(String::from("Solved"));
+        }
+        self.show_finish_screen(&contents);
+    }
+}
+
+/// Returns the content of a cell of the board of a puzzle.
+pub(super) fn cell(c: char) -> Option<TetrisColor> {
+    let kind = match c {
+        '.' => return None,
+        'I' => TetrominoKind::I,
+        'O' => TetrominoKind::O,
+        'T' => TetrominoKind::T,
+        'S' => TetrominoKind::S,
+        'Z' => TetrominoKind::Z,
+        'J' => TetrominoKind::J,
+        'L' => TetrominoKind::L,
+        _ => return Some(TetrisColor::Grey),
+    };
+    Some(kind.get_color())
+}
+
+/// Returns true if an empty cell of the grid is under a filled one.
+fn has_holes(board: &Bitboard) -> bool {
+    // the columns with a block in the rows above
+    let mut covered = 0;
+    for y in 0..board.nb_rows() as usize {
+        let row = board.row(y);
+        if covered & !row != 0 {
+            return true;
+        }
+        covered |= row;
+    }
+    false
+}
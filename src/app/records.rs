@@ -0,0 +1,88 @@
+//! Defines the [Records] of the player, kept between two launches of the game.
+use super::Modifiers;
+use crate::utils::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub(super) static RECORDS_FILE: &str = "records.json";
+
+/// Best results of the player in each game mode.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Records {
+    /// Personal best of the sprints, by number of lines.
+    #[serde(default)]
+    pub sprint: HashMap<u64, SprintRecord>,
+    /// Personal best of the dig races, by number of garbage rows.
+    #[serde(default)]
+    pub dig: HashMap<u64, DigRecord>,
+    /// Totals of all the zen sessions.
+    #[serde(default)]
+    pub zen: ZenRecord,
+    /// Puzzles solved at least once, by [Puzzle::id()](super::puzzle::Puzzle::id).
+    #[serde(default)]
+    pub puzzles: BTreeSet<String>,
+    /// Attempts at the openers of the trainer, by name of opener.
+    #[serde(default)]
+    pub openers: HashMap<String, OpenerRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SprintRecord {
+    /// Time in seconds between the first input and the last line.
+    pub time: f64,
+    pub pieces: u64,
+    pub keys: u64,
+    /// Time at which every [SPRINT_SPLIT_LINES](crate::settings::SPRINT_SPLIT_LINES) lines were cleared.
+    pub splits: Vec<f64>,
+    /// Modifiers the sprint was played with.
+    #[serde(default)]
+    pub modifiers: Modifiers,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DigRecord {
+    /// Time in seconds between the first input and the last garbage row.
+    pub time: f64,
+    pub pieces: u64,
+    pub keys: u64,
+    /// Lines cleared, with or without garbage.
+    pub lines: u64,
+    #[serde(default)]
+    pub modifiers: Modifiers,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct ZenRecord {
+    pub lines: u64,
+    pub pieces: u64,
+    /// Number of times the top of the stack was cleared when it reached the top of the grid.
+    pub top_clears: u64,
+}
+
+/// Number of attempts at building an opener and how many of them succeeded.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+pub struct OpenerRecord {
+    pub attempts: u64,
+    pub successes: u64,
+}
+
+impl Records {
+    /// Reads the records from the data directory, they are empty the first time.
+    pub fn load() -> Records {
+        storage::load(RECORDS_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = storage::save(RECORDS_FILE, self) {
+            println!("couldn't save the records: {error}");
+        }
+    }
+}
+
+/// Returns the number of seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
@@ -46,7 +46,7 @@ impl Serialize for PlayerScreen {
         S: Serializer,
     {
         if !*self.serialize_as_msg.borrow() {
-            let mut s = serializer.serialize_struct("PlayerScreen", 8)?;
+            let mut s = serializer.serialize_struct("PlayerScreen", 11)?;
             s.serialize_field("grid", &self.grid)?;
             s.serialize_field("score", &self.score)?;
             s.serialize_field("game_over", &self.game_over)?;
@@ -54,6 +54,9 @@ impl Serialize for PlayerScreen {
             s.serialize_field("active_tetromino", &self.active_tetromino)?;
             s.serialize_field("saved_tetromino", &self.saved_tetromino)?;
             s.serialize_field("fifo_next_tetromino", &self.fifo_next_tetromino)?;
+            s.serialize_field("has_hold", &self.has_hold)?;
+            s.serialize_field("nb_previews", &self.nb_previews)?;
+            s.serialize_field("hidden_queue", &self.hidden_queue)?;
             s.serialize_field("serialize_as_msg", &self.serialize_as_msg)?;
             s.end()
         } else {
@@ -1,15 +1,39 @@
 //! Defines the render function of [App].
 use super::{App, RunningState, ViewState};
-use crate::settings::{BG_COLOR, DEFAULT_WINDOW_WIDTH};
+use crate::settings::{
+    BLOCK_SIZE, DEFAULT_FONT_SIZE, DEFAULT_GRID_X, DEFAULT_GRID_Y, DEFAULT_TITLE_Y,
+    DEFAULT_WINDOW_WIDTH, FINISH_BG_COLOR, NB_COLUMNS, NB_ROWS, TEXT_COLOR, WINDOW_SCALE,
+};
+use crate::ui::text::Text;
 use crate::utils::formattings::format_seconds;
-use graphics::Transformed;
+use graphics::{Context, Transformed};
 use piston::RenderArgs;
 
 impl App<'_> {
     pub fn render(&mut self, args: &RenderArgs) {
+        let time_left = self.time_left();
+        let outlines = self.opener_outlines();
+        // the finesse is only counted with the modern handling
+        let mut finesse = (self.view_state == ViewState::Local
+            && self.game_mode().rules().hard_drop)
+            .then(|| {
+                let player = &self.local_players[0];
+                let faults = format!("Finesse faults: {}", player.stats().finesse_faults);
+                match player.finesse_extra() {
+                    0 => faults,
+                    extra => format!("{faults} (+{extra})"),
+                }
+            });
+        let mut live_stats = (self.view_state == ViewState::Local).then(|| self.live_stats());
+        let background = self.background();
         self.gl.draw(args.viewport(), |ctx, gl| {
+            // everything is placed in a window of the default size, then scaled to the window
+            let ctx = Context {
+                transform: ctx.transform.scale(*WINDOW_SCALE, *WINDOW_SCALE),
+                ..ctx
+            };
             // Clear the screen.
-            graphics::clear(BG_COLOR, gl);
+            graphics::clear(background, gl);
 
             match &self.view_state {
                 ViewState::MainMenu => {
@@ -20,6 +44,8 @@ impl App<'_> {
                 ViewState::Settings => {
                     self.title_text
                         .render(ctx.transform, &ctx, gl, &mut self.assets.tetris_font);
+                    self.settings_text
+                        .render(ctx.transform, &ctx, gl, &mut self.assets.main_font);
                     for widget_manager in &mut self.widget_manager {
                         widget_manager.render(ctx.transform, &ctx, gl, &mut self.assets);
                     }
@@ -27,6 +53,8 @@ impl App<'_> {
                 ViewState::CreateRoom => {
                     self.title_text
                         .render(ctx.transform, &ctx, gl, &mut self.assets.tetris_font);
+                    self.room_text
+                        .render(ctx.transform, &ctx, gl, &mut self.assets.main_font);
                     self.widget_manager[0].render(ctx.transform, &ctx, gl, &mut self.assets)
                 }
                 ViewState::JoinRoom => {
@@ -34,6 +62,26 @@ impl App<'_> {
                         .render(ctx.transform, &ctx, gl, &mut self.assets.tetris_font);
                     self.widget_manager[0].render(ctx.transform, &ctx, gl, &mut self.assets)
                 }
+                ViewState::Results => {
+                    for text in &self.results_texts {
+                        text.render(ctx.transform, &ctx, gl, &mut self.assets.main_font);
+                    }
+                    self.widget_manager[0].render(ctx.transform, &ctx, gl, &mut self.assets)
+                }
+                ViewState::Editor => {
+                    let hint_text = Text::new(
+                        "Letters add to the queue, Backspace removes",
+                        DEFAULT_FONT_SIZE,
+                        DEFAULT_WINDOW_WIDTH as f64 / 2.0,
+                        DEFAULT_TITLE_Y,
+                        *TEXT_COLOR,
+                    );
+                    hint_text.render(ctx.transform, &ctx, gl, &mut self.assets.main_font);
+                    self.mode_text
+                        .render(ctx.transform, &ctx, gl, &mut self.assets.main_font);
+                    self.local_players[0].render(ctx.transform, &ctx, gl, &mut self.assets);
+                    self.widget_manager[0].render(ctx.transform, &ctx, gl, &mut self.assets)
+                }
                 a if a.is_game() => {
                     if self.running == RunningState::Running {
                         self.title_text.render(
@@ -67,8 +115,28 @@ impl App<'_> {
                         self.timer_text.set_text("Elapsed: 0.00s".to_string());
                     }
 
+                    if let Some(time_left) = time_left {
+                        self.timer_text
+                            .set_text(format!("Time left: {}", format_seconds(time_left)));
+                    }
                     self.timer_text
                         .render(ctx.transform, &ctx, gl, &mut self.assets.main_font);
+                    self.mode_text
+                        .render(ctx.transform, &ctx, gl, &mut self.assets.main_font);
+                    if let Some(finesse) = finesse.take() {
+                        self.finesse_text.set_text(finesse);
+                        self.finesse_text.render(
+                            ctx.transform,
+                            &ctx,
+                            gl,
+                            &mut self.assets.main_font,
+                        );
+                    }
+                    if let Some(live_stats) = live_stats.take() {
+                        self.stats_text.set_text(live_stats);
+                        self.stats_text
+                            .render(ctx.transform, &ctx, gl, &mut self.assets.main_font);
+                    }
 
                     let mut nb_players = 0;
                     for player in &mut self.local_players {
@@ -81,6 +149,12 @@ impl App<'_> {
                         );
                         nb_players += 1;
                     }
+                    if let Some(player) = self.local_players.first() {
+                        player
+                            .player_screen()
+                            .grid
+                            .render_outlines(&outlines, &ctx.draw_state, gl);
+                    }
                     for player in &mut self.remote_player {
                         player.render(
                             ctx.transform
@@ -92,6 +166,20 @@ impl App<'_> {
                         nb_players += 1;
                     }
 
+                    // the finish screen covers the grid until the next game
+                    if self.running == RunningState::NotRunning && !self.finish_texts.is_empty() {
+                        let dims = [
+                            DEFAULT_GRID_X,
+                            DEFAULT_GRID_Y + 2.0 * BLOCK_SIZE,
+                            NB_COLUMNS as f64 * BLOCK_SIZE,
+                            (NB_ROWS - 2) as f64 * BLOCK_SIZE,
+                        ];
+                        graphics::rectangle(FINISH_BG_COLOR, dims, ctx.transform, gl);
+                        for text in &self.finish_texts {
+                            text.render(ctx.transform, &ctx, gl, &mut self.assets.main_font);
+                        }
+                    }
+
                     self.widget_manager[0].render(ctx.transform, &ctx, gl, &mut self.assets)
                 }
                 _ => unreachable!(),
@@ -0,0 +1,477 @@
+//! Defines the [GameResult]s of the finished games, ranked in the leaderboards of the game modes and logged in the match history.
+use super::{
+    player::PlayerStats,
+    records::{now, RECORDS_FILE},
+    App, GameMode, Modifiers, Rules, ViewState,
+};
+use crate::settings::{
+    BLOCK_SIZE, DEFAULT_FONT_SIZE, DEFAULT_PROFILE, DEFAULT_TITLE_Y, DEFAULT_WINDOW_WIDTH,
+    HISTORY_PAGE_SIZE, LEADERBOARD_SIZE, TEXT_COLOR,
+};
+use crate::ui::text::Text;
+use crate::utils::formattings::{format_date, format_seconds};
+use crate::utils::storage;
+use crate::PlayerConfig;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+static LEADERBOARDS_FILE: &str = "leaderboards.json";
+static HISTORY_FILE: &str = "history.json";
+
+/// Version of the format of the files of the leaderboards and of the match history, written in them.
+///
+/// A file of another version is renamed after its version and replaced by a new one.
+const FORMAT_VERSION: u32 = 1;
+
+/// Contents of a file along with the version of its format.
+#[derive(Serialize, Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    contents: T,
+}
+
+/// How a game ended.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// The goal of the game mode was reached, or its time ran out.
+    Finished,
+    /// The puzzle wasn't solved or the opener wasn't built.
+    Failed,
+    GameOver,
+    /// The other player of a versus topped out first.
+    Won,
+    Lost,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Finished => write!(f, "Finished"),
+            Outcome::Failed => write!(f, "Failed"),
+            Outcome::GameOver => write!(f, "Game over"),
+            Outcome::Won => write!(f, "Won"),
+            Outcome::Lost => write!(f, "Lost"),
+        }
+    }
+}
+
+/// Result of a finished game of the first local player.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GameResult {
+    /// Name of the game mode, or of the versus.
+    pub mode: String,
+    pub outcome: Outcome,
+    /// Seconds since the Unix epoch at the end of the game.
+    pub date: u64,
+    /// Name of the profile of the player.
+    pub profile: String,
+    /// Name of the other player of a versus.
+    #[serde(default)]
+    pub opponent: Option<String>,
+    pub rules: Rules,
+    pub modifiers: Modifiers,
+    /// Duration of the game in seconds.
+    pub time: f64,
+    /// Lines cleared, or points of a classic game, or level of a master.
+    pub score: u64,
+    pub stats: PlayerStats,
+}
+
+/// Ultra of the leaderboards formerly kept in the records, see [migrate_records()].
+#[derive(Deserialize)]
+struct RecordedUltra {
+    score: u64,
+    pieces: u64,
+    keys: u64,
+    date: u64,
+    #[serde(default)]
+    modifiers: Modifiers,
+}
+
+/// How the results of a game mode are ranked in its leaderboard.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Ranking {
+    /// The fastest games first, only the finished ones being ranked.
+    Time,
+    /// The highest scores first.
+    Score,
+}
+
+/// Page of the results shown by [ViewState::Results].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(super) enum ResultsPage {
+    /// Leaderboard of the ranked game mode of this index, see [ranked_modes()].
+    Leaderboard(usize),
+    /// Page of the match history of this index, starting from the latest games.
+    History(usize),
+}
+
+/// Leaderboards of the game modes and match history, kept between two launches of the game.
+#[derive(Default)]
+pub struct Results {
+    /// Best results of each game mode by name of the mode, from the best one.
+    leaderboards: HashMap<String, Vec<GameResult>>,
+    /// All the finished games, from the oldest one.
+    history: Vec<GameResult>,
+}
+
+/// Returns how the game mode is ranked in its leaderboard, or None if it has none.
+fn ranking(game_mode: GameMode) -> Option<Ranking> {
+    match game_mode {
+        GameMode::Sprint { lines: _ }
+        | GameMode::Dig { rows: _ }
+        | GameMode::Marathon {
+            start_level: _,
+            lines: Some(_),
+        } => Some(Ranking::Time),
+        GameMode::Endless
+        | GameMode::Ultra { seconds: _ }
+        | GameMode::Marathon {
+            start_level: _,
+            lines: None,
+        }
+        | GameMode::Master
+        | GameMode::Classic { start_level: _ } => Some(Ranking::Score),
+        _ => None,
+    }
+}
+
+/// Returns the game modes which have a leaderboard, in the order of the mode button.
+fn ranked_modes() -> Vec<GameMode> {
+    let mut modes = vec![];
+    let mut game_mode = GameMode::Endless;
+    loop {
+        if ranking(game_mode).is_some() {
+            modes.push(game_mode);
+        }
+        game_mode = game_mode.next();
+        if game_mode == GameMode::Endless {
+            return modes;
+        }
+    }
+}
+
+/// Returns the result of the game as ranked in the leaderboard of its game mode.
+fn describe_score(game_mode: GameMode, result: &GameResult) -> String {
+    match (ranking(game_mode), game_mode) {
+        (Some(Ranking::Time), _) => format_seconds(result.time),
+        (_, GameMode::Classic { start_level: _ }) => format!("{} points", result.score),
+        (_, GameMode::Master) => format!("Level {}", result.score),
+        _ => format!("{} lines", result.score),
+    }
+}
+
+/// Returns the name of the usual rules, or "Custom rules".
+fn describe_rules(rules: &Rules) -> &'static str {
+    if *rules == Rules::default() {
+        "Guideline rules"
+    } else if *rules == Rules::classic() {
+        "NES rules"
+    } else {
+        "Custom rules"
+    }
+}
+
+/// Reads the file of the data directory, setting it aside if it was written with another version of the format.
+fn load_versioned<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    let Some(file) = storage::load::<Versioned<serde_json::Value>>(file_name) else {
+        return T::default();
+    };
+    if file.version == FORMAT_VERSION {
+        if let Ok(contents) = serde_json::from_value(file.contents) {
+            return contents;
+        }
+    }
+    let kept_as = format!("{file_name}.v{}", file.version);
+    println!("{file_name} can't be read with the version {FORMAT_VERSION}, it's kept as {kept_as}");
+    if let Err(error) = storage::rename(file_name, &kept_as) {
+        println!("couldn't rename {file_name}: {error}");
+    }
+    T::default()
+}
+
+fn save_versioned<T: Serialize>(file_name: &str, contents: T) {
+    let file = Versioned {
+        version: FORMAT_VERSION,
+        contents,
+    };
+    if let Err(error) = storage::save(file_name, &file) {
+        println!("couldn't save {file_name}: {error}");
+    }
+}
+
+/// Moves the leaderboards of the ultras out of the records, where they were kept before the leaderboards of all the modes.
+fn migrate_records(leaderboards: &mut HashMap<String, Vec<GameResult>>) {
+    let Some(mut records) = storage::load::<serde_json::Value>(RECORDS_FILE) else {
+        return;
+    };
+    let Some(ultras) = records
+        .as_object_mut()
+        .and_then(|records| records.remove("ultra"))
+    else {
+        return;
+    };
+    let ultras: HashMap<u64, Vec<RecordedUltra>> =
+        serde_json::from_value(ultras).unwrap_or_default();
+    for (seconds, ultras) in ultras {
+        let mode = GameMode::Ultra { seconds }.to_string();
+        let leaderboard = leaderboards.entry(mode.clone()).or_default();
+        leaderboard.extend(ultras.into_iter().map(|ultra| GameResult {
+            mode: mode.clone(),
+            outcome: Outcome::Finished,
+            date: ultra.date,
+            profile: String::from(DEFAULT_PROFILE),
+            opponent: None,
+            rules: Rules::default(),
+            modifiers: ultra.modifiers,
+            time: seconds as f64,
+            score: ultra.score,
+            stats: PlayerStats {
+                pieces: ultra.pieces,
+                keys: ultra.keys,
+                lines: ultra.score,
+                ..PlayerStats::default()
+            },
+        }));
+        // a tie goes to the oldest result
+        leaderboard.sort_by(|a, b| b.score.cmp(&a.score).then(a.date.cmp(&b.date)));
+        leaderboard.truncate(LEADERBOARD_SIZE);
+    }
+    save_versioned(LEADERBOARDS_FILE, &*leaderboards);
+    if let Err(error) = storage::save(RECORDS_FILE, &records) {
+        println!("couldn't save the records: {error}");
+    }
+}
+
+impl Results {
+    /// Reads the leaderboards and the match history from the data directory, they are empty the first time.
+    pub fn load() -> Results {
+        let mut leaderboards = load_versioned(LEADERBOARDS_FILE);
+        migrate_records(&mut leaderboards);
+        Results {
+            leaderboards,
+            history: load_versioned(HISTORY_FILE),
+        }
+    }
+
+    /// Returns the leaderboard of the game mode, from the best result.
+    pub(super) fn leaderboard(&self, game_mode: GameMode) -> &[GameResult] {
+        self.leaderboards
+            .get(&game_mode.to_string())
+            .map_or(&[][..], Vec::as_slice)
+    }
+
+    /// Logs the result in the match history, and adds it to the leaderboard of its game mode
+    /// if it's among the LEADERBOARD_SIZE best.
+    ///
+    /// Returns its rank starting from 0, or None if it didn't enter the leaderboard.
+    fn insert(&mut self, game_mode: Option<GameMode>, result: GameResult) -> Option<usize> {
+        self.history.push(result.clone());
+        save_versioned(HISTORY_FILE, &self.history);
+        let ranking = game_mode.and_then(ranking)?;
+        let is_better = |result: &GameResult, other: &GameResult| match ranking {
+            Ranking::Time => result.time < other.time,
+            Ranking::Score => result.score > other.score,
+        };
+        match (ranking, result.outcome) {
+            (_, Outcome::Failed) => return None,
+            (Ranking::Time, outcome) if outcome != Outcome::Finished => return None,
+            _ => {}
+        }
+        let leaderboard = self.leaderboards.entry(result.mode.clone()).or_default();
+        // a tie goes to the oldest result
+        let rank = leaderboard
+            .iter()
+            .position(|other| is_better(&result, other))
+            .unwrap_or(leaderboard.len());
+        if rank >= LEADERBOARD_SIZE {
+            return None;
+        }
+        leaderboard.insert(rank, result);
+        leaderboard.truncate(LEADERBOARD_SIZE);
+        save_versioned(LEADERBOARDS_FILE, &self.leaderboards);
+        Some(rank)
+    }
+}
+
+impl App<'_> {
+    /// Returns the result of the game of the first local player as it ends.
+    fn game_result(&self, mode: String, outcome: Outcome, score: u64) -> GameResult {
+        let player = &self.local_players[0];
+        GameResult {
+            mode,
+            outcome,
+            date: now(),
+            profile: self.profile_name(0),
+            opponent: None,
+            rules: player.rules(),
+            modifiers: player.modifiers(),
+            time: self.clock,
+            score,
+            stats: player.stats(),
+        }
+    }
+
+    /// Keeps the result of the single-player game that just ended.
+    ///
+    /// Returns its rank in the leaderboard of the game mode starting from 0, or None if it didn't enter it.
+    pub(super) fn record_result(&mut self, outcome: Outcome) -> Option<usize> {
+        let game_mode = self.game_mode();
+        let result = self.game_result(game_mode.to_string(), outcome, self.mode_score());
+        self.count_profile_game(0, outcome == Outcome::Finished);
+        self.results.insert(Some(game_mode), result)
+    }
+
+    /// Keeps the result of the versus that just ended, from the side of the first local player.
+    pub(super) fn record_versus(&mut self) {
+        let (mode, opponent) = match &self.player_config {
+            PlayerConfig::TwoLocal => ("Local versus", self.profile_name(1)),
+            PlayerConfig::TwoRemote {
+                local_ip: _,
+                remote_ip,
+            } => ("Online versus", remote_ip.clone()),
+            _ => return,
+        };
+        let outcome = if self.local_players[0].get_game_over() {
+            Outcome::Lost
+        } else {
+            Outcome::Won
+        };
+        let score = self.local_players[0].player_screen().score;
+        let mut result = self.game_result(String::from(mode), outcome, score);
+        result.opponent = Some(opponent);
+        self.results.insert(None, result);
+        for seat in 0..self.local_players.len() {
+            let won = !self.local_players[seat].get_game_over();
+            self.count_profile_game(seat, won);
+        }
+    }
+
+    /// Returns the title of the page of the results and its lines.
+    fn results_contents(&self) -> (String, Vec<String>) {
+        match self.results_page {
+            ResultsPage::Leaderboard(index) => {
+                let game_mode = ranked_modes()[index];
+                let mut lines = vec![];
+                for (i, result) in self.results.leaderboard(game_mode).iter().enumerate() {
+                    lines.push(format!(
+                        "{}. {}  {}  {}",
+                        i + 1,
+                        describe_score(game_mode, result),
+                        result.profile,
+                        format_date(result.date)
+                    ));
+                    lines.push(format!(
+                        "{}, modifiers: {}",
+                        describe_rules(&result.rules),
+                        result.modifiers
+                    ));
+                }
+                if lines.is_empty() {
+                    lines.push(String::from("No results yet"));
+                }
+                (game_mode.to_string(), lines)
+            }
+            ResultsPage::History(page) => {
+                let nb_pages = self
+                    .results
+                    .history
+                    .len()
+                    .div_ceil(HISTORY_PAGE_SIZE)
+                    .max(1);
+                let mut lines = vec![];
+                for result in self
+                    .results
+                    .history
+                    .iter()
+                    .rev()
+                    .skip(page * HISTORY_PAGE_SIZE)
+                    .take(HISTORY_PAGE_SIZE)
+                {
+                    let against = result
+                        .opponent
+                        .as_ref()
+                        .map_or(String::new(), |opponent| format!(" against {opponent}"));
+                    lines.push(format!(
+                        "{}  {}: {}{against}",
+                        format_date(result.date),
+                        result.mode,
+                        result.outcome
+                    ));
+                    lines.push(format!(
+                        "{}: {} lines, {} pieces in {}",
+                        result.profile,
+                        result.stats.lines,
+                        result.stats.pieces,
+                        format_seconds(result.time)
+                    ));
+                }
+                if lines.is_empty() {
+                    lines.push(String::from("No games yet"));
+                }
+                (format!("Match history {}/{nb_pages}", page + 1), lines)
+            }
+        }
+    }
+
+    /// Returns the texts of the page of the results, and the label of the button switching between the leaderboards and the match history.
+    pub(super) fn results_texts(&self) -> (Vec<Text>, &'static str) {
+        let (title, lines) = self.results_contents();
+        let mut texts = vec![Text::new(
+            &title,
+            DEFAULT_FONT_SIZE,
+            DEFAULT_WINDOW_WIDTH as f64 / 2.0,
+            DEFAULT_TITLE_Y,
+            *TEXT_COLOR,
+        )];
+        texts.extend(lines.iter().enumerate().map(|(i, line)| {
+            Text::new(
+                line,
+                DEFAULT_FONT_SIZE,
+                DEFAULT_WINDOW_WIDTH as f64 / 2.0,
+                DEFAULT_TITLE_Y + (2.0 + 1.2 * i as f64) * BLOCK_SIZE,
+                *TEXT_COLOR,
+            )
+        }));
+        let switch_label = match self.results_page {
+            ResultsPage::Leaderboard(_) => "Match history",
+            ResultsPage::History(_) => "Leaderboards",
+        };
+        (texts, switch_label)
+    }
+
+    /// Goes from the leaderboards to the match history and back.
+    pub(super) fn switch_results(&mut self) {
+        self.results_page = match self.results_page {
+            ResultsPage::Leaderboard(_) => ResultsPage::History(0),
+            ResultsPage::History(_) => ResultsPage::Leaderboard(0),
+        };
+        self.set_view(ViewState::Results);
+    }
+
+    /// Shows the leaderboard of the previous or next ranked mode, or the previous or next page of the match history.
+    pub(super) fn turn_results_page(&mut self, forward: bool) {
+        let nb_pages = match self.results_page {
+            ResultsPage::Leaderboard(_) => ranked_modes().len(),
+            ResultsPage::History(_) => self
+                .results
+                .history
+                .len()
+                .div_ceil(HISTORY_PAGE_SIZE)
+                .max(1),
+        };
+        let turn = |index: usize| {
+            if forward {
+                (index + 1) % nb_pages
+            } else {
+                (index + nb_pages - 1) % nb_pages
+            }
+        };
+        self.results_page = match self.results_page {
+            ResultsPage::Leaderboard(index) => ResultsPage::Leaderboard(turn(index)),
+            ResultsPage::History(page) => ResultsPage::History(turn(page)),
+        };
+        self.set_view(ViewState::Results);
+    }
+}
@@ -0,0 +1,197 @@
+//! Defines the [Stat]s computed from the counters of a player, shown during and after the game and exported to files.
+use super::{player::PlayerStats, records::now, App, TetrominoKind};
+use crate::settings::LIVE_STATS;
+use crate::utils::storage;
+use serde::Serialize;
+
+/// Kinds of tetrominos in the order of the counters of [PlayerStats::kinds].
+const KINDS: [TetrominoKind; 7] = [
+    TetrominoKind::I,
+    TetrominoKind::O,
+    TetrominoKind::T,
+    TetrominoKind::S,
+    TetrominoKind::Z,
+    TetrominoKind::J,
+    TetrominoKind::L,
+];
+
+/// Statistic of a player, computed from its counters and the duration of the game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stat {
+    Pieces,
+    /// Pieces per second.
+    Pps,
+    /// Key presses per piece.
+    Kpp,
+    Lines,
+    Attack,
+    /// Attack per minute.
+    Apm,
+    MaxCombo,
+    TSpins,
+    MiniTSpins,
+    /// Share of the lines cleared by tetrises.
+    TetrisRate,
+    Holds,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 11] = [
+        Stat::Pieces,
+        Stat::Pps,
+        Stat::Kpp,
+        Stat::Lines,
+        Stat::Attack,
+        Stat::Apm,
+        Stat::MaxCombo,
+        Stat::TSpins,
+        Stat::MiniTSpins,
+        Stat::TetrisRate,
+        Stat::Holds,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stat::Pieces => "Pieces",
+            Stat::Pps => "PPS",
+            Stat::Kpp => "KPP",
+            Stat::Lines => "Lines",
+            Stat::Attack => "Attack",
+            Stat::Apm => "APM",
+            Stat::MaxCombo => "Max combo",
+            Stat::TSpins => "T-spins",
+            Stat::MiniTSpins => "Mini T-spins",
+            Stat::TetrisRate => "Tetris rate",
+            Stat::Holds => "Holds",
+        }
+    }
+
+    /// Returns the value of the stat after the given number of seconds of game, 0 when there's nothing to divide by.
+    pub fn value(self, stats: &PlayerStats, seconds: f64) -> f64 {
+        let ratio = |count: u64, total: f64| {
+            if total > 0.0 {
+                count as f64 / total
+            } else {
+                0.0
+            }
+        };
+        match self {
+            Stat::Pieces => stats.pieces as f64,
+            Stat::Pps => ratio(stats.pieces, seconds),
+            Stat::Kpp => ratio(stats.keys, stats.pieces as f64),
+            Stat::Lines => stats.lines as f64,
+            Stat::Attack => stats.attack as f64,
+            Stat::Apm => ratio(stats.attack, seconds / 60.0),
+            Stat::MaxCombo => stats.max_combo as f64,
+            Stat::TSpins => stats.t_spins as f64,
+            Stat::MiniTSpins => stats.mini_t_spins as f64,
+            Stat::TetrisRate => 100.0 * ratio(4 * stats.tetrises, stats.lines as f64),
+            Stat::Holds => stats.holds as f64,
+        }
+    }
+
+    /// Returns the name of the stat followed by its value, as shown on the screen.
+    pub fn describe(self, stats: &PlayerStats, seconds: f64) -> String {
+        let value = self.value(stats, seconds);
+        match self {
+            Stat::Pps | Stat::Kpp | Stat::Apm => format!("{}: {value:.2}", self.name()),
+            Stat::TetrisRate => format!("{}: {value:.0}%", self.name()),
+            _ => format!("{}: {value}", self.name()),
+        }
+    }
+}
+
+/// Stats of a game written in the data directory, the counters along with the stats computed from them.
+#[derive(Serialize)]
+struct StatsExport {
+    mode: String,
+    /// Seconds since the Unix epoch when the stats were exported.
+    date: u64,
+    seconds: f64,
+    pps: f64,
+    kpp: f64,
+    apm: f64,
+    tetris_rate: f64,
+    #[serde(flatten)]
+    counters: PlayerStats,
+}
+
+impl App<'_> {
+    /// Returns the stats of LIVE_STATS for the single-player game, shown under the grid.
+    pub(super) fn live_stats(&self) -> String {
+        let stats = self.local_players[0].stats();
+        LIVE_STATS
+            .iter()
+            .map(|stat| stat.describe(&stats, self.clock))
+            .collect::<Vec<String>>()
+            .join("  ")
+    }
+
+    /// Returns all the stats of the player, one per line, followed by the number of tetrominos of each kind.
+    fn stats_contents(&self) -> Vec<String> {
+        let stats = self.local_players[0].stats();
+        let mut contents: Vec<String> = Stat::ALL
+            .iter()
+            .map(|stat| stat.describe(&stats, self.clock))
+            .collect();
+        let kinds: Vec<String> = KINDS
+            .iter()
+            .zip(stats.kinds)
+            .map(|(kind, count)| format!("{}{count}", kind.get()))
+            .collect();
+        contents.push(kinds.join(" "));
+        contents
+    }
+
+    /// Shows all the stats of the single-player game when it's lost.
+    pub(super) fn show_game_over_stats(&mut self) {
+        let mut contents = vec![String::from("Game over")];
+        contents.extend(self.stats_contents());
+        self.show_finish_screen(&contents);
+    }
+
+    /// Writes the stats of the single-player game in a JSON and a CSV file of the data directory, named after the date.
+    pub(super) fn export_stats(&mut self) {
+        let stats = self.local_players[0].stats();
+        let date = now();
+        let export = StatsExport {
+            mode: self.game_mode().to_string(),
+            date,
+            seconds: self.clock,
+            pps: Stat::Pps.value(&stats, self.clock),
+            kpp: Stat::Kpp.value(&stats, self.clock),
+            apm: Stat::Apm.value(&stats, self.clock),
+            tetris_rate: Stat::TetrisRate.value(&stats, self.clock),
+            counters: stats,
+        };
+        let header: Vec<String> = ["Mode", "Date", "Seconds"]
+            .into_iter()
+            .chain(Stat::ALL.iter().map(|stat| stat.name()))
+            .map(String::from)
+            .chain(KINDS.iter().map(|kind| kind.get()))
+            .collect();
+        let values: Vec<String> = [
+            export.mode.replace(',', " "),
+            date.to_string(),
+            self.clock.to_string(),
+        ]
+        .into_iter()
+        .chain(
+            Stat::ALL
+                .iter()
+                .map(|stat| stat.value(&stats, self.clock).to_string()),
+        )
+        .chain(stats.kinds.iter().map(u64::to_string))
+        .collect();
+        let csv = format!("{}\n{}\n", header.join(","), values.join(","));
+        let result = storage::save(&format!("stats-{date}.json"), &export)
+            .and_then(|()| storage::save_text(&format!("stats-{date}.csv"), &csv));
+        match result {
+            Ok(()) => self.mode_text.set_text(String::from("Stats exported")),
+            Err(error) => {
+                println!("couldn't export the stats: {error}");
+                self.mode_text.set_text(String::from("Couldn't export"));
+            }
+        }
+    }
+}
@@ -1,8 +1,10 @@
 //! Defines the update function of [App].
 //!
 //! [update()](App::update()) is called before each render when the game is active.
-use super::{remote::MessageType, App, Countdown, PlayerConfig, RunningState, ViewState};
-use crate::ui::interactive_widget_manager::ButtonType;
+use super::{
+    App, Countdown, GameMode, Modifier, PlayerConfig, PracticeAction, RunningState, ViewState,
+};
+use crate::ui::interactive_widget_manager::{ButtonType, TextInputType};
 use piston::UpdateArgs;
 
 impl App<'_> {
@@ -14,17 +16,25 @@ impl App<'_> {
     ///     - if the game is running, updating the local players and checking that the game still runs
     /// - updating the view through the widget_manager
     ///
-    pub fn update(&mut self, args: &UpdateArgs, fall_speed_divide: u64, freeze: u64) {
+    pub fn update(&mut self, args: &UpdateArgs) {
         // first apply the changes inside the views
         if self.view_state == ViewState::Settings {
-            for (id, widget_manager) in self.widget_manager.iter_mut().enumerate() {
-                widget_manager.update_settings(&mut self.keybindings_manager[id]);
+            for id in 0..self.widget_manager.len() {
+                if self.widget_manager[id].update_settings(&mut self.keybindings_manager[id]) {
+                    self.save_profile_keybindings(id);
+                }
             }
         } else if self.view_state == ViewState::CreateRoom {
-            self.widget_manager[0].update_clipboard();
+            // the room listens on the address of the viewer, there's none to copy if the local IP wasn't found
+            if let PlayerConfig::Viewer(address) = &self.player_config {
+                self.widget_manager[0].update_clipboard(Some(address));
+            }
         } else if self.view_state == ViewState::JoinRoom {
-            self.widget_manager[0].update_clipboard();
+            self.widget_manager[0].update_clipboard(None);
             self.widget_manager[0].update_from_text();
+        } else if self.view_state == ViewState::Editor {
+            // the cells are painted as long as the mouse button is held
+            self.paint();
         } else if self.view_state.is_game() && self.running == RunningState::Starting {
             self.clock += args.dt;
             match self.clock {
@@ -36,8 +46,17 @@ impl App<'_> {
             for player in &mut self.local_players {
                 player.send_serialized();
             }
-        } else if self.view_state.is_game() && self.running == RunningState::Running {
+        } else if self.view_state.is_game()
+            && self.running == RunningState::Running
+            && self.is_time_up(args.dt)
+        {
+            self.time_up();
+        } else if self.view_state.is_game()
+            && self.running == RunningState::Running
+            && !self.waits_for_first_input()
+        {
             self.clock += args.dt;
+            let speed = self.speed();
             self.frame_counter = self.frame_counter.wrapping_add(1);
             if let PlayerConfig::TwoRemote {
                 local_ip: _,
@@ -65,12 +84,7 @@ impl App<'_> {
             }
             // update
             for (id, player) in self.local_players.iter_mut().enumerate() {
-                player.update(
-                    &self.keybindings_manager[id],
-                    self.frame_counter,
-                    fall_speed_divide,
-                    freeze,
-                );
+                player.update(&self.keybindings_manager[id], self.frame_counter, speed);
             }
             // taking into account the player states after a new piece was added
             // two options :
@@ -92,11 +106,18 @@ impl App<'_> {
 
             if game_over {
                 self.game_over();
+            } else {
+                self.update_game_mode();
             }
         }
 
-        // then eventually change the view
-        let result = self.widget_manager[0].update_view();
+        // then eventually change the view, the settings of the right player having their own buttons
+        let result = self
+            .widget_manager
+            .iter_mut()
+            .map(|widget_manager| widget_manager.update_view())
+            .find(|result| *result != ButtonType::Nothing)
+            .unwrap_or(ButtonType::Nothing);
         match result {
             ButtonType::ToPause => {
                 if self.view_state.is_game() {
@@ -123,6 +144,69 @@ impl App<'_> {
                 }
                 self.set_view(ViewState::Local)
             }
+            // the mode can't change during a game
+            ButtonType::NextGameMode if self.running == RunningState::NotRunning => {
+                self.game_mode = self.game_mode.next();
+                self.finish_texts.clear();
+                self.mode_text.set_text(self.initial_mode_text());
+                self.set_view(ViewState::Local);
+            }
+            ButtonType::NextStartLevel if self.running == RunningState::NotRunning => {
+                self.game_mode = self.game_mode.next_start_level();
+                self.mode_text.set_text(self.initial_mode_text());
+                self.set_view(ViewState::Local);
+            }
+            ButtonType::SelectStartLevel(start_level)
+                if self.running == RunningState::NotRunning =>
+            {
+                self.game_mode = GameMode::Classic { start_level };
+                self.restart();
+            }
+            ButtonType::NextItem if self.running == RunningState::NotRunning => {
+                let nb_items = match self.game_mode {
+                    GameMode::Opener { index: _ } => self.openers.len(),
+                    _ => self.puzzles.len(),
+                };
+                self.game_mode = self.game_mode.next_item(nb_items);
+                self.finish_texts.clear();
+                self.mode_text.set_text(self.initial_mode_text());
+                self.set_view(ViewState::Local);
+            }
+            // the modifiers can't change during a game either
+            ButtonType::ToggleModifier(i) if self.running == RunningState::NotRunning => {
+                self.modifiers.toggle(Modifier::ALL[i]);
+                self.set_view(ViewState::Local);
+            }
+            ButtonType::PracticeAction(i) => self.practice(PracticeAction::ALL[i]),
+            ButtonType::ToEditor if self.running == RunningState::NotRunning => {
+                self.finish_texts.clear();
+                self.mode_text.set_text(String::new());
+                self.set_view(ViewState::Editor);
+            }
+            ButtonType::SelectBrush(i) => self.select_brush(i),
+            ButtonType::NextActivePiece => self.next_active_piece(),
+            ButtonType::NextHoldPiece => self.next_hold_piece(),
+            ButtonType::ClearGrid => self.clear_grid(),
+            ButtonType::SavePosition => self.save_position(),
+            ButtonType::LoadPosition => self.load_position(),
+            ButtonType::PlayPosition => self.play_position(),
+            ButtonType::CopyFumen => self.copy_editor_fumen(),
+            ButtonType::PasteFumen => self.paste_editor_fumen(),
+            ButtonType::ToResults => self.set_view(ViewState::Results),
+            ButtonType::SwitchResults => self.switch_results(),
+            ButtonType::PreviousResults => self.turn_results_page(false),
+            ButtonType::NextResults => self.turn_results_page(true),
+            ButtonType::NextProfile(seat) => self.next_profile(seat),
+            ButtonType::NextTheme(seat) => self.next_theme(seat),
+            ButtonType::NextAutoShiftDelay(seat) => self.next_auto_shift_delay(seat),
+            ButtonType::NextAutoRepeatRate(seat) => self.next_auto_repeat_rate(seat),
+            ButtonType::CreateProfile(seat) => {
+                let name = self.widget_manager[seat]
+                    .get_input(TextInputType::ProfileNameInput)
+                    .typed_text()
+                    .to_string();
+                self.create_profile(seat, &name);
+            }
             ButtonType::ToCreateRoom => self.set_view(ViewState::CreateRoom),
             ButtonType::ToJoinRoom => {
                 if self.player_config == PlayerConfig::Local {
@@ -134,15 +218,7 @@ impl App<'_> {
             ButtonType::ToTwoRemoteGameInfo {
                 local_ip,
                 remote_ip,
-            } => {
-                self.set_player_config(PlayerConfig::TwoRemote {
-                    local_ip: local_ip.clone(),
-                    remote_ip,
-                });
-                self.set_view(ViewState::Remote);
-                self.send_message(MessageType::Hello(local_ip));
-                self.local_players[0].send_serialized();
-            }
+            } => self.join_room(local_ip, remote_ip),
             ButtonType::ToTwoLocalGame => {
                 if self.player_config != PlayerConfig::TwoLocal {
                     self.set_player_config(PlayerConfig::TwoLocal);
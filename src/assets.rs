@@ -2,7 +2,7 @@ use opengl_graphics::*;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-#[derive(Clone, Copy, Serialize, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TetrisColor {
     Cyan,
     Yellow,
@@ -0,0 +1,132 @@
+//! Line-delimited JSON server driving a [TetrisEnv] through stdin and stdout, for training code in other languages.
+//!
+//! Each line read on stdin is a request, each request gets exactly one line of response on stdout:
+//!
+//! ```text
+//! {"cmd": "reset", "seed": 42, "action_space": "placement"}
+//!     -> {"observation": {...}}
+//! {"cmd": "step", "action": 3}
+//! {"cmd": "step", "action": "rotate_clockwise"}
+//!     -> {"observation": {...}, "reward": 1.0, "done": false, "info": {...}}
+//! {"cmd": "close"}
+//! ```
+//!
+//! The action space is "key" until another one is given on reset, the key actions are "nothing", "left", "right",
+//! "rotate_clockwise", "rotate_counterclockwise", "soft_drop", "hard_drop" and "hold".
+//! An invalid request gets `{"error": "..."}` and doesn't change the environment.
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+use tetris::env::{Action, ActionSpace, Info, Observation, TetrisEnv};
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Request {
+    Reset {
+        #[serde(default)]
+        seed: u64,
+        action_space: Option<ActionSpace>,
+    },
+    Step {
+        action: Action,
+    },
+    Close,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Response {
+    Reset {
+        observation: Observation,
+    },
+    Step {
+        observation: Observation,
+        reward: f64,
+        done: bool,
+        info: Info,
+    },
+    Error {
+        error: String,
+    },
+}
+
+fn main() {
+    let mut env = TetrisEnv::new(ActionSpace::Key);
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+
+    for line in stdin.lock().lines() {
+        let line = line.expect("couldn't read stdin");
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(response) = respond(&mut env, &line) else {
+            break;
+        };
+        serde_json::to_writer(&mut stdout, &response).unwrap();
+        writeln!(stdout).unwrap();
+        stdout.flush().unwrap();
+    }
+}
+
+/// Returns the response to the request of the line, or None if it closes the environment.
+fn respond(env: &mut TetrisEnv, line: &str) -> Option<Response> {
+    let response = match serde_json::from_str::<Request>(line) {
+        Ok(Request::Reset { seed, action_space }) => {
+            if let Some(action_space) = action_space {
+                if action_space != env.action_space() {
+                    *env = TetrisEnv::new(action_space);
+                }
+            }
+            Response::Reset {
+                observation: env.reset(seed),
+            }
+        }
+        Ok(Request::Step { action }) => match env.step(action) {
+            Ok((observation, reward, done, info)) => Response::Step {
+                observation,
+                reward,
+                done,
+                info,
+            },
+            Err(error) => Response::Error { error },
+        },
+        Ok(Request::Close) => return None,
+        Err(error) => Response::Error {
+            error: error.to_string(),
+        },
+    };
+    Some(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    /// Returns the JSON line answered to the request.
+    fn answer(env: &mut TetrisEnv, line: &str) -> Value {
+        serde_json::to_value(respond(env, line).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn requests_get_one_response() {
+        let mut env = TetrisEnv::new(ActionSpace::Key);
+        let reset = answer(
+            &mut env,
+            r#"{"cmd": "reset", "seed": 42, "action_space": "placement"}"#,
+        );
+        assert!(!reset["observation"]["placements"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+        let step = answer(&mut env, r#"{"cmd": "step", "action": 0}"#);
+        assert_eq!(step["done"], Value::Bool(false));
+        assert_eq!(step["info"]["frame"], 1);
+        // a key action doesn't belong to the placement action space
+        let error = answer(&mut env, r#"{"cmd": "step", "action": "left"}"#);
+        assert!(error["error"].is_string());
+        let error = answer(&mut env, r#"{"cmd": "jump"}"#);
+        assert!(error["error"].is_string());
+        assert!(respond(&mut env, r#"{"cmd": "close"}"#).is_none());
+    }
+}
@@ -0,0 +1,285 @@
+//! Headless simulator running games played by bots, alone or in versus, and printing aggregate statistics.
+//!
+//! The games use the same [LocalPlayer] as the window, so the gravity, the locking and the garbage follow the same rules.
+//! Each game uses its own seed, the games are played with the seeds `seed..seed + games`.
+use serde::Serialize;
+use std::process::exit;
+use tetris::{
+    app::{lines_of_garbage, LocalPlayer, Speed},
+    bot::{new_bot, Bot, BOT_NAMES},
+    settings::{gravity, Keybindings, BAG_SIZE, UPDATES_PER_SECOND},
+    PlayerConfig,
+};
+
+const USAGE: &str = "\
+Usage: tetris-sim [OPTIONS]
+
+Options:
+  --games <N>            number of games to play [default: 100]
+  --seed <SEED>          seed of the first game [default: 0]
+  --bot <NAME>           bot playing the games [default: heuristic]
+  --versus <NAME>        bot playing against the first one, with garbage
+  --bag-size <N>         number of tetromino in a bag [default: 14]
+  --pps <PIECES>         pieces placed per second by the bots [default: 2]
+  --time-limit <SECS>    duration after which a game stops [default: 300]
+  --format <FORMAT>      json or csv [default: json]
+  -h, --help             print this help";
+
+/// Rules shared by all the games of a simulation.
+struct Ruleset {
+    bag_size: u32,
+    pieces_per_second: f64,
+    /// Duration of the game in seconds after which it's stopped.
+    time_limit: f64,
+}
+
+enum Format {
+    Json,
+    Csv,
+}
+
+struct Options {
+    games: u64,
+    seed: u64,
+    /// Names of the bots, one per player.
+    bots: Vec<String>,
+    ruleset: Ruleset,
+    format: Format,
+}
+
+/// Statistics of one player in one game.
+#[derive(Default)]
+struct GameResult {
+    lines: u64,
+    pieces: u64,
+    /// Lines of garbage sent, even in a single-player game.
+    attack: u64,
+    /// Time in seconds before the game over or the time limit.
+    survival_time: f64,
+    won: bool,
+}
+
+/// Aggregate statistics of one bot over all the games.
+#[derive(Serialize)]
+struct Summary {
+    bot: String,
+    games: u64,
+    mean_lines: f64,
+    mean_pieces: f64,
+    attack_per_minute: f64,
+    mean_survival_time: f64,
+    /// Only for versus matches, the games reaching the time limit are not won.
+    win_rate: Option<f64>,
+}
+
+fn main() {
+    let options = parse_args(std::env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{error}\n\n{USAGE}");
+        exit(2);
+    });
+
+    let versus = options.bots.len() > 1;
+    let mut results: Vec<Vec<GameResult>> = options.bots.iter().map(|_| vec![]).collect();
+    // parse_args checked that the last seed fits
+    for seed in options.seed..options.seed + options.games {
+        let mut bots: Vec<Box<dyn Bot>> = options
+            .bots
+            .iter()
+            .enumerate()
+            .map(|(id, name)| new_bot(name, seed ^ id as u64).unwrap())
+            .collect();
+        let game = play_game(&mut bots, seed, &options.ruleset);
+        for (id, result) in game.into_iter().enumerate() {
+            results[id].push(result);
+        }
+    }
+
+    let summaries: Vec<Summary> = options
+        .bots
+        .iter()
+        .zip(&results)
+        .map(|(bot, results)| summarize(bot, results, versus))
+        .collect();
+    match options.format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&summaries).unwrap()),
+        Format::Csv => {
+            println!(
+                "bot,games,mean_lines,mean_pieces,attack_per_minute,mean_survival_time,win_rate"
+            );
+            for summary in summaries {
+                println!(
+                    "{},{},{:.3},{:.3},{:.3},{:.3},{}",
+                    summary.bot,
+                    summary.games,
+                    summary.mean_lines,
+                    summary.mean_pieces,
+                    summary.attack_per_minute,
+                    summary.mean_survival_time,
+                    summary
+                        .win_rate
+                        .map_or(String::new(), |rate| format!("{rate:.3}"))
+                );
+            }
+        }
+    }
+}
+
+/// Plays a game with one player per bot, until a game over or the time limit.
+fn play_game(bots: &mut [Box<dyn Bot>], seed: u64, ruleset: &Ruleset) -> Vec<GameResult> {
+    let keybindings = Keybindings::default();
+    let mut players: Vec<LocalPlayer> = bots
+        .iter()
+        .map(|_| {
+            let mut player = LocalPlayer::new(&PlayerConfig::Local);
+            player.set_bag_size(ruleset.bag_size);
+            // all players get the same pieces, like in a two players game on the same computer
+            player.renew(seed);
+            player.start();
+            player
+        })
+        .collect();
+    let mut results: Vec<GameResult> = bots.iter().map(|_| GameResult::default()).collect();
+
+    let frames_per_piece =
+        ((UPDATES_PER_SECOND as f64 / ruleset.pieces_per_second).round() as u64).max(1);
+    let last_frame = (ruleset.time_limit * UPDATES_PER_SECOND as f64) as u64;
+    let mut frame_counter = 0;
+    while frame_counter < last_frame && !players.iter().any(LocalPlayer::get_game_over) {
+        frame_counter += 1;
+        let clock = frame_counter as f64 / UPDATES_PER_SECOND as f64;
+        let (fall_speed_divide, freeze) = gravity(clock);
+        let speed = Speed::new(fall_speed_divide, freeze);
+
+        send_garbage(&mut players, &mut results);
+        if frame_counter % frames_per_piece == 0 {
+            for ((player, bot), result) in players.iter_mut().zip(bots.iter_mut()).zip(&mut results)
+            {
+                if let Some(decision) = bot.decide(player.player_screen()) {
+                    if decision.hold {
+                        player.hold();
+                    }
+                    for movement in decision.moves {
+                        player.apply_move(movement);
+                    }
+                    result.pieces += 1;
+                }
+            }
+        }
+        for player in &mut players {
+            player.update(&keybindings, frame_counter, speed);
+        }
+    }
+    send_garbage(&mut players, &mut results);
+
+    let survivors = players
+        .iter()
+        .filter(|player| !player.get_game_over())
+        .count();
+    for (player, result) in players.iter().zip(&mut results) {
+        result.lines = player.player_screen().score;
+        result.survival_time = frame_counter as f64 / UPDATES_PER_SECOND as f64;
+        result.won = players.len() > 1 && survivors == 1 && !player.get_game_over();
+    }
+    results
+}
+
+/// Counts the lines of garbage sent by each player since the last call and sends them to the other player.
+fn send_garbage(players: &mut [LocalPlayer], results: &mut [GameResult]) {
+    let completed_lines: Vec<u64> = players
+        .iter_mut()
+        .map(LocalPlayer::get_lines_completed)
+        .collect();
+    for (id, lines) in completed_lines.into_iter().enumerate() {
+        results[id].attack += lines_of_garbage(lines);
+        if players.len() == 2 && lines != 0 {
+            players[1 - id].add_garbage(lines);
+        }
+    }
+}
+
+fn summarize(bot: &str, results: &[GameResult], versus: bool) -> Summary {
+    let games = results.len() as f64;
+    let sum = |stat: fn(&GameResult) -> f64| results.iter().map(stat).sum::<f64>();
+    let minutes = sum(|result| result.survival_time) / 60.0;
+    Summary {
+        bot: bot.to_string(),
+        games: results.len() as u64,
+        mean_lines: sum(|result| result.lines as f64) / games,
+        mean_pieces: sum(|result| result.pieces as f64) / games,
+        attack_per_minute: sum(|result| result.attack as f64) / minutes,
+        mean_survival_time: sum(|result| result.survival_time) / games,
+        win_rate: versus.then(|| sum(|result| result.won as u8 as f64) / games),
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        games: 100,
+        seed: 0,
+        bots: vec![String::from("heuristic")],
+        ruleset: Ruleset {
+            bag_size: BAG_SIZE,
+            pieces_per_second: 2.0,
+            time_limit: 300.0,
+        },
+        format: Format::Json,
+    };
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            println!("{USAGE}");
+            exit(0);
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {arg}"))?;
+        let invalid = || format!("invalid value for {arg}: {value}");
+        match arg.as_str() {
+            "--games" => options.games = value.parse().map_err(|_| invalid())?,
+            "--seed" => options.seed = value.parse().map_err(|_| invalid())?,
+            "--bot" | "--versus" => {
+                if !BOT_NAMES.contains(&value.as_str()) {
+                    return Err(format!(
+                        "unknown bot {value}, expected one of {}",
+                        BOT_NAMES.join(", ")
+                    ));
+                }
+                if arg == "--bot" {
+                    options.bots[0] = value;
+                } else {
+                    options.bots.truncate(1);
+                    options.bots.push(value);
+                }
+            }
+            "--bag-size" => options.ruleset.bag_size = value.parse().map_err(|_| invalid())?,
+            "--pps" => options.ruleset.pieces_per_second = value.parse().map_err(|_| invalid())?,
+            "--time-limit" => options.ruleset.time_limit = value.parse().map_err(|_| invalid())?,
+            "--format" => {
+                options.format = match value.as_str() {
+                    "json" => Format::Json,
+                    "csv" => Format::Csv,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(format!("unknown option {arg}")),
+        }
+    }
+    if options.ruleset.pieces_per_second <= 0.0 {
+        return Err(String::from("--pps must be positive"));
+    }
+    // the games last at least one update, so that the attack per minute has a duration to divide
+    let time_limit = options.ruleset.time_limit;
+    if !(time_limit.is_finite() && time_limit * UPDATES_PER_SECOND as f64 >= 1.0) {
+        return Err(format!(
+            "--time-limit must be a number of seconds of at least 1/{UPDATES_PER_SECOND}"
+        ));
+    }
+    if options.games == 0 {
+        return Err(String::from("--games must be at least 1"));
+    }
+    if options.seed.checked_add(options.games).is_none() {
+        return Err(String::from(
+            "--seed is too large, the seeds of the games go past the last 64-bit integer",
+        ));
+    }
+    Ok(options)
+}
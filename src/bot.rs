@@ -0,0 +1,145 @@
+//! Defines the bots, which play through the same moves as a player on the keyboard.
+//!
+//! A [Bot] looks at the [PlayerScreen] and returns a [Decision], which is then applied to the
+//! [LocalPlayer](crate::app::LocalPlayer) with [hold()](crate::app::LocalPlayer::hold()) and
+//! [apply_move()](crate::app::LocalPlayer::apply_move()).
+use crate::app::{Bitboard, Move, Placement, PlayerScreen};
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_pcg::Pcg32;
+
+/// Names of the bots that can be built with [new_bot()].
+pub const BOT_NAMES: [&str; 2] = ["heuristic", "random"];
+
+/// What a bot chose to do with the active tetromino.
+pub struct Decision {
+    /// Whether to hold the active tetromino before moving.
+    pub hold: bool,
+    /// Moves to apply after the eventual hold, always ending with a [Move::HardDrop].
+    pub moves: Vec<Move>,
+}
+
+pub trait Bot {
+    /// Returns the decision for the active tetromino, or None if it can't be locked anywhere.
+    fn decide(&mut self, player_screen: &PlayerScreen) -> Option<Decision>;
+}
+
+/// Returns the bot of the given name among [BOT_NAMES], using the seed for its eventual random choices.
+pub fn new_bot(name: &str, seed: u64) -> Option<Box<dyn Bot>> {
+    match name {
+        "heuristic" => Some(Box::new(HeuristicBot::default())),
+        "random" => Some(Box::new(RandomBot::new(seed))),
+        _ => None,
+    }
+}
+
+/// Bot locking the tetromino where the grid is the lowest and the flattest, using the hold if it's better.
+///
+/// The weights of the criteria are the ones found by Yiyuan Lee with a genetic algorithm.
+pub struct HeuristicBot {
+    pub height_weight: f64,
+    pub lines_weight: f64,
+    pub holes_weight: f64,
+    pub bumpiness_weight: f64,
+}
+
+impl Default for HeuristicBot {
+    fn default() -> Self {
+        HeuristicBot {
+            height_weight: -0.510066,
+            lines_weight: 0.760666,
+            holes_weight: -0.35663,
+            bumpiness_weight: -0.184483,
+        }
+    }
+}
+
+impl HeuristicBot {
+    /// Returns the score of the grid once the placement is locked, the higher the better.
+    fn evaluate(&self, bitboard: &Bitboard, placement: &Placement) -> f64 {
+        let cells = placement.tetromino.cells();
+        // locked above the visible grid, it's game over !
+        if cells.iter().all(|&(_, y)| y <= 1) {
+            return f64::NEG_INFINITY;
+        }
+        let mut bitboard = bitboard.clone();
+        for (x, y) in cells {
+            bitboard.set(x as usize, y as usize, true);
+        }
+        let mut lines = 0;
+        for y in 0..bitboard.nb_rows() as usize {
+            if bitboard.is_row_full(y) {
+                bitboard.remove_row(y);
+                lines += 1;
+            }
+        }
+
+        let heights: Vec<u32> = (0..bitboard.nb_columns())
+            .map(|x| column_height(&bitboard, x))
+            .collect();
+        let aggregate_height: u32 = heights.iter().sum();
+        let bumpiness: u32 = heights.windows(2).map(|h| h[0].abs_diff(h[1])).sum();
+        let filled: u32 = (0..bitboard.nb_rows() as usize)
+            .map(|y| bitboard.row(y).count_ones())
+            .sum();
+        // every empty cell below the top of its column is a hole
+        let holes = aggregate_height - filled;
+
+        self.height_weight * aggregate_height as f64
+            + self.lines_weight * lines as f64
+            + self.holes_weight * holes as f64
+            + self.bumpiness_weight * bumpiness as f64
+    }
+}
+
+impl Bot for HeuristicBot {
+    fn decide(&mut self, player_screen: &PlayerScreen) -> Option<Decision> {
+        let grid = &player_screen.grid;
+        let mut best: Option<(f64, Decision)> = None;
+        for (hold, tetromino) in player_screen.playable_tetrominos() {
+            for placement in grid.reachable_placements(&tetromino) {
+                let score = self.evaluate(grid.bitboard(), &placement);
+                let is_better = match &best {
+                    Some((best_score, _)) => score > *best_score,
+                    None => true,
+                };
+                if is_better {
+                    let moves = placement.moves;
+                    best = Some((score, Decision { hold, moves }));
+                }
+            }
+        }
+        best.map(|(_, decision)| decision)
+    }
+}
+
+/// Bot locking the active tetromino at a random reachable position.
+pub struct RandomBot {
+    rng: Pcg32,
+}
+
+impl RandomBot {
+    pub fn new(seed: u64) -> Self {
+        RandomBot {
+            rng: Pcg32::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Bot for RandomBot {
+    fn decide(&mut self, player_screen: &PlayerScreen) -> Option<Decision> {
+        let placements = player_screen
+            .grid
+            .reachable_placements(&player_screen.active_tetromino);
+        placements.choose(&mut self.rng).map(|placement| Decision {
+            hold: false,
+            moves: placement.moves.clone(),
+        })
+    }
+}
+
+/// Returns the number of rows between the bottom of the grid and the highest filled cell of the column.
+fn column_height(bitboard: &Bitboard, x: u32) -> u32 {
+    (0..bitboard.nb_rows())
+        .find(|&y| bitboard.row(y as usize) & (1 << x) != 0)
+        .map_or(0, |y| bitboard.nb_rows() - y)
+}
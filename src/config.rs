@@ -0,0 +1,423 @@
+//! Reads the [Config] of the game, the values of [settings](crate::settings) that can change without recompiling.
+//!
+//! The config is a TOML file, `config.toml` in the directory given by [config_dir()], read once at launch.
+//! Every key is optional and falls back on its default value, apart from the keys of a keybindings table which are given together.
+//! An unknown key or a wrong value is an error naming the key, and `--write-default-config` writes a file with every key.
+//! The keybindings mix keys and controller inputs, written as described in the [input](crate::input) module.
+use crate::{
+    input::Input,
+    settings::{default_pause_keys, default_restart_keys, Keybindings},
+};
+use opengl_graphics::OpenGL;
+use piston::{HatState, Key};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// Name of the config file in the config directory.
+static CONFIG_FILE: &str = "config.toml";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Versions of OpenGL the window can be created with, and their names in the config.
+static OPENGL_VERSIONS: [(&str, OpenGL); 12] = [
+    ("2.0", OpenGL::V2_0),
+    ("2.1", OpenGL::V2_1),
+    ("3.0", OpenGL::V3_0),
+    ("3.1", OpenGL::V3_1),
+    ("3.2", OpenGL::V3_2),
+    ("3.3", OpenGL::V3_3),
+    ("4.0", OpenGL::V4_0),
+    ("4.1", OpenGL::V4_1),
+    ("4.2", OpenGL::V4_2),
+    ("4.3", OpenGL::V4_3),
+    ("4.4", OpenGL::V4_4),
+    ("4.5", OpenGL::V4_5),
+];
+
+/// Colors of the screen, components between 0 and 1 in the order red, green, blue and alpha.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Colors {
+    pub background: graphics::types::Color,
+    pub grid_background: graphics::types::Color,
+    pub grid: graphics::types::Color,
+    pub text: graphics::types::Color,
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Colors {
+            background: [0.0, 0.3, 0.1, 1.0],
+            grid_background: [0.3, 0.3, 0.3, 1.0],
+            grid: [0.8, 0.8, 0.8, 1.0],
+            text: [0.8, 0.8, 0.8, 1.0],
+        }
+    }
+}
+
+/// Default keybindings of the single-player game and of the two sides of the two-player game.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DefaultKeybindings {
+    pub one_player: Keybindings,
+    pub left_player: Keybindings,
+    pub right_player: Keybindings,
+}
+
+/// Returns the keybindings with the keys, in the order fall, hard drop, right, left, rotations and hold,
+/// and the same controller inputs, restart and pause keys for every seat.
+fn with_controller(keys: [Key; 7]) -> Keybindings {
+    let [fall, hard_drop, right, left, rotate_clockwise, rotate_counterclockwise, hold_tetromino] =
+        keys.map(Input::Key);
+    Keybindings {
+        fall_keys: vec![
+            fall,
+            Input::Hat {
+                hat: HatState::Down,
+            },
+            Input::Axis {
+                axis: 1,
+                positive: true,
+            },
+        ],
+        hard_drop_keys: vec![hard_drop, Input::Hat { hat: HatState::Up }],
+        right_keys: vec![
+            right,
+            Input::Hat {
+                hat: HatState::Right,
+            },
+            Input::Axis {
+                axis: 0,
+                positive: true,
+            },
+        ],
+        left_keys: vec![
+            left,
+            Input::Hat {
+                hat: HatState::Left,
+            },
+            Input::Axis {
+                axis: 0,
+                positive: false,
+            },
+        ],
+        rotate_clockwise_keys: vec![rotate_clockwise, Input::Button { button: 0 }],
+        rotate_counterclockwise_keys: vec![rotate_counterclockwise, Input::Button { button: 1 }],
+        hold_tetromino_keys: vec![hold_tetromino, Input::Button { button: 4 }],
+        restart_keys: default_restart_keys(),
+        pause_keys: default_pause_keys(),
+    }
+}
+
+impl Default for DefaultKeybindings {
+    fn default() -> Self {
+        DefaultKeybindings {
+            one_player: with_controller([
+                Key::Down,
+                Key::Space,
+                Key::Right,
+                Key::Left,
+                Key::Up,
+                Key::NumPad0,
+                Key::C,
+            ]),
+            left_player: with_controller([
+                Key::S,
+                Key::LCtrl,
+                Key::D,
+                Key::A,
+                Key::W,
+                Key::E,
+                Key::C,
+            ]),
+            right_player: with_controller([
+                Key::NumPad5,
+                Key::NumPad0,
+                Key::NumPad6,
+                Key::NumPad4,
+                Key::NumPad8,
+                Key::NumPad9,
+                Key::NumPadPlus,
+            ]),
+        }
+    }
+}
+
+/// Values of the settings read from the config file.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Size of the window relative to the default one, everything on the screen being scaled with it.
+    pub window_scale: f64,
+    /// Version of OpenGL of the window, "2.1" if the default one doesn't work.
+    pub opengl_version: String,
+    /// Port on which the host of a room listens.
+    pub host_port: u16,
+    /// Port on which the guest of a room listens.
+    pub guest_port: u16,
+    /// Number of updates before a held key repeats, with the modern handling.
+    pub key_repeat_delay: u64,
+    /// Number of updates between two repeats of a held key, with the modern handling.
+    pub key_repeat_rate: u64,
+    /// Part of the way an analog stick can be pushed without pressing its direction, between 0 and 1.
+    pub controller_deadzone: f64,
+    pub colors: Colors,
+    pub keybindings: DefaultKeybindings,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            window_scale: 1.0,
+            opengl_version: String::from("4.5"),
+            host_port: 26000,
+            guest_port: 26005,
+            key_repeat_delay: 20,
+            key_repeat_rate: 5,
+            controller_deadzone: 0.5,
+            colors: Colors::default(),
+            keybindings: DefaultKeybindings::default(),
+        }
+    }
+}
+
+/// Why the config file can't be used.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    /// The file isn't valid TOML, has an unknown key or a value of the wrong type, the message pointing at the key.
+    Parse(PathBuf, toml::de::Error),
+    /// The value of the key is of the right type but can't be used.
+    Invalid {
+        path: PathBuf,
+        key: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, error) => write!(f, "couldn't read {}: {error}", path.display()),
+            ConfigError::Parse(path, error) => write!(f, "error in {}: {error}", path.display()),
+            ConfigError::Invalid { path, key, reason } => {
+                write!(f, "error in {}: `{key}` {reason}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Returns the OpenGL version of the window, known to be valid once the config is loaded.
+    pub fn opengl(&self) -> OpenGL {
+        OPENGL_VERSIONS
+            .iter()
+            .find(|(name, _)| *name == self.opengl_version)
+            .map_or(OpenGL::V4_5, |&(_, version)| version)
+    }
+
+    /// Returns the first key whose value can't be used, with the reason.
+    fn invalid_key(&self) -> Option<(String, String)> {
+        if !(self.window_scale > 0.0 && self.window_scale <= 4.0) {
+            return Some((
+                String::from("window_scale"),
+                String::from("must be greater than 0 and at most 4"),
+            ));
+        }
+        if !OPENGL_VERSIONS
+            .iter()
+            .any(|(name, _)| *name == self.opengl_version)
+        {
+            let names: Vec<&str> = OPENGL_VERSIONS.iter().map(|(name, _)| *name).collect();
+            return Some((
+                String::from("opengl_version"),
+                format!("must be one of {}", names.join(", ")),
+            ));
+        }
+        if self.host_port == self.guest_port {
+            return Some((
+                String::from("guest_port"),
+                String::from("must be different from host_port"),
+            ));
+        }
+        if self.key_repeat_rate == 0 {
+            return Some((
+                String::from("key_repeat_rate"),
+                String::from("must be at least 1"),
+            ));
+        }
+        if !(self.controller_deadzone >= 0.0 && self.controller_deadzone < 1.0) {
+            return Some((
+                String::from("controller_deadzone"),
+                String::from("must be at least 0 and less than 1"),
+            ));
+        }
+        let colors = [
+            ("background", self.colors.background),
+            ("grid_background", self.colors.grid_background),
+            ("grid", self.colors.grid),
+            ("text", self.colors.text),
+        ];
+        for (name, color) in colors {
+            if color
+                .iter()
+                .any(|component| !(0.0..=1.0).contains(component))
+            {
+                return Some((
+                    format!("colors.{name}"),
+                    String::from("must have components between 0 and 1"),
+                ));
+            }
+        }
+        None
+    }
+
+    /// Reads the config file at the path, or the default config if there's no file there.
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(error) => return Err(ConfigError::Io(path.to_path_buf(), error)),
+        };
+        let config: Config = toml::from_str(&contents)
+            .map_err(|error| ConfigError::Parse(path.to_path_buf(), error))?;
+        match config.invalid_key() {
+            Some((key, reason)) => Err(ConfigError::Invalid {
+                path: path.to_path_buf(),
+                key,
+                reason,
+            }),
+            None => Ok(config),
+        }
+    }
+}
+
+/// Returns the directory of the config file, or None if the home of the user can't be found.
+///
+/// It's `$TETRIS_CONFIG_DIR` if set, otherwise `tetris` in `$XDG_CONFIG_HOME`, `%APPDATA%` or `~/.config`.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("TETRIS_CONFIG_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| env::var_os("APPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("tetris"))
+}
+
+/// Returns the path of the config file in the config directory.
+pub fn default_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(CONFIG_FILE))
+}
+
+/// Writes the default config at the path, with every key, unless there's already a file there.
+pub fn write_default(path: &Path) -> io::Result<()> {
+    if path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        ));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let contents = toml::to_string_pretty(&Config::default())
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    fs::write(path, contents)
+}
+
+/// Makes the config used by the game, which must happen before anything reads it.
+pub fn init(config: Config) {
+    if CONFIG.set(config).is_err() {
+        println!("the config was already read, the new one is ignored");
+    }
+}
+
+/// Returns the config used by the game, the default one if none was given to [init()].
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the key named by the error of the config, if it has one.
+    fn invalid(contents: &str) -> Option<String> {
+        let config: Config = toml::from_str(contents).unwrap();
+        config.invalid_key().map(|(key, _)| key)
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        let contents = toml::to_string_pretty(&Config::default()).unwrap();
+        assert_eq!(invalid(&contents), None);
+    }
+
+    #[test]
+    fn unknown_key_is_named() {
+        let error = toml::from_str::<Config>("window_size = 2.0").err().unwrap();
+        assert!(error.to_string().contains("window_size"), "{error}");
+        let error = toml::from_str::<Config>("[colors]\nforeground = [1.0, 1.0, 1.0, 1.0]")
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("foreground"), "{error}");
+    }
+
+    #[test]
+    fn unknown_command_is_named() {
+        let mut contents = String::from("[keybindings.one_player]\n");
+        for command in [
+            "fall_keys",
+            "hard_drop_keys",
+            "right_keys",
+            "left_keys",
+            "rotate_clockwise_keys",
+            "rotate_counterclockwise_keys",
+            "hold_keys",
+        ] {
+            contents += &format!("{command} = [\"A\"]\n");
+        }
+        let error = toml::from_str::<Config>(&contents).err().unwrap();
+        assert!(error.to_string().contains("hold_keys"), "{error}");
+    }
+
+    #[test]
+    fn unknown_opengl_version_is_named() {
+        assert_eq!(
+            invalid("opengl_version = \"5.0\""),
+            Some(String::from("opengl_version"))
+        );
+    }
+
+    #[test]
+    fn same_ports_are_named() {
+        assert_eq!(
+            invalid("host_port = 27000\nguest_port = 27000"),
+            Some(String::from("guest_port"))
+        );
+    }
+
+    #[test]
+    fn null_key_repeat_rate_is_named() {
+        assert_eq!(
+            invalid("key_repeat_rate = 0"),
+            Some(String::from("key_repeat_rate"))
+        );
+    }
+
+    #[test]
+    fn color_out_of_range_is_named() {
+        assert_eq!(
+            invalid("[colors]\ngrid = [0.5, 1.5, 0.5, 1.0]"),
+            Some(String::from("colors.grid"))
+        );
+    }
+}
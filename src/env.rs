@@ -0,0 +1,316 @@
+//! Defines [TetrisEnv], a reinforcement-learning environment in the style of OpenAI Gym.
+//!
+//! The environment plays a single-player game with the same [LocalPlayer] as the window,
+//! so the gravity, the locking and the scoring follow the same rules.
+//! Each step lasts one update of the game, that is 1/[UPDATES_PER_SECOND] second.
+use crate::{
+    app::{lines_of_garbage, LocalPlayer, Move, Placement, Speed, TSpin, TetrominoKind},
+    settings::{gravity, Keybindings, UPDATES_PER_SECOND},
+    PlayerConfig,
+};
+use serde::{Deserialize, Serialize};
+
+/// How the agent acts on the game.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionSpace {
+    /// A key action every update, the tetromino falls with the gravity.
+    Key,
+    /// The index of one of the placements given in the observation, locked at once.
+    Placement,
+}
+
+/// Action pressed for one update, like a key press.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+    Nothing,
+    Left,
+    Right,
+    RotateClockwise,
+    RotateCounterclockwise,
+    /// Moves the tetromino one cell down.
+    SoftDrop,
+    HardDrop,
+    Hold,
+}
+
+/// Action of the agent, it must belong to the [ActionSpace] of the environment.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Action {
+    Key(KeyAction),
+    Placement(usize),
+}
+
+/// What the agent sees of the game.
+#[derive(Serialize)]
+pub struct Observation {
+    /// Rows of the grid from the top, including the 2 hidden ones. A cell is 1 if filled and 0 if empty.
+    pub board: Vec<Vec<u8>>,
+    pub active: PieceObservation,
+    pub hold: Option<TetrominoKind>,
+    /// Next tetrominos, the first one comes next.
+    pub queue: Vec<TetrominoKind>,
+    /// Positions where a tetromino can be locked, only given in the [ActionSpace::Placement].
+    pub placements: Vec<PlacementObservation>,
+}
+
+#[derive(Serialize)]
+pub struct PieceObservation {
+    pub kind: TetrominoKind,
+    /// Coordinates (x, y) of the 4 cells of the tetromino, y increases towards the bottom.
+    pub cells: [(i8, i8); 4],
+}
+
+#[derive(Serialize)]
+pub struct PlacementObservation {
+    /// Whether the tetromino is held before being placed.
+    pub hold: bool,
+    pub piece: PieceObservation,
+    pub t_spin: TSpin,
+}
+
+/// Additional informations returned with each step.
+#[derive(Serialize)]
+pub struct Info {
+    /// Number of lines cleared since the reset.
+    pub lines: u64,
+    /// Lines of garbage that would have been sent to an adversary since the reset.
+    pub attack: u64,
+    /// Number of updates since the reset.
+    pub frame: u64,
+}
+
+pub struct TetrisEnv {
+    action_space: ActionSpace,
+    player: LocalPlayer,
+    keybindings: Keybindings,
+    frame_counter: u64,
+    attack: u64,
+    /// Placements of the last observation, along with whether they need a hold.
+    placements: Vec<(bool, Placement)>,
+}
+
+impl TetrisEnv {
+    pub fn new(action_space: ActionSpace) -> Self {
+        let mut env = TetrisEnv {
+            action_space,
+            player: LocalPlayer::new(&PlayerConfig::Local),
+            keybindings: Keybindings::default(),
+            frame_counter: 0,
+            attack: 0,
+            placements: vec![],
+        };
+        env.reset(0);
+        env
+    }
+
+    pub fn action_space(&self) -> ActionSpace {
+        self.action_space
+    }
+
+    /// Starts a new game whose tetrominos are given by the seed and returns the first observation.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.player.renew(seed);
+        self.player.start();
+        self.frame_counter = 0;
+        self.attack = 0;
+        self.observe()
+    }
+
+    /// Applies the action then updates the game once.
+    ///
+    /// Returns the new observation, the reward (the number of lines cleared during the step),
+    /// whether the game is over and additional informations.
+    /// Returns an error if the action doesn't belong to the action space or if the game is over.
+    pub fn step(&mut self, action: Action) -> Result<(Observation, f64, bool, Info), String> {
+        if self.player.get_game_over() {
+            return Err(String::from("the game is over, it must be reset"));
+        }
+        let score = self.player.player_screen().score;
+        match (self.action_space, action) {
+            (ActionSpace::Key, Action::Key(key_action)) => match key_action {
+                KeyAction::Nothing => {}
+                KeyAction::Left => self.player.apply_move(Move::Left),
+                KeyAction::Right => self.player.apply_move(Move::Right),
+                KeyAction::RotateClockwise => self.player.apply_move(Move::RotateClockwise),
+                KeyAction::RotateCounterclockwise => {
+                    self.player.apply_move(Move::RotateCounterclockwise)
+                }
+                KeyAction::SoftDrop => self.player.fall(),
+                KeyAction::HardDrop => self.player.apply_move(Move::HardDrop),
+                KeyAction::Hold => self.player.hold(),
+            },
+            (ActionSpace::Placement, Action::Placement(index)) => {
+                let Some((hold, placement)) = self.placements.get(index) else {
+                    return Err(format!(
+                        "placement {index} doesn't exist, there are {} placements",
+                        self.placements.len()
+                    ));
+                };
+                if *hold {
+                    self.player.hold();
+                }
+                for movement in placement.moves.clone() {
+                    self.player.apply_move(movement);
+                }
+            }
+            (action_space, action) => {
+                return Err(format!(
+                    "{action:?} doesn't belong to the {action_space:?} action space"
+                ))
+            }
+        }
+
+        self.frame_counter += 1;
+        let clock = self.frame_counter as f64 / UPDATES_PER_SECOND as f64;
+        let (fall_speed_divide, freeze) = gravity(clock);
+        self.player.update(
+            &self.keybindings,
+            self.frame_counter,
+            Speed::new(fall_speed_divide, freeze),
+        );
+        self.attack += lines_of_garbage(self.player.get_lines_completed());
+
+        let lines = self.player.player_screen().score;
+        let info = Info {
+            lines,
+            attack: self.attack,
+            frame: self.frame_counter,
+        };
+        let reward = (lines - score) as f64;
+        Ok((self.observe(), reward, self.player.get_game_over(), info))
+    }
+
+    /// Returns the observation of the current state of the game.
+    fn observe(&mut self) -> Observation {
+        let player_screen = self.player.player_screen();
+        let grid = &player_screen.grid;
+        let bitboard = grid.bitboard();
+        let board = (0..bitboard.nb_rows() as usize)
+            .map(|y| {
+                (0..bitboard.nb_columns())
+                    .map(|x| (bitboard.row(y) >> x & 1) as u8)
+                    .collect()
+            })
+            .collect();
+
+        self.placements.clear();
+        if self.action_space == ActionSpace::Placement && !player_screen.game_over {
+            for (hold, tetromino) in player_screen.playable_tetrominos() {
+                for placement in grid.reachable_placements(&tetromino) {
+                    self.placements.push((hold, placement));
+                }
+            }
+        }
+
+        let active = player_screen.active_tetromino;
+        Observation {
+            board,
+            active: PieceObservation {
+                kind: active.kind(),
+                cells: active.cells(),
+            },
+            hold: player_screen.saved_tetromino.map(|held| held.kind()),
+            queue: (0..)
+                .map_while(|i| player_screen.next_tetromino(i))
+                .map(|tetromino| tetromino.kind())
+                .collect(),
+            placements: self
+                .placements
+                .iter()
+                .map(|(hold, placement)| PlacementObservation {
+                    hold: *hold,
+                    piece: PieceObservation {
+                        kind: placement.tetromino.kind(),
+                        cells: placement.tetromino.cells(),
+                    },
+                    t_spin: placement.t_spin,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the observation as JSON, to compare two of them.
+    fn json(observation: &Observation) -> String {
+        serde_json::to_string(observation).unwrap()
+    }
+
+    fn filled_cells(observation: &Observation) -> usize {
+        observation
+            .board
+            .iter()
+            .flatten()
+            .filter(|&&cell| cell == 1)
+            .count()
+    }
+
+    #[test]
+    fn same_seed_same_observations() {
+        let mut first = TetrisEnv::new(ActionSpace::Key);
+        let mut second = TetrisEnv::new(ActionSpace::Key);
+        assert_eq!(json(&first.reset(42)), json(&second.reset(42)));
+        let actions = [
+            KeyAction::Left,
+            KeyAction::RotateClockwise,
+            KeyAction::HardDrop,
+            KeyAction::Hold,
+            KeyAction::Right,
+            KeyAction::SoftDrop,
+            KeyAction::HardDrop,
+        ];
+        for action in actions {
+            let (first_observation, ..) = first.step(Action::Key(action)).unwrap();
+            let (second_observation, ..) = second.step(Action::Key(action)).unwrap();
+            assert_eq!(json(&first_observation), json(&second_observation));
+        }
+    }
+
+    #[test]
+    fn invalid_action_leaves_the_game_unchanged() {
+        let mut env = TetrisEnv::new(ActionSpace::Placement);
+        let before = json(&env.reset(7));
+        let nb_placements = env.placements.len();
+        assert!(env.step(Action::Key(KeyAction::HardDrop)).is_err());
+        assert!(env.step(Action::Placement(nb_placements)).is_err());
+        assert_eq!(env.frame_counter, 0);
+        assert_eq!(json(&env.observe()), before);
+    }
+
+    #[test]
+    fn step_after_game_over_errors() {
+        let mut env = TetrisEnv::new(ActionSpace::Key);
+        env.reset(3);
+        let mut done = false;
+        for _ in 0..1000 {
+            done = env.step(Action::Key(KeyAction::HardDrop)).unwrap().2;
+            if done {
+                break;
+            }
+        }
+        assert!(done, "hard drops in the middle top out the game");
+        assert!(env.step(Action::Key(KeyAction::Nothing)).is_err());
+    }
+
+    #[test]
+    fn placement_locks_one_piece() {
+        let mut env = TetrisEnv::new(ActionSpace::Placement);
+        let observation = env.reset(11);
+        let next = observation.queue[0];
+        let index = observation
+            .placements
+            .iter()
+            .position(|placement| !placement.hold)
+            .unwrap();
+        let (observation, reward, done, info) = env.step(Action::Placement(index)).unwrap();
+        assert_eq!(filled_cells(&observation), 4);
+        assert!(observation.active.kind == next);
+        assert_eq!((reward, done, info.frame), (0.0, false, 1));
+    }
+}
@@ -0,0 +1,77 @@
+//! Defines the [Input]s that can be bound to the commands of a player: keys of the keyboard and parts of controllers.
+//!
+//! In the keybindings, a key is written with its name, like `"Left"`, a controller button with its index, like `{ button = 0 }`,
+//! a direction of the D-pad like `{ hat = "Left" }` and a direction of an analog stick like `{ axis = 0, positive = false }`.
+use piston::{HatState, Key};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Input bound to a command, the same one for all the controllers.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(untagged)]
+pub enum Input {
+    Key(Key),
+    /// Button of a controller, with the index given by the controller.
+    Button {
+        button: u8,
+    },
+    /// Direction of the D-pad of a controller, Up, Down, Left or Right, a diagonal pressing two of them.
+    Hat {
+        hat: HatState,
+    },
+    /// Analog stick of a controller pushed past the deadzone, towards the positive or the negative side of the axis.
+    Axis {
+        axis: u8,
+        positive: bool,
+    },
+}
+
+impl Input {
+    /// Returns the directions of the D-pad pressed in the state.
+    pub fn hat_directions(state: HatState) -> Vec<Input> {
+        let directions: &[HatState] = match state {
+            HatState::Centered => &[],
+            HatState::RightUp => &[HatState::Right, HatState::Up],
+            HatState::RightDown => &[HatState::Right, HatState::Down],
+            HatState::LeftUp => &[HatState::Left, HatState::Up],
+            HatState::LeftDown => &[HatState::Left, HatState::Down],
+            HatState::Up => &[HatState::Up],
+            HatState::Down => &[HatState::Down],
+            HatState::Left => &[HatState::Left],
+            HatState::Right => &[HatState::Right],
+        };
+        directions.iter().map(|&hat| Input::Hat { hat }).collect()
+    }
+
+    /// Returns the direction of the axis at the position, None inside the deadzone.
+    pub fn axis_direction(axis: u8, position: f64, deadzone: f64) -> Option<Input> {
+        if position.abs() <= deadzone {
+            None
+        } else {
+            Some(Input::Axis {
+                axis,
+                positive: position > 0.0,
+            })
+        }
+    }
+}
+
+impl From<Key> for Input {
+    fn from(key: Key) -> Self {
+        Input::Key(key)
+    }
+}
+
+/// Writes the input without spaces, the key inputs of the settings splitting the inputs on them.
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Input::Key(key) => write!(f, "{key:?}"),
+            Input::Button { button } => write!(f, "Button{button}"),
+            Input::Hat { hat } => write!(f, "Pad{hat:?}"),
+            Input::Axis { axis, positive } => {
+                write!(f, "Axis{axis}{}", if *positive { '+' } else { '-' })
+            }
+        }
+    }
+}
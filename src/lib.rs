@@ -0,0 +1,49 @@
+//! Tetris game following the *Super Rotation System*, playable alone, with two players on the same computer or remotely.
+//!
+//! The game engine ([TetrisGrid](app::TetrisGrid), [Tetromino](app::Tetromino) and the move generator) is exposed
+//! so that other tools like bots can build on it.
+extern crate clipboard;
+extern crate find_folder;
+extern crate graphics;
+extern crate opengl_graphics;
+extern crate piston;
+
+pub mod app;
+pub mod assets;
+pub mod bot;
+pub mod config;
+pub mod env;
+pub mod input;
+pub mod settings;
+pub mod ui;
+pub mod utils;
+
+#[derive(PartialEq, Debug)]
+pub enum PlayerConfig {
+    Local,
+    TwoLocal,
+    TwoRemote { local_ip: String, remote_ip: String },
+    Viewer(String),
+}
+
+impl PlayerConfig {
+    pub fn is_remote(&self) -> bool {
+        matches!(
+            self,
+            PlayerConfig::TwoRemote {
+                local_ip: _,
+                remote_ip: _,
+            } | PlayerConfig::Viewer(_)
+        )
+    }
+
+    pub fn is_multiplayer(&self) -> bool {
+        matches!(
+            self,
+            PlayerConfig::TwoRemote {
+                local_ip: _,
+                remote_ip: _,
+            } | PlayerConfig::TwoLocal
+        )
+    }
+}
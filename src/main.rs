@@ -1,78 +1,162 @@
-extern crate clipboard;
-extern crate find_folder;
-extern crate graphics;
-extern crate opengl_graphics;
-extern crate piston;
-
-use crate::{
-    app::App,
-    assets::Assets,
-    settings::{DEFAULT_WINDOW_HEIGHT, DEFAULT_WINDOW_WIDTH, OPENGL_VERSION},
-};
 use glfw_window::GlfwWindow;
 use piston::{
     event_loop::{EventSettings, Events},
     input::{RenderEvent, UpdateEvent},
     window::WindowSettings,
-    AdvancedWindow, {Button, MouseCursorEvent, PressEvent, ReleaseEvent, TextEvent},
+    AdvancedWindow,
+    {Button, ControllerAxisEvent, MouseCursorEvent, PressEvent, ReleaseEvent, TextEvent},
 };
 use piston_window::PistonWindow;
+use std::{env, iter::Peekable, path::PathBuf, process};
+use tetris::{
+    app::{App, GameMode, Launch},
+    config::{self, Config},
+    once,
+    settings::{DEFAULT_WINDOW_HEIGHT, DEFAULT_WINDOW_WIDTH, OPENGL_VERSION, WINDOW_SCALE},
+};
 
-mod app;
-mod assets;
-mod settings;
-mod ui;
-mod utils;
-
-#[derive(PartialEq, Debug)]
-pub enum PlayerConfig {
-    Local,
-    TwoLocal,
-    TwoRemote { local_ip: String, remote_ip: String },
-    Viewer(String),
+const USAGE: &str = "\
+Usage: tetris [OPTIONS]
+
+Options:
+  --mode <MODE>            start a single-player game of the mode: endless, sprint, ultra,
+                           marathon, dig, master, zen, classic, puzzle, opener or practice
+  --two-local              start a two-player game on this keyboard
+  --host [ADDR:PORT]       create a room, listening on the address if given
+  --join <ADDR:PORT>       join the room listening on the address
+  --seed <SEED>            deal the tetrominos of every game from this seed
+  --config <PATH>          read the config from this file instead of the config directory
+  --write-default-config   write the default config, to the --config path if given, and quit
+  -h, --help               print this help";
+
+/// Options of the command line.
+struct Options {
+    launch: Launch,
+    seed: Option<u64>,
+    config: Option<PathBuf>,
+    write_default_config: bool,
 }
 
-impl PlayerConfig {
-    pub fn is_remote(&self) -> bool {
-        matches!(
-            self,
-            PlayerConfig::TwoRemote {
-                local_ip: _,
-                remote_ip: _,
-            } | PlayerConfig::Viewer(_)
-        )
+fn parse_args(mut args: Peekable<impl Iterator<Item = String>>) -> Result<Options, String> {
+    let mut options = Options {
+        launch: Launch::MainMenu,
+        seed: None,
+        config: None,
+        write_default_config: false,
+    };
+    while let Some(arg) = args.next() {
+        let launch = match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                process::exit(0);
+            }
+            "--two-local" => Launch::TwoLocal,
+            "--write-default-config" => {
+                options.write_default_config = true;
+                continue;
+            }
+            // the address of a hosted room is optional
+            "--host" => Launch::Host(args.next_if(|value| !value.starts_with('-'))),
+            _ => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {arg}"))?;
+                match arg.as_str() {
+                    "--mode" => Launch::Local(GameMode::from_name(&value).ok_or_else(|| {
+                        format!(
+                            "unknown mode {value}, expected one of {}",
+                            GameMode::NAMES.join(", ")
+                        )
+                    })?),
+                    "--join" => Launch::Join(value),
+                    "--seed" => {
+                        options.seed = Some(
+                            value
+                                .parse()
+                                .map_err(|_| format!("invalid value for {arg}: {value}"))?,
+                        );
+                        continue;
+                    }
+                    "--config" => {
+                        options.config = Some(PathBuf::from(value));
+                        continue;
+                    }
+                    _ => return Err(format!("unknown option {arg}")),
+                }
+            }
+        };
+        if options.launch != Launch::MainMenu {
+            return Err(String::from(
+                "only one of --mode, --two-local, --host and --join can be given",
+            ));
+        }
+        options.launch = launch;
     }
+    Ok(options)
+}
 
-    pub fn is_multiplayer(&self) -> bool {
-        matches!(
-            self,
-            PlayerConfig::TwoRemote {
-                local_ip: _,
-                remote_ip: _,
-            } | PlayerConfig::TwoLocal
-        )
-    }
+/// Returns the size of the window showing the given number of players side by side.
+fn window_size(nb_players: u32) -> [u32; 2] {
+    [
+        (f64::from(DEFAULT_WINDOW_WIDTH * nb_players) * *WINDOW_SCALE) as u32,
+        (f64::from(DEFAULT_WINDOW_HEIGHT) * *WINDOW_SCALE) as u32,
+    ]
 }
 
 fn main() {
+    let options = parse_args(env::args().skip(1).peekable()).unwrap_or_else(|error| {
+        eprintln!("{error}\n\n{USAGE}");
+        process::exit(2);
+    });
+    let Some(config_path) = options.config.clone().or_else(config::default_path) else {
+        eprintln!("no home directory to find the config in");
+        process::exit(1);
+    };
+    if options.write_default_config {
+        match config::write_default(&config_path) {
+            Ok(()) => println!("wrote the default config to {}", config_path.display()),
+            Err(error) => {
+                eprintln!("couldn't write the default config: {error}");
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    // a config given on the command line must exist, unlike the one of the config directory
+    if options.config.is_some() && !config_path.exists() {
+        eprintln!("no config at {}", config_path.display());
+        process::exit(1);
+    }
+    match Config::load(&config_path) {
+        Ok(config) => config::init(config),
+        Err(error) => {
+            eprintln!("{error}");
+            process::exit(1);
+        }
+    }
+
     // Create a Sdl2 window.
-    let mut window: PistonWindow<GlfwWindow> =
-        WindowSettings::new("TETRIS", [DEFAULT_WINDOW_WIDTH, DEFAULT_WINDOW_HEIGHT])
-            .graphics_api(OPENGL_VERSION)
-            .vsync(true)
-            .build()
-            .unwrap();
+    let mut window: PistonWindow<GlfwWindow> = WindowSettings::new("TETRIS", window_size(1))
+        .graphics_api(*OPENGL_VERSION)
+        .vsync(true)
+        .build()
+        .unwrap();
 
     // Create a new game and run it.
-    let mut app = App::new(OPENGL_VERSION);
-    let mut fall_speed_divide: u64 = 50;
-    let mut freeze: u64 = 50;
+    let mut app = App::new(*OPENGL_VERSION);
+    if let Some(seed) = options.seed {
+        app.set_seed(seed);
+    }
+    if let Err(error) = app.launch(options.launch) {
+        eprintln!("{error}");
+        process::exit(1);
+    }
     let mut multiplayer = false;
 
     let mut events = Events::new(EventSettings::new());
     while let Some(e) = events.next(&mut window) {
         if let Some(args) = e.update_args() {
-            app.update(&args, fall_speed_divide, freeze);
+            app.update(&args);
         }
 
         if let Some(args) = e.render_args() {
@@ -90,11 +174,11 @@ fn main() {
 
         if app.player_config.is_multiplayer() {
             if !multiplayer {
-                window.set_size([DEFAULT_WINDOW_WIDTH * 2, DEFAULT_WINDOW_HEIGHT]);
+                window.set_size(window_size(2));
                 multiplayer = true;
             }
         } else if multiplayer {
-            window.set_size([DEFAULT_WINDOW_WIDTH, DEFAULT_WINDOW_HEIGHT]);
+            window.set_size(window_size(1));
             multiplayer = false;
         }
 
@@ -106,29 +190,23 @@ fn main() {
             app.handle_key_release(key);
         }
 
-        // TODO : think about this, move this and make it less painful
-        match app.clock {
-            i if i <= 30.0 => {
-                fall_speed_divide = 50;
-                freeze = 50
-            }
-            i if i <= 60.0 => {
-                fall_speed_divide = 40;
-                freeze = 50
-            }
-            i if i <= 90.0 => {
-                fall_speed_divide = 30;
-                freeze = 50
-            }
-            i if i <= 120.0 => {
-                fall_speed_divide = 20;
-                freeze = 50
-            }
-            _ => {
-                fall_speed_divide = 15;
-                freeze = 50
-            }
+        if let Some(Button::Controller(button)) = e.press_args() {
+            app.handle_controller_press(button);
         }
+
+        if let Some(Button::Controller(button)) = e.release_args() {
+            app.handle_controller_release(button);
+        }
+
+        // the D-pad only sends its new state, the released directions being the ones it doesn't hold anymore
+        if let Some(Button::Hat(hat)) = e.press_args() {
+            app.handle_controller_hat(hat);
+        }
+
+        if let Some(args) = e.controller_axis_args() {
+            app.handle_controller_axis(args);
+        }
+
         if let Some(Button::Mouse(button)) = e.press_args() {
             app.handle_mouse_press(button);
         }
@@ -137,8 +215,10 @@ fn main() {
             app.handle_mouse_release(button);
         }
 
-        e.mouse_cursor(|cursor_position| {
-            app.cursor_position = cursor_position;
+        // the app places its widgets in a window of the default size
+        e.mouse_cursor(|[x, y]| {
+            app.cursor_position = [x / *WINDOW_SCALE, y / *WINDOW_SCALE];
         });
     }
+    app.quit();
 }
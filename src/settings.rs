@@ -1,16 +1,22 @@
-use std::{cell::RefCell, net::TcpStream};
+use std::{cell::RefCell, net::TcpStream, sync::LazyLock};
 
-use crate::{once, ui::interactive_widget_manager::TetrisCommand, PlayerConfig};
+use crate::{
+    app::Stat, config::config, input::Input, once, ui::interactive_widget_manager::TetrisCommand,
+    PlayerConfig,
+};
 use opengl_graphics::OpenGL;
 use piston::Key;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-static SCALE_FACTOR: f64 = 1.0;
-pub static HOST_PORT: &str = ":26000";
-pub static GUEST_PORT: &str = ":26005";
+// the values read from the config file are only known once the game runs, see the config module
 
-// Change this to OpenGL::V2_1 if not working.
-pub static OPENGL_VERSION: OpenGL = OpenGL::V4_5;
+// everything on the screen is scaled by WINDOW_SCALE, the positions below being the ones in a window of the default size
+pub static WINDOW_SCALE: LazyLock<f64> = LazyLock::new(|| config().window_scale);
+pub static HOST_PORT: LazyLock<String> = LazyLock::new(|| format!(":{}", config().host_port));
+pub static GUEST_PORT: LazyLock<String> = LazyLock::new(|| format!(":{}", config().guest_port));
+
+// set opengl_version to "2.1" in the config if not working.
+pub static OPENGL_VERSION: LazyLock<OpenGL> = LazyLock::new(|| config().opengl());
 
 pub static NB_COLUMNS: u32 = 10;
 pub static NB_ROWS: u32 = 22;
@@ -19,20 +25,20 @@ pub static NB_ROWS: u32 = 22;
 /* POSITIONS IN SINGLE PLAYER GAME VIEW */
 /****************************************/
 
-pub static DEFAULT_WINDOW_WIDTH: u32 = (650.0 * SCALE_FACTOR) as u32;
-pub static DEFAULT_WINDOW_HEIGHT: u32 = (800.0 * SCALE_FACTOR) as u32;
+pub static DEFAULT_WINDOW_WIDTH: u32 = 650;
+pub static DEFAULT_WINDOW_HEIGHT: u32 = 800;
 
 // coordinates of the top left corner of the grid
 // changing this moves everything in the single-player view except the title
-pub static DEFAULT_GRID_X: f64 = 200.0 * SCALE_FACTOR;
-pub static DEFAULT_GRID_Y: f64 = 160.0 * SCALE_FACTOR;
+pub static DEFAULT_GRID_X: f64 = 200.0;
+pub static DEFAULT_GRID_Y: f64 = 160.0;
 
 // height of the title "Tetris" and "Press R to (re)start"
-pub static DEFAULT_TITLE_Y: f64 = 100.0 * SCALE_FACTOR;
+pub static DEFAULT_TITLE_Y: f64 = 100.0;
 // height of the text on the left side indicating the score
 pub static DEFAULT_SCORE_TEXT_Y: f64 = DEFAULT_GRID_Y + 8.0 * BLOCK_SIZE;
 
-pub static BLOCK_SIZE: f64 = 25.0 * SCALE_FACTOR;
+pub static BLOCK_SIZE: f64 = 25.0;
 pub static TETROMINO_MAX_WIDTH: f64 = 4.0 * BLOCK_SIZE;
 pub static TETROMINO_MAX_HEIGHT: f64 = 2.0 * BLOCK_SIZE;
 pub static GRID_THICKNESS: f64 = 0.5;
@@ -42,121 +48,88 @@ pub static GRID_THICKNESS: f64 = 0.5;
 /****************************************/
 
 // size of the buttons
-pub static DEFAULT_BUTTON_WIDTH: f64 = 300.0 * SCALE_FACTOR;
-pub static DEFAULT_BUTTON_HEIGHT: f64 = 50.0 * SCALE_FACTOR;
-pub static DEFAULT_BUTTON_Y_SPACING: f64 = 100.0 * SCALE_FACTOR;
+pub static DEFAULT_BUTTON_WIDTH: f64 = 300.0;
+pub static DEFAULT_BUTTON_HEIGHT: f64 = 50.0;
+pub static DEFAULT_BUTTON_Y_SPACING: f64 = 100.0;
 // size of the text
-pub static DEFAULT_FONT_SIZE: u32 = (16.0 * SCALE_FACTOR) as u32;
+pub static DEFAULT_FONT_SIZE: u32 = 16;
 
 /****************************************/
 /*      POSITIONS IN SETTINGS VIEW      */
 /****************************************/
 
 // size of the buttons
-pub static DEFAULT_KEY_INPUT_WIDTH: f64 = 200.0 * SCALE_FACTOR;
-pub static DEFAULT_KEY_INPUT_HEIGHT: f64 = 50.0 * SCALE_FACTOR;
+pub static DEFAULT_KEY_INPUT_WIDTH: f64 = 200.0;
+pub static DEFAULT_KEY_INPUT_HEIGHT: f64 = 50.0;
 
 /****************************************/
 /*               COLORS                 */
 /****************************************/
 
-pub static BG_COLOR: graphics::types::Color = [0.0, 0.3, 0.1, 1.0];
-pub static GRID_BG_COLOR: graphics::types::Color = [0.3, 0.3, 0.3, 1.0];
-pub static GRID_COLOR: graphics::types::Color = [0.8, 0.8, 0.8, 1.0];
-pub static TEXT_COLOR: graphics::types::Color = [0.8, 0.8, 0.8, 1.0];
+pub static BG_COLOR: LazyLock<graphics::types::Color> =
+    LazyLock::new(|| config().colors.background);
+pub static GRID_BG_COLOR: LazyLock<graphics::types::Color> =
+    LazyLock::new(|| config().colors.grid_background);
+pub static GRID_COLOR: LazyLock<graphics::types::Color> = LazyLock::new(|| config().colors.grid);
+pub static TEXT_COLOR: LazyLock<graphics::types::Color> = LazyLock::new(|| config().colors.text);
+// the perfect clear hint is drawn with the texture of the tetromino, made translucent
+pub static PC_HINT_COLOR: graphics::types::Color = [1.0, 1.0, 1.0, 0.4];
+// the cells of the setup still to fill are outlined during the opener trainer
+pub static OPENER_TARGET_COLOR: graphics::types::Color = [1.0, 1.0, 1.0, 0.9];
+// the finish screen darkens the grid under the results
+pub static FINISH_BG_COLOR: graphics::types::Color = [0.0, 0.0, 0.0, 0.7];
+// backgrounds of the other themes a profile can choose, the default one being BG_COLOR
+pub static NIGHT_BG_COLOR: graphics::types::Color = [0.05, 0.05, 0.15, 1.0];
+pub static SAND_BG_COLOR: graphics::types::Color = [0.35, 0.28, 0.15, 1.0];
 
 /****************************************/
 /*          GAME KEYBINDINGS            */
 /****************************************/
 
-static FALL_KEYS_1P: [Key; 1] = [Key::Down];
-static HARD_DROP_KEYS_1P: [Key; 1] = [Key::Space];
-static RIGHT_KEYS_1P: [Key; 1] = [Key::Right];
-static LEFT_KEYS_1P: [Key; 1] = [Key::Left];
-static ROTATE_CLOCKWISE_KEYS_1P: [Key; 1] = [Key::Up];
-static ROTATE_COUNTERCLOCKWISE_KEYS_1P: [Key; 1] = [Key::NumPad0];
-static HOLD_TETROMINO_KEYS_1P: [Key; 1] = [Key::C];
-
-static FALL_KEYS_2P: [Key; 2] = [Key::S, Key::NumPad5];
-static HARD_DROP_KEYS_2P: [Key; 2] = [Key::LCtrl, Key::NumPad0];
-static RIGHT_KEYS_2P: [Key; 2] = [Key::D, Key::NumPad6];
-static LEFT_KEYS_2P: [Key; 2] = [Key::A, Key::NumPad4];
-static ROTATE_CLOCKWISE_KEYS_2P: [Key; 2] = [Key::W, Key::NumPad8];
-static ROTATE_COUNTERCLOCKWISE_KEYS_2P: [Key; 2] = [Key::E, Key::NumPad9];
-static HOLD_TETROMINO_KEYS_2P: [Key; 2] = [Key::C, Key::NumPadPlus];
+// the default keybindings are the ones of the config
 
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct Keybindings {
-    pub fall_keys: Vec<Key>,
-    pub hard_drop_keys: Vec<Key>,
-    pub right_keys: Vec<Key>,
-    pub left_keys: Vec<Key>,
-    pub rotate_clockwise_keys: Vec<Key>,
-    pub rotate_counterclockwise_keys: Vec<Key>,
-    pub hold_tetromino_keys: Vec<Key>,
+    pub fall_keys: Vec<Input>,
+    pub hard_drop_keys: Vec<Input>,
+    pub right_keys: Vec<Input>,
+    pub left_keys: Vec<Input>,
+    pub rotate_clockwise_keys: Vec<Input>,
+    pub rotate_counterclockwise_keys: Vec<Input>,
+    pub hold_tetromino_keys: Vec<Input>,
+    /// Restart the game once it's over, the keybindings saved before they could be set getting the default ones.
+    #[serde(default = "default_restart_keys")]
+    pub restart_keys: Vec<Input>,
+    #[serde(default = "default_pause_keys")]
+    pub pause_keys: Vec<Input>,
+}
+
+// the buttons are Back and Start on the usual controller layout
+pub fn default_restart_keys() -> Vec<Input> {
+    vec![Input::Key(Key::R), Input::Button { button: 6 }]
+}
+
+pub fn default_pause_keys() -> Vec<Input> {
+    vec![Input::Key(Key::P), Input::Button { button: 7 }]
 }
 
 impl Keybindings {
+    /// Returns the default keybindings of the single-player game.
     pub fn new() -> Keybindings {
-        let fall_keys = FALL_KEYS_1P.to_vec();
-        let hard_drop_keys = HARD_DROP_KEYS_1P.to_vec();
-        let right_keys = RIGHT_KEYS_1P.to_vec();
-        let left_keys = LEFT_KEYS_1P.to_vec();
-        let rotate_clockwise_keys = ROTATE_CLOCKWISE_KEYS_1P.to_vec();
-        let rotate_counterclockwise_keys = ROTATE_COUNTERCLOCKWISE_KEYS_1P.to_vec();
-        let hold_tetromino_keys = HOLD_TETROMINO_KEYS_1P.to_vec();
-
-        Keybindings {
-            fall_keys,
-            hard_drop_keys,
-            right_keys,
-            left_keys,
-            rotate_clockwise_keys,
-            rotate_counterclockwise_keys,
-            hold_tetromino_keys,
-        }
+        config().keybindings.one_player.clone()
     }
 
+    /// Returns the default keybindings of the left side of the two-player game for the id 0, of the right side otherwise.
     pub fn new_two_local(id: usize) -> Keybindings {
         if id == 0 {
-            let fall_keys = vec![FALL_KEYS_2P[0]];
-            let hard_drop_keys = vec![HARD_DROP_KEYS_2P[0]];
-            let right_keys = vec![RIGHT_KEYS_2P[0]];
-            let left_keys = vec![LEFT_KEYS_2P[0]];
-            let rotate_clockwise_keys = vec![ROTATE_CLOCKWISE_KEYS_2P[0]];
-            let rotate_counterclockwise_keys = vec![ROTATE_COUNTERCLOCKWISE_KEYS_2P[0]];
-            let hold_tetromino_keys = vec![HOLD_TETROMINO_KEYS_2P[0]];
-
-            Keybindings {
-                fall_keys,
-                hard_drop_keys,
-                right_keys,
-                left_keys,
-                rotate_clockwise_keys,
-                rotate_counterclockwise_keys,
-                hold_tetromino_keys,
-            }
+            config().keybindings.left_player.clone()
         } else {
-            let fall_keys = vec![FALL_KEYS_2P[1]];
-            let hard_drop_keys = vec![HARD_DROP_KEYS_2P[1]];
-            let right_keys = vec![RIGHT_KEYS_2P[1]];
-            let left_keys = vec![LEFT_KEYS_2P[1]];
-            let rotate_clockwise_keys = vec![ROTATE_CLOCKWISE_KEYS_2P[1]];
-            let rotate_counterclockwise_keys = vec![ROTATE_COUNTERCLOCKWISE_KEYS_2P[1]];
-            let hold_tetromino_keys = vec![HOLD_TETROMINO_KEYS_2P[1]];
-
-            Keybindings {
-                fall_keys,
-                hard_drop_keys,
-                right_keys,
-                left_keys,
-                rotate_clockwise_keys,
-                rotate_counterclockwise_keys,
-                hold_tetromino_keys,
-            }
+            config().keybindings.right_player.clone()
         }
     }
 
-    pub fn set_keys(&mut self, key_type: &TetrisCommand, new_keys: Vec<Key>) {
+    pub fn set_keys(&mut self, key_type: &TetrisCommand, new_keys: Vec<Input>) {
         match key_type {
             TetrisCommand::Fall(_) => self.fall_keys = new_keys,
             TetrisCommand::HardDrop(_) => self.hard_drop_keys = new_keys,
@@ -167,9 +140,26 @@ impl Keybindings {
                 self.rotate_counterclockwise_keys = new_keys
             }
             TetrisCommand::HoldTetromino(_) => self.hold_tetromino_keys = new_keys,
+            TetrisCommand::Restart(_) => self.restart_keys = new_keys,
+            TetrisCommand::Pause(_) => self.pause_keys = new_keys,
         }
     }
 
+    /// Returns true if the input triggers one of the commands of the tetromino.
+    pub fn is_bound(&self, input: &Input) -> bool {
+        [
+            &self.fall_keys,
+            &self.hard_drop_keys,
+            &self.right_keys,
+            &self.left_keys,
+            &self.rotate_clockwise_keys,
+            &self.rotate_counterclockwise_keys,
+            &self.hold_tetromino_keys,
+        ]
+        .iter()
+        .any(|keys| keys.contains(input))
+    }
+
     pub fn print(&self) {
         println!("printing the updated settings");
         println!("     fall_keys: {:?}", self.fall_keys);
@@ -189,14 +179,90 @@ impl Keybindings {
     }
 }
 
+impl Default for Keybindings {
+    fn default() -> Self {
+        Keybindings::new()
+    }
+}
+
 /****************************************/
 /*           APP PARAMETERS             */
 /****************************************/
 
-// not setable in the UI
-pub static RESTART_KEYS: [Key; 1] = [Key::R];
-pub static PAUSE_KEYS: [Key; 1] = [Key::P];
-pub static KEY_REPEAT_DELAY: u64 = 20;
+// shows or hides the perfect clear hint in practice
+pub static PC_HINT_KEYS: [Key; 1] = [Key::H];
+// undo and redo the placements, change the next tetromino and undo back to the start of the bag in practice
+pub static UNDO_KEYS: [Key; 1] = [Key::Z];
+pub static REDO_KEYS: [Key; 1] = [Key::Y];
+pub static NEXT_PIECE_KEYS: [Key; 1] = [Key::N];
+pub static BAG_START_KEYS: [Key; 1] = [Key::B];
+// undo the placements with a finesse fault in practice
+pub static FINESSE_RETRY_KEYS: [Key; 1] = [Key::G];
+// copy the fumen of the single player game to the clipboard, and practice from the fumen of the clipboard
+pub static COPY_FUMEN_KEYS: [Key; 1] = [Key::F];
+pub static PASTE_FUMEN_KEYS: [Key; 1] = [Key::V];
+// write the stats of the single player game in files of the data directory
+pub static EXPORT_STATS_KEYS: [Key; 1] = [Key::X];
+// stats shown under the grid during the single player game, all of them are shown once it's lost
+pub static LIVE_STATS: [Stat; 3] = [Stat::Pps, Stat::Apm, Stat::Kpp];
+// number of rows from the bottom where the perfect clear solver can place tetrominos
+pub static PC_MAX_HEIGHT: u32 = 4;
+// number of updates before a key pressed for a long time moves the tetromino again
+pub static KEY_REPEAT_DELAY: LazyLock<u64> = LazyLock::new(|| config().key_repeat_delay);
+// number of updates between two moves of the tetromino when a key is pressed for a long time
+pub static KEY_REPEAT_RATE: LazyLock<u64> = LazyLock::new(|| config().key_repeat_rate);
+// an analog stick pushed further than the deadzone, between 0 and 1, presses its direction
+pub static CONTROLLER_DEADZONE: LazyLock<f64> = LazyLock::new(|| config().controller_deadzone);
+// number of lines to clear in a sprint, the mode button goes through them in this order
+pub static SPRINT_LINES: [u64; 3] = [20, 40, 100];
+// a split time is taken every SPRINT_SPLIT_LINES lines of a sprint
+pub static SPRINT_SPLIT_LINES: u64 = 10;
+// time limits of an ultra in seconds, the first one is the default
+pub static ULTRA_SECONDS: [u64; 3] = [120, 180, 300];
+// lines to clear to win a marathon
+pub static MARATHON_LINES: u64 = 150;
+// the level of a marathon goes up every MARATHON_LEVEL_LINES lines
+pub static MARATHON_LEVEL_LINES: u64 = 10;
+pub static MARATHON_MAX_START_LEVEL: u32 = 15;
+// garbage rows to clear in a dig race
+pub static DIG_ROWS: [u64; 3] = [10, 18, 100];
+// rows of garbage in the grid at the start of a dig race, if there are enough rows to clear
+pub static DIG_START_ROWS: u64 = 10;
+// a new row of garbage rises every DIG_RISE_SECONDS seconds, until all the rows to clear were added
+pub static DIG_RISE_SECONDS: f64 = 2.0;
+// probability that the hole of a garbage row isn't under the one of the row above, between 0 and 1
+pub static DIG_MESSINESS: f64 = 0.3;
+// number of results kept in the leaderboard of each mode
+pub static LEADERBOARD_SIZE: usize = 10;
+// number of games on each page of the match history
+pub static HISTORY_PAGE_SIZE: usize = 10;
+// name of the player without a profile in the leaderboards and the match history, followed by its seat after the first one
+pub static DEFAULT_PROFILE: &str = "Player";
+// delays of the keys a profile chooses from, in updates, the default ones being KEY_REPEAT_DELAY and KEY_REPEAT_RATE
+pub static PROFILE_AUTO_SHIFT_DELAYS: [u64; 5] = [10, 14, 20, 28, 40];
+pub static PROFILE_AUTO_REPEAT_RATES: [u64; 5] = [1, 2, 3, 5, 8];
+// longest name of a profile, in characters
+pub static PROFILE_NAME_MAX_LENGTH: usize = 16;
+// the start level of a classic game is chosen between 0 and CLASSIC_MAX_START_LEVEL
+pub static CLASSIC_MAX_START_LEVEL: u32 = 19;
+// delays of the keys of the NES in updates, 16 and 6 frames at 60 fps
+pub static CLASSIC_AUTO_SHIFT_DELAY: u64 = 32;
+pub static CLASSIC_AUTO_REPEAT_RATE: u64 = 12;
+// number of updates before the next tetromino appears in a classic game, 10 frames at 60 fps
+pub static CLASSIC_ENTRY_DELAY: u64 = 20;
+// points of a single, double, triple and tetris in a classic game, multiplied by the level plus one
+pub static CLASSIC_POINTS: [u64; 4] = [40, 100, 300, 1200];
+// visible rows emptied at the top of the stack when it reaches the top in zen
+pub static ZEN_CLEARED_ROWS: u32 = 10;
+// the master ends at this level, which is also the last level stop
+pub static MASTER_MAX_LEVEL: u32 = 999;
+// a master ends early if a level isn't reached before a time in seconds
+pub static MASTER_TORIKANS: [(u32, f64); 1] = [(500, 420.0)];
+// locked blocks fade away after INVISIBLE_SECONDS with the invisible modifier, the last INVISIBLE_FADE_SECONDS gradually
+pub static INVISIBLE_SECONDS: f64 = 5.0;
+pub static INVISIBLE_FADE_SECONDS: f64 = 1.0;
+// the grid is flipped horizontally every MIRROR_PIECES locked tetrominos with the mirror modifier
+pub static MIRROR_PIECES: u64 = 10;
 
 /// Settings represents parameters that need to be common between players in multiplayer mode.
 ///
@@ -265,3 +331,99 @@ pub static BAG_SIZE: u32 = 14;
 // typical sizes are 7 and 14, 1 is entirely random
 // for size 7 * n + k, k < 7, there's n or n + 1 of each tetromino and exactly k tetrominos are present n + 1 times
 pub const NB_NEXT_TETROMINO: usize = 6;
+
+/// Number of updates per second of the game, the default of the piston event loop.
+pub static UPDATES_PER_SECOND: u64 = 120;
+
+/// Number of updates between two falls of the tetromino at each level of a marathon, starting from level 1.
+///
+/// It follows the guideline speed of (0.8 - (level - 1) * 0.007)^(level - 1) seconds per row,
+/// the levels after the last one are as fast as the last one.
+pub static LEVEL_GRAVITY: [u64; 15] = [120, 95, 74, 57, 43, 31, 23, 16, 11, 8, 5, 3, 2, 1, 1];
+
+/// Number of updates between two falls of the tetromino at each level of a classic game, starting from level 0.
+///
+/// It follows the NES gravity in frames per row at 60 fps, the levels after the last one are as fast as the last one.
+pub static CLASSIC_GRAVITY: [u64; 30] = [
+    96, 86, 76, 66, 56, 46, 36, 26, 16, 12, 10, 10, 10, 8, 8, 8, 6, 6, 6, 4, 4, 4, 4, 4, 4, 4, 4,
+    4, 4, 2,
+];
+
+/// Gravity of a master from each level on: the level, the number of updates between two falls and the number of rows of each fall.
+///
+/// It follows the gravity of the arcade games (in 1/256 rows per frame at 60 fps), the fall being instant (20G) from level 500.
+pub static MASTER_GRAVITY: [(u32, u64, u32); 26] = [
+    (0, 128, 1),
+    (30, 85, 1),
+    (35, 64, 1),
+    (40, 51, 1),
+    (50, 43, 1),
+    (60, 32, 1),
+    (70, 16, 1),
+    (80, 11, 1),
+    (90, 8, 1),
+    (100, 6, 1),
+    (120, 5, 1),
+    (160, 4, 1),
+    (200, 128, 1),
+    (220, 16, 1),
+    (230, 8, 1),
+    (233, 5, 1),
+    (236, 4, 1),
+    (239, 3, 1),
+    (247, 2, 1),
+    (300, 1, 1),
+    (330, 2, 3),
+    (360, 1, 2),
+    (400, 2, 5),
+    (420, 1, 2),
+    (450, 2, 3),
+    (500, 1, NB_ROWS),
+];
+
+/// Delays of a master from each level on: the level, the number of updates before the next tetromino appears (ARE)
+/// and before the tetromino freezes at the bottom.
+pub static MASTER_DELAYS: [(u32, u64, u64); 6] = [
+    (0, 50, 60),
+    (500, 50, 56),
+    (600, 32, 52),
+    (700, 24, 48),
+    (800, 12, 40),
+    (900, 12, 34),
+];
+
+/// Minimal score of each grade of a master, from the lowest to the highest.
+pub static MASTER_GRADES: [(u64, &str); 18] = [
+    (0, "9"),
+    (400, "8"),
+    (800, "7"),
+    (1400, "6"),
+    (2000, "5"),
+    (3500, "4"),
+    (5500, "3"),
+    (8000, "2"),
+    (12000, "1"),
+    (16000, "S1"),
+    (22000, "S2"),
+    (30000, "S3"),
+    (40000, "S4"),
+    (52000, "S5"),
+    (66000, "S6"),
+    (82000, "S7"),
+    (100000, "S8"),
+    (120000, "S9"),
+];
+
+/// Returns the number of updates between two falls of the tetromino and before it freezes at the bottom,
+/// given the time elapsed since the start of the game in seconds.
+pub fn gravity(clock: f64) -> (u64, u64) {
+    let freeze = 50;
+    let fall_speed_divide = match clock {
+        i if i <= 30.0 => 50,
+        i if i <= 60.0 => 40,
+        i if i <= 90.0 => 30,
+        i if i <= 120.0 => 20,
+        _ => 15,
+    };
+    (fall_speed_divide, freeze)
+}
@@ -1,19 +1,23 @@
+use crate::input::Input;
 use crate::settings::{
-    Keybindings, DEFAULT_BUTTON_HEIGHT, DEFAULT_BUTTON_WIDTH, DEFAULT_BUTTON_Y_SPACING,
-    DEFAULT_KEY_INPUT_HEIGHT, DEFAULT_KEY_INPUT_WIDTH, DEFAULT_WINDOW_HEIGHT, DEFAULT_WINDOW_WIDTH,
-    GUEST_PORT, HOST_PORT,
+    Keybindings, BLOCK_SIZE, DEFAULT_BUTTON_HEIGHT, DEFAULT_BUTTON_WIDTH, DEFAULT_BUTTON_Y_SPACING,
+    DEFAULT_GRID_X, DEFAULT_GRID_Y, DEFAULT_KEY_INPUT_HEIGHT, DEFAULT_KEY_INPUT_WIDTH,
+    DEFAULT_TITLE_Y, DEFAULT_WINDOW_HEIGHT, DEFAULT_WINDOW_WIDTH, GUEST_PORT, HOST_PORT,
 };
 use crate::ui::{button::Button, key_input::KeyInput, text_input::TextInput};
 use clipboard::{ClipboardContext, ClipboardProvider};
 use local_ip_address::local_ip;
-use piston::{Key, MouseButton};
+use piston::MouseButton;
 use std::collections::HashMap;
 
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
 pub enum ButtonType {
     ToSinglePlayerGame,
     ToTwoRemoteGame,
-    ToTwoRemoteGameInfo { local_ip: String, remote_ip: String },
+    ToTwoRemoteGameInfo {
+        local_ip: String,
+        remote_ip: String,
+    },
     ToCreateRoom,
     ToJoinRoom,
     ToSettings,
@@ -23,6 +27,43 @@ pub enum ButtonType {
     ToPause,
     CopyToClipboard,
     PasteFromClipboard,
+    NextGameMode,
+    NextStartLevel,
+    SelectStartLevel(u32),
+    /// Toggles the modifier at this index of the ones listed in the single-player game.
+    ToggleModifier(usize),
+    /// Goes to the next puzzle or opener to train on.
+    NextItem,
+    /// Applies the practice action at this index of the ones listed in the single-player game.
+    PracticeAction(usize),
+    ToEditor,
+    /// Selects the brush at this index of the ones listed in the editor.
+    SelectBrush(usize),
+    NextActivePiece,
+    NextHoldPiece,
+    ClearGrid,
+    SavePosition,
+    LoadPosition,
+    /// Plays from the position set up in the editor.
+    PlayPosition,
+    /// Copies the position of the editor to the clipboard as a fumen.
+    CopyFumen,
+    /// Replaces the position of the editor by the fumen of the clipboard.
+    PasteFumen,
+    ToResults,
+    /// Goes from the leaderboards to the match history and back.
+    SwitchResults,
+    /// Shows the leaderboard of the previous mode or the previous page of the match history.
+    PreviousResults,
+    NextResults,
+    /// Gives the seat at this index the next profile.
+    NextProfile(usize),
+    /// Changes the theme of the profile of the seat at this index.
+    NextTheme(usize),
+    NextAutoShiftDelay(usize),
+    NextAutoRepeatRate(usize),
+    /// Creates a profile for the seat at this index, named after the text typed on its side.
+    CreateProfile(usize),
     Nothing,
 }
 
@@ -39,17 +80,20 @@ pub enum TextInputType {
     #[allow(unused)]
     DebugTextInput,
     IpAddressInput,
+    ProfileNameInput,
 }
 
 #[derive(Hash, PartialEq, Eq)]
 pub enum TetrisCommand {
-    Fall(Vec<Key>),
-    HardDrop(Vec<Key>),
-    Right(Vec<Key>),
-    Left(Vec<Key>),
-    RotateClockwise(Vec<Key>),
-    RotateCounterclockwise(Vec<Key>),
-    HoldTetromino(Vec<Key>),
+    Fall(Vec<Input>),
+    HardDrop(Vec<Input>),
+    Right(Vec<Input>),
+    Left(Vec<Input>),
+    RotateClockwise(Vec<Input>),
+    RotateCounterclockwise(Vec<Input>),
+    HoldTetromino(Vec<Input>),
+    Restart(Vec<Input>),
+    Pause(Vec<Input>),
 }
 
 #[allow(clippy::enum_variant_names)]
@@ -108,6 +152,14 @@ impl InteractiveWidgetManager {
             "two-player game",
         );
 
+        let results_button = Button::new(
+            (58.0 * DEFAULT_WINDOW_WIDTH as f64) / 65.0,
+            (5.0 * DEFAULT_WINDOW_HEIGHT as f64) / 70.0,
+            DEFAULT_BUTTON_WIDTH / 3.5,
+            DEFAULT_BUTTON_HEIGHT / 2.0,
+            "Results",
+        );
+
         let mut buttons = HashMap::new();
         buttons.insert(
             ButtonType::ToSinglePlayerGame,
@@ -117,6 +169,7 @@ impl InteractiveWidgetManager {
         buttons.insert(ButtonType::ToJoinRoom, join_room_button);
         buttons.insert(ButtonType::ToSettings, settings_button);
         buttons.insert(ButtonType::ToTwoLocalGame, create_two_player_game_button);
+        buttons.insert(ButtonType::ToResults, results_button);
 
         let text_inputs = HashMap::new();
 
@@ -129,15 +182,18 @@ impl InteractiveWidgetManager {
         }
     }
 
+    /// profile_labels are the labels of the buttons changing the profile of the seat,
+    /// the first one choosing the profile and the next ones changing its theme, DAS and ARR.
     pub fn new_settings(
         settings: &Keybindings,
         settings_type: SettingsType,
         from_game: bool,
+        profile_labels: &[String],
     ) -> InteractiveWidgetManager {
-        let player_x = if settings_type == SettingsType::RightPlayer {
-            DEFAULT_WINDOW_WIDTH as f64
+        let (player_x, seat) = if settings_type == SettingsType::RightPlayer {
+            (DEFAULT_WINDOW_WIDTH as f64, 1)
         } else {
-            0.0
+            (0.0, 0)
         };
         let fall_keys_input = KeyInput::new_with_info(
             DEFAULT_WINDOW_WIDTH as f64 / 4.0 + player_x,
@@ -202,8 +258,62 @@ impl InteractiveWidgetManager {
             "Hold Tetromino Keys :",
         );
 
+        // the restart and pause keys share the last row
+        let restart_keys_input = KeyInput::new_with_info(
+            DEFAULT_WINDOW_WIDTH as f64 * 3.0 / 4.0 - DEFAULT_KEY_INPUT_WIDTH / 4.0 + player_x,
+            DEFAULT_WINDOW_HEIGHT as f64 / 2.0 + DEFAULT_BUTTON_Y_SPACING * 3.0,
+            DEFAULT_KEY_INPUT_WIDTH * 0.45,
+            DEFAULT_KEY_INPUT_HEIGHT,
+            &settings.restart_keys,
+            "Restart :",
+        );
+
+        let pause_keys_input = KeyInput::new_with_info(
+            DEFAULT_WINDOW_WIDTH as f64 * 3.0 / 4.0 + DEFAULT_KEY_INPUT_WIDTH / 4.0 + player_x,
+            DEFAULT_WINDOW_HEIGHT as f64 / 2.0 + DEFAULT_BUTTON_Y_SPACING * 3.0,
+            DEFAULT_KEY_INPUT_WIDTH * 0.45,
+            DEFAULT_KEY_INPUT_HEIGHT,
+            &settings.pause_keys,
+            "Pause :",
+        );
+
         let mut buttons = HashMap::new();
 
+        // the profile buttons are two by two between the title and the keys
+        let profile_types = [
+            ButtonType::NextProfile(seat),
+            ButtonType::NextTheme(seat),
+            ButtonType::NextAutoShiftDelay(seat),
+            ButtonType::NextAutoRepeatRate(seat),
+        ];
+        for (i, (button_type, label)) in profile_types.into_iter().zip(profile_labels).enumerate() {
+            let profile_button = Button::new(
+                DEFAULT_WINDOW_WIDTH as f64 * (1 + 2 * (i % 2)) as f64 / 4.0 + player_x,
+                175.0 + 75.0 * (i / 2) as f64,
+                DEFAULT_KEY_INPUT_WIDTH,
+                DEFAULT_BUTTON_HEIGHT / 1.5,
+                label,
+            );
+            buttons.insert(button_type, profile_button);
+        }
+
+        let create_profile_button = Button::new(
+            DEFAULT_WINDOW_WIDTH as f64 * 3.0 / 4.0 + player_x,
+            325.0,
+            DEFAULT_KEY_INPUT_WIDTH,
+            DEFAULT_BUTTON_HEIGHT / 1.5,
+            "Create profile",
+        );
+        buttons.insert(ButtonType::CreateProfile(seat), create_profile_button);
+
+        let profile_name_input = TextInput::new(
+            DEFAULT_WINDOW_WIDTH as f64 / 4.0 + player_x,
+            325.0,
+            DEFAULT_KEY_INPUT_WIDTH,
+            DEFAULT_BUTTON_HEIGHT / 1.5,
+            "New profile name",
+        );
+
         if !from_game {
             match settings_type {
                 SettingsType::OnePlayer => {
@@ -254,7 +364,8 @@ impl InteractiveWidgetManager {
             }
         }
 
-        let text_inputs = HashMap::new();
+        let mut text_inputs = HashMap::new();
+        text_inputs.insert(TextInputType::ProfileNameInput, profile_name_input);
 
         let mut key_inputs = HashMap::new();
         key_inputs.insert(
@@ -285,6 +396,14 @@ impl InteractiveWidgetManager {
             TetrisCommand::HoldTetromino(hold_tetromino_keys_input.keys.clone()),
             hold_tetromino_keys_input,
         );
+        key_inputs.insert(
+            TetrisCommand::Restart(restart_keys_input.keys.clone()),
+            restart_keys_input,
+        );
+        key_inputs.insert(
+            TetrisCommand::Pause(pause_keys_input.keys.clone()),
+            pause_keys_input,
+        );
 
         InteractiveWidgetManager {
             buttons,
@@ -293,7 +412,21 @@ impl InteractiveWidgetManager {
         }
     }
 
-    pub fn new_single_player_game() -> InteractiveWidgetManager {
+    /// game_mode is the name of the mode shown on the button that changes it,
+    /// start_level is given if the mode can start at different levels.
+    ///
+    /// If a maximal level is given, the start level is instead chosen among the levels from 0 to it,
+    /// on a level select over the grid which starts the game.
+    /// next_item is the label of the button that goes to the next puzzle or opener, given in the modes that have them.
+    /// practice_actions are the labels of the buttons of the practice, empty in the other modes.
+    pub fn new_single_player_game(
+        game_mode: &str,
+        start_level: Option<u32>,
+        level_select: Option<u32>,
+        modifiers: &[String],
+        next_item: Option<&str>,
+        practice_actions: &[String],
+    ) -> InteractiveWidgetManager {
         let back_to_main_menu_button = Button::new(
             (5.0 * DEFAULT_WINDOW_WIDTH as f64) / 65.0,
             (5.0 * DEFAULT_WINDOW_HEIGHT as f64) / 70.0,
@@ -318,10 +451,243 @@ impl InteractiveWidgetManager {
             "Settings",
         );
 
+        let game_mode_button = Button::new(
+            DEFAULT_WINDOW_WIDTH as f64 / 2.0,
+            (5.0 * DEFAULT_WINDOW_HEIGHT as f64) / 70.0,
+            DEFAULT_BUTTON_WIDTH / 1.8,
+            DEFAULT_BUTTON_HEIGHT / 2.0,
+            game_mode,
+        );
+
         let mut buttons = HashMap::new();
         buttons.insert(ButtonType::BackToMainMenu, back_to_main_menu_button);
         buttons.insert(ButtonType::ToPause, pause_button);
         buttons.insert(ButtonType::ToSettings, settings_button);
+        buttons.insert(ButtonType::NextGameMode, game_mode_button);
+        if let Some(max_level) = level_select {
+            // the levels are on rows of 5 buttons, each one is 2 blocks wide
+            for level in 0..=max_level {
+                let level_button = Button::new(
+                    DEFAULT_GRID_X + (2.0 * (level % 5) as f64 + 1.0) * BLOCK_SIZE,
+                    DEFAULT_GRID_Y + (7.0 + 2.0 * (level / 5) as f64) * BLOCK_SIZE,
+                    1.6 * BLOCK_SIZE,
+                    1.6 * BLOCK_SIZE,
+                    &level.to_string(),
+                );
+                buttons.insert(ButtonType::SelectStartLevel(level), level_button);
+            }
+        } else if let Some(start_level) = start_level {
+            let start_level_button = Button::new(
+                (47.0 * DEFAULT_WINDOW_WIDTH as f64) / 65.0,
+                (5.0 * DEFAULT_WINDOW_HEIGHT as f64) / 70.0,
+                DEFAULT_BUTTON_WIDTH / 3.0,
+                DEFAULT_BUTTON_HEIGHT / 2.0,
+                &format!("Level {start_level}"),
+            );
+            buttons.insert(ButtonType::NextStartLevel, start_level_button);
+        } else if let Some(next_item) = next_item {
+            let next_item_button = Button::new(
+                (47.0 * DEFAULT_WINDOW_WIDTH as f64) / 65.0,
+                (5.0 * DEFAULT_WINDOW_HEIGHT as f64) / 70.0,
+                DEFAULT_BUTTON_WIDTH / 3.0,
+                DEFAULT_BUTTON_HEIGHT / 2.0,
+                next_item,
+            );
+            buttons.insert(ButtonType::NextItem, next_item_button);
+        }
+        // the modifiers are listed on the left of the grid, under the texts of the game
+        for (i, modifier) in modifiers.iter().enumerate() {
+            let modifier_button = Button::new(
+                DEFAULT_GRID_X - 4.0 * BLOCK_SIZE,
+                DEFAULT_GRID_Y + (14.0 + 1.5 * i as f64) * BLOCK_SIZE,
+                6.0 * BLOCK_SIZE,
+                1.2 * BLOCK_SIZE,
+                modifier,
+            );
+            buttons.insert(ButtonType::ToggleModifier(i), modifier_button);
+        }
+        // the practice actions are listed on the right of the grid, under the next tetrominos
+        for (i, action) in practice_actions.iter().enumerate() {
+            let action_button = Button::new(
+                DEFAULT_GRID_X + 14.0 * BLOCK_SIZE,
+                DEFAULT_GRID_Y + (19.0 + 1.5 * i as f64) * BLOCK_SIZE,
+                6.0 * BLOCK_SIZE,
+                1.2 * BLOCK_SIZE,
+                action,
+            );
+            buttons.insert(ButtonType::PracticeAction(i), action_button);
+        }
+        let editor_button = Button::new(
+            DEFAULT_GRID_X - 4.0 * BLOCK_SIZE,
+            DEFAULT_GRID_Y + (14.0 + 1.5 * modifiers.len() as f64) * BLOCK_SIZE,
+            6.0 * BLOCK_SIZE,
+            1.2 * BLOCK_SIZE,
+            "Editor",
+        );
+        buttons.insert(ButtonType::ToEditor, editor_button);
+
+        let text_inputs = HashMap::new();
+        let key_inputs = HashMap::new();
+
+        InteractiveWidgetManager {
+            buttons,
+            text_inputs,
+            key_inputs,
+        }
+    }
+
+    /// switch_label is the label of the button going from the leaderboards to the match history and back.
+    pub fn new_results(switch_label: &str) -> InteractiveWidgetManager {
+        let back_to_main_menu_button = Button::new(
+            (5.0 * DEFAULT_WINDOW_WIDTH as f64) / 65.0,
+            (5.0 * DEFAULT_WINDOW_HEIGHT as f64) / 70.0,
+            DEFAULT_BUTTON_WIDTH / 6.0,
+            DEFAULT_BUTTON_HEIGHT / 2.0,
+            "Back",
+        );
+
+        let switch_button = Button::new(
+            DEFAULT_WINDOW_WIDTH as f64 / 2.0,
+            (5.0 * DEFAULT_WINDOW_HEIGHT as f64) / 70.0,
+            DEFAULT_BUTTON_WIDTH / 1.8,
+            DEFAULT_BUTTON_HEIGHT / 2.0,
+            switch_label,
+        );
+
+        // the previous and next buttons are on both sides of the title of the page
+        let previous_button = Button::new(
+            (8.0 * DEFAULT_WINDOW_WIDTH as f64) / 65.0,
+            DEFAULT_TITLE_Y,
+            DEFAULT_BUTTON_WIDTH / 6.0,
+            DEFAULT_BUTTON_HEIGHT / 2.0,
+            "<",
+        );
+
+        let next_button = Button::new(
+            (57.0 * DEFAULT_WINDOW_WIDTH as f64) / 65.0,
+            DEFAULT_TITLE_Y,
+            DEFAULT_BUTTON_WIDTH / 6.0,
+            DEFAULT_BUTTON_HEIGHT / 2.0,
+            ">",
+        );
+
+        let mut buttons = HashMap::new();
+        buttons.insert(ButtonType::BackToMainMenu, back_to_main_menu_button);
+        buttons.insert(ButtonType::SwitchResults, switch_button);
+        buttons.insert(ButtonType::PreviousResults, previous_button);
+        buttons.insert(ButtonType::NextResults, next_button);
+
+        let text_inputs = HashMap::new();
+        let key_inputs = HashMap::new();
+
+        InteractiveWidgetManager {
+            buttons,
+            text_inputs,
+            key_inputs,
+        }
+    }
+
+    /// brushes are the labels of the buttons choosing what the clicks put in the cells,
+    /// active_piece and hold_piece the labels of the buttons changing the active and the held tetrominos.
+    pub fn new_editor(
+        brushes: &[String],
+        active_piece: &str,
+        hold_piece: &str,
+    ) -> InteractiveWidgetManager {
+        let back_to_game_button = Button::new(
+            (5.0 * DEFAULT_WINDOW_WIDTH as f64) / 65.0,
+            (5.0 * DEFAULT_WINDOW_HEIGHT as f64) / 70.0,
+            DEFAULT_BUTTON_WIDTH / 6.0,
+            DEFAULT_BUTTON_HEIGHT / 2.0,
+            "Back",
+        );
+
+        let clear_button = Button::new(
+            (13.0 * DEFAULT_WINDOW_WIDTH as f64) / 65.0,
+            (5.0 * DEFAULT_WINDOW_HEIGHT as f64) / 70.0,
+            DEFAULT_BUTTON_WIDTH / 5.0,
+            DEFAULT_BUTTON_HEIGHT / 2.0,
+            "Clear",
+        );
+
+        let save_button = Button::new(
+            (28.0 * DEFAULT_WINDOW_WIDTH as f64) / 65.0,
+            (5.0 * DEFAULT_WINDOW_HEIGHT as f64) / 70.0,
+            DEFAULT_BUTTON_WIDTH / 5.0,
+            DEFAULT_BUTTON_HEIGHT / 2.0,
+            "Save",
+        );
+
+        let load_button = Button::new(
+            (37.0 * DEFAULT_WINDOW_WIDTH as f64) / 65.0,
+            (5.0 * DEFAULT_WINDOW_HEIGHT as f64) / 70.0,
+            DEFAULT_BUTTON_WIDTH / 5.0,
+            DEFAULT_BUTTON_HEIGHT / 2.0,
+            "Load",
+        );
+
+        let play_button = Button::new(
+            (58.0 * DEFAULT_WINDOW_WIDTH as f64) / 65.0,
+            (5.0 * DEFAULT_WINDOW_HEIGHT as f64) / 70.0,
+            DEFAULT_BUTTON_WIDTH / 3.5,
+            DEFAULT_BUTTON_HEIGHT / 2.0,
+            "Play",
+        );
+
+        // the pieces are changed on the right of the grid, under the next tetrominos
+        let active_piece_button = Button::new(
+            DEFAULT_GRID_X + 14.0 * BLOCK_SIZE,
+            DEFAULT_GRID_Y + 19.0 * BLOCK_SIZE,
+            6.0 * BLOCK_SIZE,
+            1.2 * BLOCK_SIZE,
+            active_piece,
+        );
+
+        let hold_piece_button = Button::new(
+            DEFAULT_GRID_X + 14.0 * BLOCK_SIZE,
+            DEFAULT_GRID_Y + 20.5 * BLOCK_SIZE,
+            6.0 * BLOCK_SIZE,
+            1.2 * BLOCK_SIZE,
+            hold_piece,
+        );
+
+        let copy_fumen_button = Button::new(
+            DEFAULT_GRID_X + 14.0 * BLOCK_SIZE,
+            DEFAULT_GRID_Y + 22.0 * BLOCK_SIZE,
+            6.0 * BLOCK_SIZE,
+            1.2 * BLOCK_SIZE,
+            "Copy fumen",
+        );
+
+        let paste_fumen_button = Button::new(
+            DEFAULT_GRID_X + 14.0 * BLOCK_SIZE,
+            DEFAULT_GRID_Y + 23.5 * BLOCK_SIZE,
+            6.0 * BLOCK_SIZE,
+            1.2 * BLOCK_SIZE,
+            "Paste fumen",
+        );
+
+        let mut buttons = HashMap::new();
+        buttons.insert(ButtonType::BackToGame, back_to_game_button);
+        buttons.insert(ButtonType::ClearGrid, clear_button);
+        buttons.insert(ButtonType::SavePosition, save_button);
+        buttons.insert(ButtonType::LoadPosition, load_button);
+        buttons.insert(ButtonType::PlayPosition, play_button);
+        buttons.insert(ButtonType::NextActivePiece, active_piece_button);
+        buttons.insert(ButtonType::NextHoldPiece, hold_piece_button);
+        buttons.insert(ButtonType::CopyFumen, copy_fumen_button);
+        buttons.insert(ButtonType::PasteFumen, paste_fumen_button);
+        // the brushes are listed on the left of the grid, under the text of the editor
+        for (i, brush) in brushes.iter().enumerate() {
+            let brush_button = Button::new(
+                DEFAULT_GRID_X - 4.0 * BLOCK_SIZE,
+                DEFAULT_GRID_Y + (12.5 + 1.5 * i as f64) * BLOCK_SIZE,
+                6.0 * BLOCK_SIZE,
+                1.2 * BLOCK_SIZE,
+                brush,
+            );
+            buttons.insert(ButtonType::SelectBrush(i), brush_button);
+        }
 
         let text_inputs = HashMap::new();
         let key_inputs = HashMap::new();
@@ -479,6 +845,13 @@ impl InteractiveWidgetManager {
         }
     }
 
+    /// Gives the controller input to the key inputs, which take it while they're being typed in.
+    pub fn handle_input_press(&mut self, input: Input) {
+        for key_input in self.key_inputs.values_mut() {
+            key_input.handle_input_press(input);
+        }
+    }
+
     pub fn handle_text_input(&mut self, text: &str) {
         for text_input in self.text_inputs.values_mut() {
             text_input.handle_text_input(text);
@@ -497,22 +870,32 @@ impl InteractiveWidgetManager {
             .unwrap_or_else(|| panic!("Input {:?} not found", input_type))
     }
 
-    pub fn update_settings(&mut self, keybindings_manager: &mut Keybindings) {
+    /// Returns whether the keybindings were changed.
+    pub fn update_settings(&mut self, keybindings_manager: &mut Keybindings) -> bool {
+        let mut changed = false;
         for (key_type, key_input) in self.key_inputs.iter_mut() {
             if key_input.commit() {
                 keybindings_manager.set_keys(key_type, key_input.keys.clone());
                 keybindings_manager.print();
+                changed = true;
             }
         }
+        changed
+    }
+
+    pub fn has_button(&self, button_type: &ButtonType) -> bool {
+        self.buttons.contains_key(button_type)
     }
 
-    pub fn update_clipboard(&mut self) {
+    /// room_address is the address of the hosted room, if it doesn't listen on the local IP and HOST_PORT.
+    pub fn update_clipboard(&mut self, room_address: Option<&str>) {
         if let Some(button) = self.buttons.get_mut(&ButtonType::CopyToClipboard) {
             if button.commit() {
                 println!("supposed to COPY");
                 let ip = local_ip().unwrap().to_string();
                 //let ip = "127.0.0.1".to_string();
-                let text = format!("{}{}", ip, HOST_PORT);
+                let text =
+                    room_address.map_or_else(|| format!("{}{}", ip, *HOST_PORT), String::from);
                 let mut ctx: ClipboardContext = ClipboardProvider::new().unwrap();
                 ctx.set_contents(text.to_owned()).unwrap();
             }
@@ -536,7 +919,7 @@ impl InteractiveWidgetManager {
             println!("remote ip is {remote_ip}");
             let local_ip = local_ip().unwrap().to_string();
             //let local_ip = "127.0.0.1".to_string();
-            let local_ip = format!("{}{}", local_ip, GUEST_PORT);
+            let local_ip = format!("{}{}", local_ip, *GUEST_PORT);
 
             let join_room = Button::new_committed(
                 DEFAULT_WINDOW_WIDTH as f64 / 2.0,
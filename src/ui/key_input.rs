@@ -1,4 +1,5 @@
 use crate::{
+    input::Input,
     settings::{DEFAULT_FONT_SIZE, TEXT_COLOR},
     ui::text::Text,
 };
@@ -13,8 +14,8 @@ pub struct KeyInput {
     pub(super) custom: bool, // when unfocused, true : display custom_text, false : display keys_to_string(init_keys)
     pub(super) custom_text: Text,
     pub(super) cursor: String,
-    pub(super) keys: Vec<Key>,
-    init_keys: Vec<Key>,          // initial values from settings.rs
+    pub(super) keys: Vec<Input>,
+    init_keys: Vec<Input>,        // initial values from settings.rs
     pub(super) placeholder: Text, // initial text
     pub(super) commit: bool,      // true : update app's settings
     pub(super) info_text: Text,
@@ -27,7 +28,7 @@ impl KeyInput {
         y: f64,
         width: f64,
         height: f64,
-        keys: &[Key],
+        keys: &[Input],
         info_text: &str,
     ) -> Self {
         let placeholder: &str = &keys_to_string(keys);
@@ -39,13 +40,13 @@ impl KeyInput {
             height,
             focused: false,
             custom: false,
-            custom_text: Text::new("", DEFAULT_FONT_SIZE, x, y, TEXT_COLOR),
+            custom_text: Text::new("", DEFAULT_FONT_SIZE, x, y, *TEXT_COLOR),
             cursor: String::from(""),
             keys: vec![],
             init_keys: vec_keys,
-            placeholder: Text::new(placeholder, DEFAULT_FONT_SIZE, x, y, TEXT_COLOR),
+            placeholder: Text::new(placeholder, DEFAULT_FONT_SIZE, x, y, *TEXT_COLOR),
             commit: false,
-            info_text: Text::new(info_text, DEFAULT_FONT_SIZE, x, y, TEXT_COLOR),
+            info_text: Text::new(info_text, DEFAULT_FONT_SIZE, x, y, *TEXT_COLOR),
             animation_counter: 0,
         }
     }
@@ -88,12 +89,19 @@ impl KeyInput {
                 self.unfocus();
             }
             _ => {
-                self.push_key(key);
+                self.push_key(Input::Key(key));
             }
         }
     }
 
-    fn push_key(&mut self, key: Key) {
+    /// Adds the controller input to the keys being typed, the controllers having no keys to erase or validate.
+    pub fn handle_input_press(&mut self, input: Input) {
+        if self.focused {
+            self.push_key(input);
+        }
+    }
+
+    fn push_key(&mut self, key: Input) {
         self.custom_text.content.push_str(&key_to_string(key));
         self.keys.push(key);
     }
@@ -134,14 +142,14 @@ impl KeyInput {
     }
 }
 
-fn key_to_string(key: Key) -> String {
-    if key == Key::Unknown {
+fn key_to_string(key: Input) -> String {
+    if key == Input::Key(Key::Unknown) {
         return String::from("");
     }
-    format!("{:?}, ", key)
+    format!("{}, ", key)
 }
 
-fn keys_to_string(keys: &[Key]) -> String {
+fn keys_to_string(keys: &[Input]) -> String {
     let mut s = String::new();
     for key in keys {
         s.push_str(&key_to_string(*key));
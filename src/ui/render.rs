@@ -51,7 +51,11 @@ impl TextInput {
         );
         let button_transform = transform.trans(self.x, self.y);
 
-        let color = if self.focused { color::RED } else { TEXT_COLOR };
+        let color = if self.focused {
+            color::RED
+        } else {
+            *TEXT_COLOR
+        };
 
         let outline_rect = graphics::Rectangle::new_border(color, 1.0);
         outline_rect.draw(dims, &ctx.draw_state, button_transform, gl);
@@ -96,7 +100,11 @@ impl KeyInput {
         );
         let button_transform = transform.trans(self.x, self.y);
 
-        let color = if self.focused { color::RED } else { TEXT_COLOR };
+        let color = if self.focused {
+            color::RED
+        } else {
+            *TEXT_COLOR
+        };
 
         let outline_rect = graphics::Rectangle::new_border(color, 1.0);
         outline_rect.draw(dims, &ctx.draw_state, button_transform, gl);
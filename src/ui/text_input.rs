@@ -24,9 +24,9 @@ impl TextInput {
             y,
             width,
             height,
-            info_text: Text::new("", DEFAULT_FONT_SIZE, x, y, TEXT_COLOR),
+            info_text: Text::new("", DEFAULT_FONT_SIZE, x, y, *TEXT_COLOR),
             cursor: String::from(""),
-            text: Text::new(placeholder, DEFAULT_FONT_SIZE, x, y, TEXT_COLOR),
+            text: Text::new(placeholder, DEFAULT_FONT_SIZE, x, y, *TEXT_COLOR),
             placeholder: String::from(placeholder),
             focused: false,
             animation_counter: 0,
@@ -47,9 +47,9 @@ impl TextInput {
             y,
             width,
             height,
-            info_text: Text::new(info_text, DEFAULT_FONT_SIZE, x, y, TEXT_COLOR),
+            info_text: Text::new(info_text, DEFAULT_FONT_SIZE, x, y, *TEXT_COLOR),
             cursor: String::from(""),
-            text: Text::new(placeholder, DEFAULT_FONT_SIZE, x, y, TEXT_COLOR),
+            text: Text::new(placeholder, DEFAULT_FONT_SIZE, x, y, *TEXT_COLOR),
             placeholder: String::from(placeholder),
             focused: false,
             animation_counter: 0,
@@ -98,6 +98,15 @@ impl TextInput {
         }
     }
 
+    /// Returns the text typed in the input, empty while the placeholder is shown.
+    pub fn typed_text(&self) -> &str {
+        if self.text.content == self.placeholder {
+            ""
+        } else {
+            &self.text.content
+        }
+    }
+
     pub fn handle_text_input(&mut self, text: &str) {
         if self.focused {
             self.text.content.push_str(text);
@@ -1,2 +1,3 @@
 pub mod formattings;
 pub mod macros;
+pub mod storage;
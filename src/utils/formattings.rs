@@ -3,3 +3,29 @@ pub fn format_seconds(seconds: f64) -> String {
     let seconds = seconds % 60.0;
     format!("{:}m {:.2}s", minutes, seconds)
 }
+
+/// Returns the date and time in UTC of a number of seconds since the Unix epoch, like 2024-01-31 23:59.
+pub fn format_date(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let minutes = timestamp % 86400 / 60;
+    // converts the days to a date of the proleptic Gregorian calendar, with eras of 400 years starting in March
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!(
+        "{year}-{month:02}-{day:02} {:02}:{:02}",
+        minutes / 60,
+        minutes % 60
+    )
+}
@@ -0,0 +1,91 @@
+//! Reads and writes the files kept between two launches of the game.
+//!
+//! The files are JSON, apart from the exported text files, in the directory given by [data_dir()] or one of its subdirectories.
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter},
+    path::PathBuf,
+};
+
+/// Returns the directory of the files of the game, or None if the home of the user can't be found.
+///
+/// It's `$TETRIS_DATA_DIR` if set, otherwise `tetris` in `$XDG_DATA_HOME`, `%APPDATA%` or `~/.local/share`.
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("TETRIS_DATA_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let base = env::var_os("XDG_DATA_HOME")
+        .or_else(|| env::var_os("APPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(base.join("tetris"))
+}
+
+/// Reads the file of the data directory, returns None if it doesn't exist or can't be read.
+pub fn load<T: DeserializeOwned>(file_name: &str) -> Option<T> {
+    let file = File::open(data_dir()?.join(file_name)).ok()?;
+    match serde_json::from_reader(BufReader::new(file)) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            println!("couldn't read {file_name}: {error}");
+            None
+        }
+    }
+}
+
+/// Writes the value in the file of the data directory, creating the directory and the subdirectory of the file if needed.
+pub fn save<T: Serialize>(file_name: &str, value: &T) -> io::Result<()> {
+    let dir =
+        data_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?;
+    let path = dir.join(file_name);
+    fs::create_dir_all(path.parent().unwrap_or(&dir))?;
+    let file = File::create(path)?;
+    serde_json::to_writer_pretty(BufWriter::new(file), value)?;
+    Ok(())
+}
+
+/// Writes the text in the file of the data directory, creating the directory if needed.
+pub fn save_text(file_name: &str, contents: &str) -> io::Result<()> {
+    let dir =
+        data_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?;
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(file_name), contents)
+}
+
+/// Renames the file of the data directory.
+pub fn rename(file_name: &str, new_name: &str) -> io::Result<()> {
+    let dir =
+        data_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?;
+    fs::rename(dir.join(file_name), dir.join(new_name))
+}
+
+/// Reads all the files of a subdirectory of the data directory, sorted by name.
+///
+/// The files that can't be read are left out, and there are none if the subdirectory doesn't exist.
+pub fn load_all<T: DeserializeOwned>(dir_name: &str) -> Vec<T> {
+    let Some(dir) = data_dir().map(|dir| dir.join(dir_name)) else {
+        return vec![];
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return vec![];
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let file = File::open(&path).ok()?;
+            match serde_json::from_reader(BufReader::new(file)) {
+                Ok(value) => Some(value),
+                Err(error) => {
+                    println!("couldn't read {}: {error}", path.display());
+                    None
+                }
+            }
+        })
+        .collect()
+}